@@ -50,6 +50,15 @@ client = create_client(url=db_url, auth_token=auth_token)
 # Turn on FK enforcement for THIS connection (safe; no data change)
 await client.execute("PRAGMA foreign_keys = ON;")
 
+# Add a column to an existing table (databases created by an earlier version of this script).
+# Returns True if the column was added, so callers can backfill it.
+async def add_column_if_missing(table: str, column: str, definition: str) -> bool:
+    rs = await client.execute(f"PRAGMA table_info({table})")
+    if column in [row["name"] for row in rs.rows]:
+        return False
+    await client.execute(f"ALTER TABLE {table} ADD COLUMN {column} {definition}")
+    return True
+
 # create the Product table
 create_table_sql = """
 CREATE TABLE IF NOT EXISTS Product (
@@ -61,6 +70,32 @@ CREATE TABLE IF NOT EXISTS Product (
 """
 await client.execute(create_table_sql)
 
+# Default purchase cost per unit, used for lots received without a cost
+await add_column_if_missing("Product", "cost", "INTEGER")
+
+# USANA point values per unit (PV / BV), as currently in effect
+await add_column_if_missing("Product", "pv", "REAL")
+await add_column_if_missing("Product", "bv", "REAL")
+
+# Days one unit lasts a customer (e.g. 28 or 30), for refill reminders
+await add_column_if_missing("Product", "consumption_days", "INTEGER")
+
+# Point value history: a row applies from effective_date until the next row
+create_product_point_history = """
+CREATE TABLE IF NOT EXISTS ProductPointHistory (
+  name           TEXT NOT NULL,
+  effective_date TEXT NOT NULL,  -- YYYY-MM-DD
+  pv             REAL,
+  bv             REAL,
+
+  PRIMARY KEY (name, effective_date),
+  FOREIGN KEY (name) REFERENCES Product(name)
+    ON UPDATE CASCADE
+    ON DELETE CASCADE
+);
+"""
+await client.execute(create_product_point_history)
+
 # Create the Stock table if it doesn't exist
 stock_sql = """
 CREATE TABLE IF NOT EXISTS Stock (
@@ -78,6 +113,38 @@ await client.execute(stock_sql)
   ## make sure that the pair (name, expiry) is unique in this table.
 await client.execute("CREATE UNIQUE INDEX IF NOT EXISTS stock_name_expiry_uq ON Stock(name, expiry);")
 
+# Stock receipts: one row per add_stock line, with the unit purchase cost paid
+create_stock_receipt = """
+CREATE TABLE IF NOT EXISTS StockReceipt (
+  id        TEXT PRIMARY KEY NOT NULL,  -- e.g. UUID
+  date      TEXT NOT NULL,              -- YYYY-MM-DD
+  name      TEXT NOT NULL,
+  expiry    TEXT NOT NULL,
+  quantity  INTEGER NOT NULL CHECK(quantity > 0),
+  unit_cost INTEGER,                    -- NULL = unknown
+
+  FOREIGN KEY (name) REFERENCES Product(name)
+    ON UPDATE CASCADE
+    ON DELETE RESTRICT
+);
+"""
+await client.execute(create_stock_receipt)
+await client.execute("CREATE INDEX IF NOT EXISTS idx_stockreceipt_lot ON StockReceipt(name, expiry)")
+
+# Unit cost per lot: quantity-weighted average of its costed receipts
+await client.execute("DROP VIEW IF EXISTS LotCost")
+create_lot_cost = """
+CREATE VIEW LotCost AS
+SELECT
+  name,
+  expiry,
+  CAST(ROUND(SUM(quantity * unit_cost) * 1.0 / SUM(quantity)) AS INTEGER) AS unit_cost
+FROM StockReceipt
+WHERE unit_cost IS NOT NULL
+GROUP BY name, expiry;
+"""
+await client.execute(create_lot_cost)
+
 # Sales Tracking
 
 # Create the SalesHeader table
@@ -85,7 +152,12 @@ create_sales_header = """
 CREATE TABLE IF NOT EXISTS SalesHeader (
   id   TEXT PRIMARY KEY NOT NULL,  -- e.g. UUID
   date TEXT NOT NULL,              -- YYYY-MM-DD
-  note TEXT                        -- optional
+  note TEXT,                       -- optional
+  customer TEXT,                   -- optional
+  payment_method TEXT,             -- optional, e.g. cash / bank transfer
+  amount_paid INTEGER NOT NULL DEFAULT 0,                   -- SUM(SalesPayment.amount)
+  payment_status TEXT NOT NULL DEFAULT 'unpaid'
+    CHECK(payment_status IN ('unpaid','partial','paid'))   -- maintained by the app
 );
 """
 await client.execute(create_sales_header)
@@ -98,6 +170,7 @@ CREATE TABLE IF NOT EXISTS SalesItem (
   product_name TEXT NOT NULL,
   quantity     INTEGER NOT NULL CHECK(quantity > 0),
   expiry       TEXT NOT NULL,
+  price        INTEGER,                    -- unit price at time of sale; NULL = Product.price
 
   FOREIGN KEY (sale_id)      REFERENCES SalesHeader(id) ON DELETE CASCADE,
   FOREIGN KEY (product_name) REFERENCES Product(name)
@@ -105,6 +178,10 @@ CREATE TABLE IF NOT EXISTS SalesItem (
 """
 await client.execute(create_sales_item)
 
+## Columns added after the first release
+await add_column_if_missing("SalesHeader", "customer", "TEXT")
+await add_column_if_missing("SalesItem", "price", "INTEGER")
+
 # Helpful indexes to speed up queries
 sales_indexes = [
     "CREATE INDEX IF NOT EXISTS idx_salesheader_date    ON SalesHeader(date)",
@@ -126,6 +203,189 @@ JOIN SalesHeader h ON h.id = i.sale_id;
 """
 await client.execute(create_sales_ledger)
 
+# Customer returns
+
+## Return header: one return against one original sale
+create_sales_return_header = """
+CREATE TABLE IF NOT EXISTS SalesReturnHeader (
+  id      TEXT PRIMARY KEY NOT NULL,  -- e.g. UUID
+  sale_id TEXT NOT NULL,              -- original sale
+  date    TEXT NOT NULL,              -- YYYY-MM-DD
+  note    TEXT,                       -- optional
+
+  FOREIGN KEY (sale_id) REFERENCES SalesHeader(id) ON DELETE CASCADE
+);
+"""
+await client.execute(create_sales_return_header)
+
+## Return item: which sale line came back, and whether it went back into stock
+create_sales_return_item = """
+CREATE TABLE IF NOT EXISTS SalesReturnItem (
+  id           TEXT PRIMARY KEY NOT NULL,  -- e.g. UUID
+  return_id    TEXT NOT NULL,
+  sale_item_id TEXT NOT NULL,              -- original SalesItem line (no FK: sale lines are rewritten on edit)
+  product_name TEXT NOT NULL,
+  expiry       TEXT NOT NULL,              -- lot the goods were sold from
+  quantity     INTEGER NOT NULL CHECK(quantity > 0),
+  damaged      INTEGER NOT NULL DEFAULT 0, -- 1 = written off instead of restocked
+  refund       INTEGER NOT NULL DEFAULT 0, -- amount refunded for this line
+
+  FOREIGN KEY (return_id)    REFERENCES SalesReturnHeader(id) ON DELETE CASCADE,
+  FOREIGN KEY (product_name) REFERENCES Product(name)
+);
+"""
+await client.execute(create_sales_return_item)
+
+await client.batch([
+    "CREATE INDEX IF NOT EXISTS idx_salesreturnheader_sale_id ON SalesReturnHeader(sale_id)",
+    "CREATE INDEX IF NOT EXISTS idx_salesreturnheader_date    ON SalesReturnHeader(date)",
+    "CREATE INDEX IF NOT EXISTS idx_salesreturnitem_return_id ON SalesReturnItem(return_id)",
+])
+
+## Revenue per line: sales are positive, returns are negative on the return date.
+## Views hold no data, so it is dropped and recreated to pick up changes.
+await client.execute("DROP VIEW IF EXISTS SalesRevenueLedger")
+create_sales_revenue_ledger = """
+CREATE VIEW SalesRevenueLedger AS
+SELECT
+  h.id           AS sale_id,
+  h.date         AS date,
+  i.product_name AS product_name,
+  i.quantity     AS quantity,
+  i.quantity * COALESCE(i.price, p.price, 0) AS amount
+FROM SalesItem i
+JOIN SalesHeader h ON h.id = i.sale_id
+LEFT JOIN Product p ON p.name = i.product_name
+UNION ALL
+SELECT
+  r.sale_id       AS sale_id,
+  r.date          AS date,
+  ri.product_name AS product_name,
+  -ri.quantity    AS quantity,
+  -ri.refund      AS amount
+FROM SalesReturnItem ri
+JOIN SalesReturnHeader r ON r.id = ri.return_id;
+"""
+await client.execute(create_sales_revenue_ledger)
+
+## Points per line, valued at the point values in effect on the sale date.
+## Returns take the points back on the return date, like SalesRevenueLedger.
+## Sales before the first history row use that first row; products without
+## any history use their current values.
+await client.execute("DROP VIEW IF EXISTS SalesPointsLedger")
+create_sales_points_ledger = """
+CREATE VIEW SalesPointsLedger AS
+SELECT
+  l.sale_id      AS sale_id,
+  l.date         AS date,
+  l.product_name AS product_name,
+  l.quantity     AS quantity,
+  l.quantity * COALESCE(ph.pv, p.pv, 0) AS pv,
+  l.quantity * COALESCE(ph.bv, p.bv, 0) AS bv
+FROM (
+  SELECT h.id AS sale_id, h.date AS date, h.date AS sale_date,
+         i.product_name AS product_name, i.quantity AS quantity
+  FROM SalesItem i
+  JOIN SalesHeader h ON h.id = i.sale_id
+  UNION ALL
+  SELECT r.sale_id, r.date, h.date,
+         ri.product_name, -ri.quantity
+  FROM SalesReturnItem ri
+  JOIN SalesReturnHeader r ON r.id = ri.return_id
+  JOIN SalesHeader h ON h.id = r.sale_id
+) l
+LEFT JOIN Product p ON p.name = l.product_name
+LEFT JOIN ProductPointHistory ph
+  ON ph.name = l.product_name
+ AND ph.effective_date = COALESCE(
+   (SELECT MAX(x.effective_date) FROM ProductPointHistory x
+    WHERE x.name = l.product_name AND x.effective_date <= l.sale_date),
+   (SELECT MIN(x.effective_date) FROM ProductPointHistory x
+    WHERE x.name = l.product_name)
+ );
+"""
+await client.execute(create_sales_points_ledger)
+
+# Payments
+
+create_sales_payment = """
+CREATE TABLE IF NOT EXISTS SalesPayment (
+  id      TEXT PRIMARY KEY NOT NULL,  -- e.g. UUID
+  sale_id TEXT NOT NULL,
+  date    TEXT NOT NULL,              -- YYYY-MM-DD
+  amount  INTEGER NOT NULL CHECK(amount > 0),
+  method  TEXT,                       -- optional
+  note    TEXT,                       -- optional
+
+  FOREIGN KEY (sale_id) REFERENCES SalesHeader(id) ON DELETE CASCADE
+);
+"""
+await client.execute(create_sales_payment)
+await client.execute("CREATE INDEX IF NOT EXISTS idx_salespayment_sale_id ON SalesPayment(sale_id)")
+
+await add_column_if_missing("SalesHeader", "payment_method", "TEXT")
+await add_column_if_missing(
+    "SalesHeader", "payment_status",
+    "TEXT NOT NULL DEFAULT 'unpaid' CHECK(payment_status IN ('unpaid','partial','paid'))"
+)
+if await add_column_if_missing("SalesHeader", "amount_paid", "INTEGER NOT NULL DEFAULT 0"):
+    # Sales recorded before payment tracking are treated as fully paid
+    await client.execute("""
+    INSERT INTO SalesPayment (id, sale_id, date, amount, note)
+    SELECT lower(hex(randomblob(16))), h.id, h.date, t.total, '启用付款记录前的销售'
+    FROM SalesHeader h
+    JOIN (SELECT sale_id, SUM(amount) AS total FROM SalesRevenueLedger GROUP BY sale_id) t
+      ON t.sale_id = h.id
+    WHERE t.total > 0
+    """)
+    await client.execute("""
+    UPDATE SalesHeader
+    SET amount_paid = (SELECT COALESCE(SUM(amount), 0) FROM SalesPayment WHERE sale_id = SalesHeader.id),
+        payment_status = 'paid'
+    """)
+
+# Recurring sales (autoship): templates turned into real sales when due
+
+create_recurring_sale = """
+CREATE TABLE IF NOT EXISTS RecurringSale (
+  id             TEXT PRIMARY KEY NOT NULL,  -- e.g. UUID
+  customer       TEXT NOT NULL,
+  interval_unit  TEXT NOT NULL CHECK(interval_unit IN ('day','week','month')),
+  interval_count INTEGER NOT NULL CHECK(interval_count > 0),
+  next_date      TEXT NOT NULL,              -- YYYY-MM-DD, next order due
+  payment_method TEXT,                       -- optional, copied onto the sale
+  note           TEXT,                       -- optional, copied onto the sale
+  active         INTEGER NOT NULL DEFAULT 1, -- 0 = paused
+  last_sale_id   TEXT                        -- last sale generated (no FK: sales can be deleted)
+);
+"""
+await client.execute(create_recurring_sale)
+# day of month monthly templates fall on, so a short month does not pull later dates forward
+await add_column_if_missing("RecurringSale", "anchor_day", "INTEGER")
+# status of the generated sales; autoship customers usually pay later, so NULL = unpaid
+await add_column_if_missing("RecurringSale", "payment_status", "TEXT")
+
+create_recurring_sale_item = """
+CREATE TABLE IF NOT EXISTS RecurringSaleItem (
+  id           TEXT PRIMARY KEY NOT NULL,  -- e.g. UUID
+  recurring_id TEXT NOT NULL,
+  product_name TEXT NOT NULL,
+  quantity     INTEGER NOT NULL CHECK(quantity > 0),
+  price        INTEGER,                    -- agreed unit price; NULL = Product.price when generated
+
+  FOREIGN KEY (recurring_id) REFERENCES RecurringSale(id) ON DELETE CASCADE,
+  FOREIGN KEY (product_name) REFERENCES Product(name)
+    ON UPDATE CASCADE
+    ON DELETE RESTRICT
+);
+"""
+await client.execute(create_recurring_sale_item)
+
+await client.batch([
+    "CREATE INDEX IF NOT EXISTS idx_recurringsale_next_date      ON RecurringSale(next_date)",
+    "CREATE INDEX IF NOT EXISTS idx_recurringsaleitem_recurring_id ON RecurringSaleItem(recurring_id)",
+])
+
 # Borrowing / Lending
 
 ## Header table
@@ -165,6 +425,142 @@ stmts = [
 ]
 await client.batch(stmts)
 
+## Stock effect of each loan per lot, so edits and deletes can adjust or undo it.
+## Loans recorded before this table (or without adjusting stock) have no rows.
+create_loan_stock_move = """
+CREATE TABLE IF NOT EXISTS LoanStockMove (
+  id           TEXT PRIMARY KEY NOT NULL,  -- e.g. UUID
+  loan_id      TEXT NOT NULL,
+  product_name TEXT NOT NULL,
+  expiry       TEXT NOT NULL,              -- lot touched
+  delta        INTEGER NOT NULL,           -- signed change applied to Stock.quantity
+
+  FOREIGN KEY (loan_id)      REFERENCES LoanHeader(id) ON DELETE CASCADE,
+  FOREIGN KEY (product_name) REFERENCES Product(name)
+    ON UPDATE CASCADE
+    ON DELETE RESTRICT
+);
+"""
+await client.execute(create_loan_stock_move)
+await client.execute("CREATE INDEX IF NOT EXISTS idx_loanstockmove_loan_id ON LoanStockMove(loan_id)")
+
+## Whether a loan's stock effect is in LoanStockMove: 1 = yes, 0 = recorded without
+## adjusting stock, NULL = recorded before stock moves were kept (edits cannot redo them)
+if await add_column_if_missing("LoanHeader", "stock_tracked", "INTEGER"):
+    await client.execute("""
+    UPDATE LoanHeader SET stock_tracked = 1
+    WHERE EXISTS (SELECT 1 FROM LoanStockMove m WHERE m.loan_id = LoanHeader.id)
+    """)
+
+## People and companies we lend to or borrow from, with contact details.
+## LoanHeader.counterparty keeps the name for display; counterparty_id is the reference.
+create_counterparty = """
+CREATE TABLE IF NOT EXISTS Counterparty (
+  id       TEXT PRIMARY KEY NOT NULL,  -- e.g. UUID
+  name     TEXT NOT NULL UNIQUE,
+  phone    TEXT,
+  wechat   TEXT,
+  address  TEXT,
+  note     TEXT
+);
+"""
+await client.execute(create_counterparty)
+
+if await add_column_if_missing("LoanHeader", "counterparty_id", "TEXT REFERENCES Counterparty(id)"):
+    # One counterparty per distinct name already used on loans
+    await client.execute("""
+    INSERT OR IGNORE INTO Counterparty (id, name)
+    SELECT lower(hex(randomblob(16))), counterparty FROM LoanHeader GROUP BY counterparty
+    """)
+    await client.execute("""
+    UPDATE LoanHeader
+    SET counterparty_id = (SELECT c.id FROM Counterparty c WHERE c.name = LoanHeader.counterparty)
+    """)
+await client.execute("CREATE INDEX IF NOT EXISTS idx_loanheader_counterparty_id ON LoanHeader(counterparty_id)")
+
+## Expected return date of a loan; optional
+await add_column_if_missing("LoanHeader", "due_date", "TEXT")
+await client.execute("CREATE INDEX IF NOT EXISTS idx_loanheader_due_date ON LoanHeader(due_date)")
+
+## Lot of each loan line; NULL for lines recorded before it was stored
+if await add_column_if_missing("LoanItem", "expiry", "TEXT"):
+    # Recover it where the loan moved a single lot of the product
+    await client.execute("""
+    UPDATE LoanItem
+    SET expiry = (
+      SELECT MIN(m.expiry) FROM LoanStockMove m
+      WHERE m.loan_id = LoanItem.loan_id AND m.product_name = LoanItem.product_name
+    )
+    WHERE (
+      SELECT COUNT(DISTINCT m.expiry) FROM LoanStockMove m
+      WHERE m.loan_id = LoanItem.loan_id AND m.product_name = LoanItem.product_name
+    ) = 1
+    """)
+
+## Member price of the product when the loan line was recorded, for valuing
+## open loans at loan-date prices; NULL for older lines (valued at the current price)
+await add_column_if_missing("LoanItem", "unit_price", "INTEGER")
+
+## Which loan lines a return line settles, and how much of each. Optional:
+## return quantity without a match settles the oldest open loans first.
+create_loan_match = """
+CREATE TABLE IF NOT EXISTS LoanMatch (
+  id             TEXT PRIMARY KEY NOT NULL,  -- e.g. UUID
+  return_item_id TEXT NOT NULL,              -- LoanItem of a return_in / return_out
+  loan_item_id   TEXT NOT NULL,              -- LoanItem of the loan_out / loan_in it settles
+  quantity       INTEGER NOT NULL CHECK (quantity > 0),
+
+  FOREIGN KEY (return_item_id) REFERENCES LoanItem(id) ON DELETE CASCADE,
+  FOREIGN KEY (loan_item_id)   REFERENCES LoanItem(id) ON DELETE CASCADE
+);
+"""
+await client.execute(create_loan_match)
+stmts = [
+    "CREATE INDEX IF NOT EXISTS idx_loanmatch_return_item_id ON LoanMatch(return_item_id)",
+    "CREATE INDEX IF NOT EXISTS idx_loanmatch_loan_item_id   ON LoanMatch(loan_item_id)",
+]
+await client.batch(stmts)
+
+## Product swaps with a counterparty: we give some products and receive others
+## under one record, each side at an agreed unit value. The difference in value
+## stays open as a balance with the counterparty; stock moves when recorded.
+create_loan_exchange = """
+CREATE TABLE IF NOT EXISTS LoanExchange (
+  id              TEXT PRIMARY KEY NOT NULL,  -- e.g. UUID
+  date            TEXT NOT NULL,              -- YYYY-MM-DD
+  counterparty    TEXT NOT NULL,
+  counterparty_id TEXT REFERENCES Counterparty(id),
+  note            TEXT
+);
+"""
+await client.execute(create_loan_exchange)
+## Money received from (> 0) or paid to (< 0) the counterparty with an exchange; it
+## counts against the value balance, so a cash-only exchange settles a swap difference
+await add_column_if_missing("LoanExchange", "cash", "INTEGER NOT NULL DEFAULT 0")
+
+create_loan_exchange_item = """
+CREATE TABLE IF NOT EXISTS LoanExchangeItem (
+  id           TEXT PRIMARY KEY NOT NULL,  -- e.g. UUID
+  exchange_id  TEXT NOT NULL,
+  side         TEXT NOT NULL CHECK(side IN ('give','receive')),
+  product_name TEXT NOT NULL,
+  expiry       TEXT NOT NULL,              -- lot given or received
+  quantity     INTEGER NOT NULL CHECK(quantity > 0),
+  unit_value   INTEGER NOT NULL CHECK(unit_value >= 0),
+
+  FOREIGN KEY (exchange_id)  REFERENCES LoanExchange(id) ON DELETE CASCADE,
+  FOREIGN KEY (product_name) REFERENCES Product(name)
+    ON UPDATE CASCADE
+    ON DELETE RESTRICT
+);
+"""
+await client.execute(create_loan_exchange_item)
+stmts = [
+    "CREATE INDEX IF NOT EXISTS idx_loanexchange_counterparty_id  ON LoanExchange(counterparty_id)",
+    "CREATE INDEX IF NOT EXISTS idx_loanexchangeitem_exchange_id ON LoanExchangeItem(exchange_id)",
+]
+await client.batch(stmts)
+
 
 ## assign direction values to the LoanLedger view
 stmt = """
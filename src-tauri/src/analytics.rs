@@ -0,0 +1,940 @@
+use crate::db::{get_db_config, sql_quote};
+use chrono::{Datelike, Duration, Local, NaiveDate};
+use libsql_client::Client;
+use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet};
+use tokio::task;
+
+/// Upper bound on buckets per series, so a typo in the range can't hang the UI.
+const MAX_BUCKETS: usize = 5000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Granularity {
+    Day,
+    Week, // ISO weeks, Monday to Sunday
+    Month,
+    Quarter,
+    Year,
+}
+
+impl Granularity {
+    pub fn parse(s: &str) -> Result<Self, String> {
+        match s {
+            "day" => Ok(Self::Day),
+            "week" => Ok(Self::Week),
+            "month" => Ok(Self::Month),
+            "quarter" => Ok(Self::Quarter),
+            "year" => Ok(Self::Year),
+            other => Err(format!("不支持的时间粒度：{}", other)),
+        }
+    }
+
+    /// First day of the bucket containing `d`.
+    pub fn start_of(self, d: NaiveDate) -> NaiveDate {
+        match self {
+            Self::Day => d,
+            Self::Week => d - Duration::days(d.weekday().num_days_from_monday() as i64),
+            Self::Month => d.with_day(1).unwrap(),
+            Self::Quarter => {
+                NaiveDate::from_ymd_opt(d.year(), (d.month0() / 3) * 3 + 1, 1).unwrap()
+            }
+            Self::Year => NaiveDate::from_ymd_opt(d.year(), 1, 1).unwrap(),
+        }
+    }
+
+    /// First day of the bucket after the one starting at `start`.
+    pub fn next(self, start: NaiveDate) -> NaiveDate {
+        match self {
+            Self::Day => start + Duration::days(1),
+            Self::Week => start + Duration::days(7),
+            Self::Month => add_months(start, 1),
+            Self::Quarter => add_months(start, 3),
+            Self::Year => add_months(start, 12),
+        }
+    }
+
+    /// Display label of the bucket starting at `start`.
+    pub fn label(self, start: NaiveDate) -> String {
+        match self {
+            Self::Day => start.format("%Y-%m-%d").to_string(),
+            Self::Week => {
+                let w = start.iso_week();
+                format!("{}-W{:02}", w.year(), w.week())
+            }
+            Self::Month => start.format("%Y-%m").to_string(),
+            Self::Quarter => format!("{}-Q{}", start.year(), start.month0() / 3 + 1),
+            Self::Year => start.format("%Y").to_string(),
+        }
+    }
+}
+
+/// `d` moved by `months` calendar months, clamping the day to the target month.
+pub fn add_months(d: NaiveDate, months: i32) -> NaiveDate {
+    add_months_on_day(d, months, d.day())
+}
+
+/// The month `months` after `d`'s, on `day` or its last day if shorter.
+pub fn add_months_on_day(d: NaiveDate, months: i32, day: u32) -> NaiveDate {
+    let total = d.year() * 12 + d.month0() as i32 + months;
+    let (y, m0) = (total.div_euclid(12), total.rem_euclid(12) as u32);
+    let mut day = day.clamp(1, 31);
+    loop {
+        if let Some(nd) = NaiveDate::from_ymd_opt(y, m0 + 1, day) {
+            return nd;
+        }
+        day -= 1;
+    }
+}
+
+pub fn parse_date(s: &str) -> Result<NaiveDate, String> {
+    NaiveDate::parse_from_str(s, "%Y-%m-%d").map_err(|_| format!("无效的日期：{}", s))
+}
+
+pub fn today() -> NaiveDate {
+    Local::now().date_naive()
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SalesBreakdown {
+    pub key: Option<String>, // product name or product type; None = no type set
+    pub value: i64,
+    pub quantity: i64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SalesBucket {
+    pub period: String, // "2025-03-14" | "2025-W11" | "2025-03" | "2025-Q1" | "2025"
+    pub start: String,  // first day of the bucket, clipped to the requested range
+    pub end: String,    // last day of the bucket, clipped to the requested range
+    pub value: i64,     // sales minus refunds
+    pub quantity: i64,  // sold minus returned
+    pub breakdown: Vec<SalesBreakdown>, // empty unless `group_by` is set
+}
+
+/// Net sales per (day, group) between two dates, from `SalesRevenueLedger`.
+/// `group_by` is None, "product" or "type"; the key is None when ungrouped.
+pub(crate) async fn daily_sales(
+    client: &Client,
+    from: NaiveDate,
+    to: NaiveDate,
+    group_by: Option<&str>,
+) -> Result<Vec<(NaiveDate, Option<String>, i64, i64)>, String> {
+    let key_expr = match group_by {
+        None => "NULL",
+        Some("product") => "l.product_name",
+        Some("type") => "p.type",
+        Some(other) => return Err(format!("不支持的分组方式：{}", other)),
+    };
+    let sql = format!(
+        r#"
+        SELECT l.date AS date,
+               {key} AS group_key,
+               CAST(SUM(l.quantity) AS INTEGER) AS quantity,
+               CAST(SUM(l.amount) AS INTEGER) AS value
+        FROM SalesRevenueLedger l
+        LEFT JOIN Product p ON p.name = l.product_name
+        WHERE l.date >= '{from}' AND l.date <= '{to}'
+        GROUP BY l.date, group_key
+        "#,
+        key = key_expr,
+        from = sql_quote(&from.format("%Y-%m-%d").to_string()),
+        to = sql_quote(&to.format("%Y-%m-%d").to_string())
+    );
+
+    let result = client.execute(sql).await.map_err(|e| e.to_string())?;
+    let mut out = Vec::new();
+    for row in result.rows {
+        let date = row
+            .try_column::<&str>("date")
+            .map_err(|_| "Failed to get date".to_string())?;
+        let Ok(date) = parse_date(date) else {
+            continue; // malformed legacy row
+        };
+        let key = row
+            .try_column::<&str>("group_key")
+            .ok()
+            .map(|s| s.to_string());
+        let quantity = row.try_column::<i64>("quantity").unwrap_or(0);
+        let value = row.try_column::<i64>("value").unwrap_or(0);
+        out.push((date, key, quantity, value));
+    }
+    Ok(out)
+}
+
+/// Fold daily rows into zero-filled buckets covering `from..=to`.
+pub(crate) fn bucketize(
+    rows: &[(NaiveDate, Option<String>, i64, i64)],
+    from: NaiveDate,
+    to: NaiveDate,
+    granularity: Granularity,
+    grouped: bool,
+) -> Result<Vec<SalesBucket>, String> {
+    let keys: BTreeSet<Option<String>> = rows.iter().map(|r| r.1.clone()).collect();
+
+    let mut buckets: Vec<SalesBucket> = Vec::new();
+    let mut index: BTreeMap<NaiveDate, usize> = BTreeMap::new();
+    let mut start = granularity.start_of(from);
+    while start <= to {
+        if buckets.len() >= MAX_BUCKETS {
+            return Err(format!("时间范围过大：超过 {} 个区间", MAX_BUCKETS));
+        }
+        let next = granularity.next(start);
+        let end = (next - Duration::days(1)).min(to);
+        index.insert(start, buckets.len());
+        buckets.push(SalesBucket {
+            period: granularity.label(start),
+            start: start.max(from).format("%Y-%m-%d").to_string(),
+            end: end.format("%Y-%m-%d").to_string(),
+            value: 0,
+            quantity: 0,
+            breakdown: if grouped {
+                keys.iter()
+                    .map(|k| SalesBreakdown {
+                        key: k.clone(),
+                        value: 0,
+                        quantity: 0,
+                    })
+                    .collect()
+            } else {
+                Vec::new()
+            },
+        });
+        start = next;
+    }
+
+    for (date, key, quantity, value) in rows {
+        let Some(&i) = index.get(&granularity.start_of(*date)) else {
+            continue;
+        };
+        let b = &mut buckets[i];
+        b.value += value;
+        b.quantity += quantity;
+        if grouped {
+            if let Some(g) = b.breakdown.iter_mut().find(|g| &g.key == key) {
+                g.value += value;
+                g.quantity += quantity;
+            }
+        }
+    }
+
+    Ok(buckets)
+}
+
+/// Net sales (value and quantity) in zero-filled buckets between `date_from` and
+/// `date_to` (default today), optionally broken down by "product" or "type".
+#[tauri::command]
+pub async fn get_sales_series(
+    date_from: String,
+    date_to: Option<String>,
+    granularity: Option<String>,
+    group_by: Option<String>,
+) -> Result<Vec<SalesBucket>, String> {
+    let from = parse_date(&date_from)?;
+    let to = match date_to.as_deref() {
+        Some(s) if !s.is_empty() => parse_date(s)?,
+        _ => today(),
+    };
+    if from > to {
+        return Err("开始日期不能晚于结束日期。".into());
+    }
+    let granularity = Granularity::parse(granularity.as_deref().unwrap_or("month"))?;
+
+    task::spawn_blocking(move || {
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        rt.block_on(async move {
+            let config = get_db_config().await.map_err(|e| e.to_string())?;
+            let client = Client::from_config(config)
+                .await
+                .map_err(|e| e.to_string())?;
+
+            let rows = daily_sales(&client, from, to, group_by.as_deref()).await?;
+            bucketize(&rows, from, to, granularity, group_by.is_some())
+        })
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PeriodRange {
+    pub from: String, // "YYYY-MM-DD", inclusive
+    pub to: String,   // "YYYY-MM-DD", inclusive
+}
+
+#[derive(Debug, Default, Clone, Copy, Serialize)]
+pub struct SalesFigures {
+    pub value: i64,
+    pub quantity: i64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SalesChange {
+    pub value: i64,
+    pub quantity: i64,
+    pub value_pct: Option<f64>, // None when the base period is 0
+    pub quantity_pct: Option<f64>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ComparisonLine {
+    pub key: Option<String>, // product name or type; None = total or no type set
+    pub current: SalesFigures,
+    pub previous: SalesFigures,
+    pub last_year: SalesFigures,
+    pub vs_previous: SalesChange,
+    pub vs_last_year: SalesChange,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SalesComparison {
+    pub current: PeriodRange,
+    pub previous: PeriodRange,
+    pub last_year: PeriodRange,
+    pub total: ComparisonLine,
+    pub products: Vec<ComparisonLine>,
+    pub types: Vec<ComparisonLine>,
+}
+
+/// Percentage change from `base` to `now`, one decimal; None when `base` is 0.
+pub fn pct_change(now: i64, base: i64) -> Option<f64> {
+    if base == 0 {
+        return None;
+    }
+    Some(((now - base) as f64 * 1000.0 / base.abs() as f64).round() / 10.0)
+}
+
+fn change(now: SalesFigures, base: SalesFigures) -> SalesChange {
+    SalesChange {
+        value: now.value - base.value,
+        quantity: now.quantity - base.quantity,
+        value_pct: pct_change(now.value, base.value),
+        quantity_pct: pct_change(now.quantity, base.quantity),
+    }
+}
+
+fn comparison_line(key: Option<String>, figs: [SalesFigures; 3]) -> ComparisonLine {
+    ComparisonLine {
+        key,
+        current: figs[0],
+        previous: figs[1],
+        last_year: figs[2],
+        vs_previous: change(figs[0], figs[1]),
+        vs_last_year: change(figs[0], figs[2]),
+    }
+}
+
+fn range(from: NaiveDate, to: NaiveDate) -> PeriodRange {
+    PeriodRange {
+        from: from.format("%Y-%m-%d").to_string(),
+        to: to.format("%Y-%m-%d").to_string(),
+    }
+}
+
+/// Current, previous and same-period-last-year ranges for a comparison.
+/// Calendar periods run from their first day up to `as_of`, and the previous
+/// period covers the same span, like month-to-date vs last month.
+pub(crate) fn comparison_ranges(
+    period: &str,
+    as_of: NaiveDate,
+    custom: Option<(NaiveDate, NaiveDate)>,
+) -> Result<[(NaiveDate, NaiveDate); 3], String> {
+    let shift = |(f, t): (NaiveDate, NaiveDate), months: i32| {
+        (add_months(f, months), add_months(t, months))
+    };
+    let current;
+    let previous;
+    match period {
+        "week" => {
+            current = (Granularity::Week.start_of(as_of), as_of);
+            previous = (current.0 - Duration::days(7), current.1 - Duration::days(7));
+        }
+        "month" => {
+            current = (Granularity::Month.start_of(as_of), as_of);
+            previous = shift(current, -1);
+        }
+        "quarter" => {
+            current = (Granularity::Quarter.start_of(as_of), as_of);
+            previous = shift(current, -3);
+        }
+        "ytd" => {
+            current = (Granularity::Year.start_of(as_of), as_of);
+            previous = shift(current, -12);
+        }
+        "custom" => {
+            let (f, t) = custom.ok_or("自定义区间需要开始和结束日期。")?;
+            if f > t {
+                return Err("开始日期不能晚于结束日期。".into());
+            }
+            let days = (t - f).num_days() + 1;
+            current = (f, t);
+            previous = (f - Duration::days(days), t - Duration::days(days));
+        }
+        other => return Err(format!("不支持的比较周期：{}", other)),
+    }
+    Ok([current, previous, shift(current, -12)])
+}
+
+/// Sales of a period (week, month, quarter, ytd or custom) against the previous
+/// period and the same period last year, in total, per product and per type.
+#[tauri::command]
+pub async fn get_sales_comparison(
+    period: String,
+    as_of: Option<String>,
+    date_from: Option<String>,
+    date_to: Option<String>,
+) -> Result<SalesComparison, String> {
+    let as_of = match as_of.as_deref() {
+        Some(s) if !s.is_empty() => parse_date(s)?,
+        _ => today(),
+    };
+    let custom = match (date_from.as_deref(), date_to.as_deref()) {
+        (Some(f), Some(t)) if !f.is_empty() && !t.is_empty() => {
+            Some((parse_date(f)?, parse_date(t)?))
+        }
+        _ => None,
+    };
+    let ranges = comparison_ranges(&period, as_of, custom)?;
+
+    task::spawn_blocking(move || {
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        rt.block_on(async move {
+            let config = get_db_config().await.map_err(|e| e.to_string())?;
+            let client = Client::from_config(config)
+                .await
+                .map_err(|e| e.to_string())?;
+
+            let d = |x: NaiveDate| x.format("%Y-%m-%d").to_string();
+            let in_range = |i: usize| {
+                format!(
+                    "l.date BETWEEN '{}' AND '{}'",
+                    d(ranges[i].0),
+                    d(ranges[i].1)
+                )
+            };
+            let sums = (0..3)
+                .map(|i| {
+                    format!(
+                        "CAST(SUM(CASE WHEN {c} THEN l.amount ELSE 0 END) AS INTEGER) AS value{i},
+                         CAST(SUM(CASE WHEN {c} THEN l.quantity ELSE 0 END) AS INTEGER) AS quantity{i}",
+                        c = in_range(i),
+                        i = i
+                    )
+                })
+                .collect::<Vec<_>>()
+                .join(",\n");
+
+            let sql = format!(
+                r#"
+                SELECT l.product_name AS product_name, p.type AS ptype,
+                       {sums}
+                FROM SalesRevenueLedger l
+                LEFT JOIN Product p ON p.name = l.product_name
+                WHERE ({c0}) OR ({c1}) OR ({c2})
+                GROUP BY l.product_name
+                ORDER BY value0 DESC, l.product_name
+                "#,
+                sums = sums,
+                c0 = in_range(0),
+                c1 = in_range(1),
+                c2 = in_range(2)
+            );
+
+            let result = client.execute(sql).await.map_err(|e| e.to_string())?;
+
+            let mut total = [SalesFigures::default(); 3];
+            let mut types: BTreeMap<Option<String>, [SalesFigures; 3]> = BTreeMap::new();
+            let mut products = Vec::new();
+            for row in result.rows {
+                let name = row
+                    .try_column::<&str>("product_name")
+                    .map_err(|_| "Failed to get product_name".to_string())?
+                    .to_string();
+                let ptype = row.try_column::<&str>("ptype").ok().map(|s| s.to_string());
+
+                let mut figs = [SalesFigures::default(); 3];
+                for (i, f) in figs.iter_mut().enumerate() {
+                    f.value = row.try_column::<i64>(&format!("value{}", i)).unwrap_or(0);
+                    f.quantity = row
+                        .try_column::<i64>(&format!("quantity{}", i))
+                        .unwrap_or(0);
+                }
+
+                let by_type = types.entry(ptype).or_default();
+                for i in 0..3 {
+                    total[i].value += figs[i].value;
+                    total[i].quantity += figs[i].quantity;
+                    by_type[i].value += figs[i].value;
+                    by_type[i].quantity += figs[i].quantity;
+                }
+                products.push(comparison_line(Some(name), figs));
+            }
+
+            let mut types: Vec<ComparisonLine> = types
+                .into_iter()
+                .map(|(k, figs)| comparison_line(k, figs))
+                .collect();
+            types.sort_by_key(|l| std::cmp::Reverse(l.current.value));
+
+            Ok(SalesComparison {
+                current: range(ranges[0].0, ranges[0].1),
+                previous: range(ranges[1].0, ranges[1].1),
+                last_year: range(ranges[2].0, ranges[2].1),
+                total: comparison_line(None, total),
+                products,
+                types,
+            })
+        })
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+#[derive(Debug, Serialize)]
+pub struct RankingEntry {
+    pub rank: i64,
+    pub key: Option<String>, // product name or type; None = no type set
+    pub value: i64,
+    pub quantity: i64,
+    pub sales_count: i64,           // distinct sales containing it
+    pub value_share: f64,           // % of total value in the period
+    pub quantity_share: f64,        // % of total quantity in the period
+    pub previous: SalesFigures,     // same-length period right before
+    pub previous_rank: Option<i64>, // None = not sold in the previous period
+    pub vs_previous: SalesChange,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SalesRankings {
+    pub current: PeriodRange,
+    pub previous: PeriodRange,
+    pub total: SalesFigures,
+    pub total_sales_count: i64,
+    pub entries: Vec<RankingEntry>,
+}
+
+fn share(part: i64, total: i64) -> f64 {
+    if total == 0 {
+        return 0.0;
+    }
+    (part as f64 * 1000.0 / total as f64).round() / 10.0
+}
+
+/// Sales of one group in the ranked period and the one before.
+pub(crate) struct GroupSales {
+    pub key: Option<String>,
+    pub current: SalesFigures,
+    pub previous: SalesFigures,
+    pub sales_count: i64,
+    pub previous_sales_count: i64,
+}
+
+/// Rank `groups` by `sort_by` ("value", "quantity" or "sales_count"), best
+/// first and ties by key, keeping the top `limit`. Groups that sold nothing in
+/// the period are left out; shares are of the period total, which is returned
+/// alongside.
+pub(crate) fn rank_groups(
+    mut groups: Vec<GroupSales>,
+    sort_by: &str,
+    limit: Option<u32>,
+) -> (SalesFigures, Vec<RankingEntry>) {
+    let metric = |f: &SalesFigures, count: i64| match sort_by {
+        "quantity" => f.quantity,
+        "sales_count" => count,
+        _ => f.value,
+    };
+
+    // rank in the previous period, among what sold then
+    let mut before: Vec<&GroupSales> = groups
+        .iter()
+        .filter(|g| g.previous_sales_count > 0 || g.previous.value != 0 || g.previous.quantity != 0)
+        .collect();
+    before.sort_by(|a, b| {
+        metric(&b.previous, b.previous_sales_count)
+            .cmp(&metric(&a.previous, a.previous_sales_count))
+            .then_with(|| a.key.cmp(&b.key))
+    });
+    let previous_rank: BTreeMap<Option<String>, i64> = before
+        .iter()
+        .enumerate()
+        .map(|(i, g)| (g.key.clone(), i as i64 + 1))
+        .collect();
+
+    let mut total = SalesFigures::default();
+    for g in &groups {
+        total.value += g.current.value;
+        total.quantity += g.current.quantity;
+    }
+
+    groups.retain(|g| g.sales_count > 0 || g.current.value != 0 || g.current.quantity != 0);
+    groups.sort_by(|a, b| {
+        metric(&b.current, b.sales_count)
+            .cmp(&metric(&a.current, a.sales_count))
+            .then_with(|| a.key.cmp(&b.key))
+    });
+
+    let entries = groups
+        .into_iter()
+        .take(limit.map(|n| n as usize).unwrap_or(usize::MAX))
+        .enumerate()
+        .map(|(i, g)| RankingEntry {
+            rank: i as i64 + 1,
+            previous_rank: previous_rank.get(&g.key).copied(),
+            key: g.key,
+            value: g.current.value,
+            quantity: g.current.quantity,
+            sales_count: g.sales_count,
+            value_share: share(g.current.value, total.value),
+            quantity_share: share(g.current.quantity, total.quantity),
+            previous: g.previous,
+            vs_previous: change(g.current, g.previous),
+        })
+        .collect();
+    (total, entries)
+}
+
+/// Products (or product types) ranked by "value", "quantity" or "sales_count"
+/// between two dates, with share of total and change vs the previous period.
+#[tauri::command]
+pub async fn get_sales_rankings(
+    date_from: String,
+    date_to: Option<String>,
+    group_by: Option<String>,
+    sort_by: Option<String>,
+    limit: Option<u32>,
+) -> Result<SalesRankings, String> {
+    let from = parse_date(&date_from)?;
+    let to = match date_to.as_deref() {
+        Some(s) if !s.is_empty() => parse_date(s)?,
+        _ => today(),
+    };
+    let [current, previous, _] = comparison_ranges("custom", to, Some((from, to)))?;
+    let key_expr = match group_by.as_deref().unwrap_or("product") {
+        "product" => "l.product_name",
+        "type" => "p.type",
+        other => return Err(format!("不支持的分组方式：{}", other)),
+    };
+    let sort_by = sort_by.unwrap_or_else(|| "value".to_string());
+    if !matches!(sort_by.as_str(), "value" | "quantity" | "sales_count") {
+        return Err(format!("不支持的排序字段：{}", sort_by));
+    }
+
+    task::spawn_blocking(move || {
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        rt.block_on(async move {
+            let config = get_db_config().await.map_err(|e| e.to_string())?;
+            let client = Client::from_config(config)
+                .await
+                .map_err(|e| e.to_string())?;
+
+            let d = |x: NaiveDate| x.format("%Y-%m-%d").to_string();
+            let cur = format!("l.date BETWEEN '{}' AND '{}'", d(current.0), d(current.1));
+            let prev = format!("l.date BETWEEN '{}' AND '{}'", d(previous.0), d(previous.1));
+
+            // Returns are negative ledger rows: they lower value and quantity
+            // but a sale is only counted where it sold something.
+            let sql = format!(
+                r#"
+                SELECT {key} AS group_key,
+                       CAST(SUM(CASE WHEN {cur} THEN l.amount ELSE 0 END) AS INTEGER) AS value,
+                       CAST(SUM(CASE WHEN {cur} THEN l.quantity ELSE 0 END) AS INTEGER) AS quantity,
+                       COUNT(DISTINCT CASE WHEN {cur} AND l.quantity > 0 THEN l.sale_id END) AS sales_count,
+                       COUNT(DISTINCT CASE WHEN {prev} AND l.quantity > 0 THEN l.sale_id END) AS prev_sales_count,
+                       CAST(SUM(CASE WHEN {prev} THEN l.amount ELSE 0 END) AS INTEGER) AS prev_value,
+                       CAST(SUM(CASE WHEN {prev} THEN l.quantity ELSE 0 END) AS INTEGER) AS prev_quantity
+                FROM SalesRevenueLedger l
+                LEFT JOIN Product p ON p.name = l.product_name
+                WHERE ({cur}) OR ({prev})
+                GROUP BY group_key
+                "#,
+                key = key_expr,
+                cur = cur,
+                prev = prev
+            );
+
+            let result = client.execute(sql).await.map_err(|e| e.to_string())?;
+
+            let mut groups = Vec::new();
+            for row in result.rows {
+                groups.push(GroupSales {
+                    key: row
+                        .try_column::<&str>("group_key")
+                        .ok()
+                        .map(|s| s.to_string()),
+                    current: SalesFigures {
+                        value: row.try_column::<i64>("value").unwrap_or(0),
+                        quantity: row.try_column::<i64>("quantity").unwrap_or(0),
+                    },
+                    previous: SalesFigures {
+                        value: row.try_column::<i64>("prev_value").unwrap_or(0),
+                        quantity: row.try_column::<i64>("prev_quantity").unwrap_or(0),
+                    },
+                    sales_count: row.try_column::<i64>("sales_count").unwrap_or(0),
+                    previous_sales_count: row.try_column::<i64>("prev_sales_count").unwrap_or(0),
+                });
+            }
+            let (total, entries) = rank_groups(groups, &sort_by, limit);
+
+            // total distinct sales in the period, not the sum over groups
+            let rs = client
+                .execute(format!(
+                    "SELECT COUNT(DISTINCT l.sale_id) AS n FROM SalesRevenueLedger l
+                     WHERE {} AND l.quantity > 0;",
+                    cur
+                ))
+                .await
+                .map_err(|e| e.to_string())?;
+            let total_sales_count = rs
+                .rows
+                .first()
+                .and_then(|row| row.try_column::<i64>("n").ok())
+                .unwrap_or(0);
+
+            Ok(SalesRankings {
+                current: range(current.0, current.1),
+                previous: range(previous.0, previous.1),
+                total,
+                total_sales_count,
+                entries,
+            })
+        })
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    type DailyRow = (NaiveDate, Option<String>, i64, i64);
+
+    fn d(s: &str) -> NaiveDate {
+        parse_date(s).unwrap()
+    }
+
+    fn row(date: &str, key: Option<&str>, quantity: i64, value: i64) -> DailyRow {
+        (d(date), key.map(str::to_string), quantity, value)
+    }
+
+    #[test]
+    fn bucketize_clips_months_to_the_range() {
+        let rows = [
+            row("2024-01-31", None, 1, 10),
+            row("2024-02-29", None, 2, 20),
+            row("2024-03-01", None, 3, 30),
+        ];
+        let b = bucketize(
+            &rows,
+            d("2024-01-15"),
+            d("2024-03-10"),
+            Granularity::Month,
+            false,
+        )
+        .unwrap();
+        let periods: Vec<_> = b.iter().map(|b| b.period.as_str()).collect();
+        assert_eq!(periods, ["2024-01", "2024-02", "2024-03"]);
+        assert_eq!(
+            (b[0].start.as_str(), b[0].end.as_str()),
+            ("2024-01-15", "2024-01-31")
+        );
+        assert_eq!(
+            (b[1].start.as_str(), b[1].end.as_str()),
+            ("2024-02-01", "2024-02-29")
+        );
+        assert_eq!(
+            (b[2].start.as_str(), b[2].end.as_str()),
+            ("2024-03-01", "2024-03-10")
+        );
+        assert_eq!(b.iter().map(|b| b.value).collect::<Vec<_>>(), [10, 20, 30]);
+    }
+
+    #[test]
+    fn bucketize_zero_fills_groups() {
+        let rows = [
+            row("2025-01-06", Some("A"), 1, 5),
+            row("2025-01-14", Some("B"), 2, 8),
+        ];
+        let b = bucketize(
+            &rows,
+            d("2025-01-06"),
+            d("2025-01-19"),
+            Granularity::Week,
+            true,
+        )
+        .unwrap();
+        assert_eq!(b.len(), 2);
+        assert_eq!(b[0].period, "2025-W02");
+        let first: Vec<_> = b[0].breakdown.iter().map(|g| g.value).collect();
+        let second: Vec<_> = b[1].breakdown.iter().map(|g| g.value).collect();
+        assert_eq!(first, [5, 0]);
+        assert_eq!(second, [0, 8]);
+    }
+
+    #[test]
+    fn bucketize_empty_range() {
+        let rows = [row("2025-03-01", None, 1, 1)];
+        let b = bucketize(
+            &rows,
+            d("2025-03-02"),
+            d("2025-03-01"),
+            Granularity::Day,
+            false,
+        )
+        .unwrap();
+        assert!(b.is_empty());
+    }
+
+    #[test]
+    fn bucketize_rejects_huge_ranges() {
+        assert!(bucketize(
+            &[],
+            d("2000-01-01"),
+            d("2030-01-01"),
+            Granularity::Day,
+            false
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn add_months_keeps_the_anchor_day() {
+        let jan31 = d("2024-01-31");
+        let feb = add_months_on_day(jan31, 1, 31);
+        assert_eq!(feb, d("2024-02-29"));
+        // stepping from the clamped date keeps returning to the 31st
+        assert_eq!(add_months_on_day(feb, 1, 31), d("2024-03-31"));
+        assert_eq!(add_months(feb, 1), d("2024-03-29"));
+        assert_eq!(add_months_on_day(d("2023-01-31"), 1, 31), d("2023-02-28"));
+        assert_eq!(add_months_on_day(d("2024-02-29"), 12, 29), d("2025-02-28"));
+        assert_eq!(add_months_on_day(d("2025-01-15"), -1, 15), d("2024-12-15"));
+    }
+
+    #[test]
+    fn comparison_ranges_month_end() {
+        let [current, previous, last_year] =
+            comparison_ranges("month", d("2024-03-31"), None).unwrap();
+        assert_eq!(current, (d("2024-03-01"), d("2024-03-31")));
+        assert_eq!(previous, (d("2024-02-01"), d("2024-02-29")));
+        assert_eq!(last_year, (d("2023-03-01"), d("2023-03-31")));
+    }
+
+    #[test]
+    fn comparison_ranges_leap_day() {
+        let [current, _, last_year] = comparison_ranges("ytd", d("2024-02-29"), None).unwrap();
+        assert_eq!(current, (d("2024-01-01"), d("2024-02-29")));
+        assert_eq!(last_year, (d("2023-01-01"), d("2023-02-28")));
+    }
+
+    #[test]
+    fn comparison_ranges_custom() {
+        let [current, previous, _] = comparison_ranges(
+            "custom",
+            d("2025-01-01"),
+            Some((d("2025-01-10"), d("2025-01-19"))),
+        )
+        .unwrap();
+        assert_eq!(current, (d("2025-01-10"), d("2025-01-19")));
+        assert_eq!(previous, (d("2024-12-31"), d("2025-01-09")));
+        assert!(comparison_ranges("custom", d("2025-01-01"), None).is_err());
+        assert!(comparison_ranges(
+            "custom",
+            d("2025-01-01"),
+            Some((d("2025-01-02"), d("2025-01-01")))
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn pct_change_of_zero_base() {
+        assert_eq!(pct_change(5, 0), None);
+        assert_eq!(pct_change(150, 100), Some(50.0));
+        assert_eq!(pct_change(-50, -100), Some(50.0));
+    }
+
+    #[test]
+    fn comparison_line_changes() {
+        let figs = |value, quantity| SalesFigures { value, quantity };
+        let line = comparison_line(None, [figs(120, 12), figs(100, 0), figs(0, 3)]);
+        assert_eq!(line.vs_previous.value, 20);
+        assert_eq!(line.vs_previous.value_pct, Some(20.0));
+        assert_eq!(line.vs_previous.quantity_pct, None);
+        assert_eq!(line.vs_last_year.value_pct, None);
+        assert_eq!(line.vs_last_year.quantity, 9);
+        assert_eq!(line.vs_last_year.quantity_pct, Some(300.0));
+    }
+
+    fn group(key: &str, now: (i64, i64, i64), before: (i64, i64, i64)) -> GroupSales {
+        GroupSales {
+            key: Some(key.to_string()),
+            current: SalesFigures {
+                value: now.0,
+                quantity: now.1,
+            },
+            previous: SalesFigures {
+                value: before.0,
+                quantity: before.1,
+            },
+            sales_count: now.2,
+            previous_sales_count: before.2,
+        }
+    }
+
+    #[test]
+    fn rankings_share_and_trend() {
+        let groups = vec![
+            group("A", (300, 3, 2), (100, 1, 1)),
+            group("B", (600, 2, 1), (0, 0, 0)),
+            group("C", (100, 5, 4), (400, 8, 3)),
+        ];
+        let (total, entries) = rank_groups(groups, "value", None);
+        assert_eq!((total.value, total.quantity), (1000, 10));
+
+        let keys: Vec<_> = entries.iter().map(|e| e.key.as_deref().unwrap()).collect();
+        assert_eq!(keys, ["B", "A", "C"]);
+        assert_eq!(entries[0].value_share, 60.0);
+        assert_eq!(entries[1].quantity_share, 30.0);
+        // B did not sell before; C led the previous period
+        assert_eq!(entries[0].previous_rank, None);
+        assert_eq!(entries[2].previous_rank, Some(1));
+        assert_eq!(entries[1].vs_previous.value, 200);
+        assert_eq!(entries[1].vs_previous.value_pct, Some(200.0));
+        assert_eq!(entries[0].vs_previous.value_pct, None);
+        assert_eq!(entries[2].vs_previous.quantity_pct, Some(-37.5));
+    }
+
+    #[test]
+    fn rankings_sort_by_and_limit() {
+        let groups = vec![
+            group("A", (300, 3, 2), (0, 0, 0)),
+            group("B", (600, 2, 1), (0, 0, 0)),
+            group("C", (100, 5, 4), (0, 0, 0)),
+        ];
+        let (total, entries) = rank_groups(groups, "sales_count", Some(2));
+        let keys: Vec<_> = entries.iter().map(|e| e.key.as_deref().unwrap()).collect();
+        assert_eq!(keys, ["C", "A"]);
+        assert_eq!(entries[1].rank, 2);
+        // shares stay of the full total, not of the shown entries
+        assert_eq!(total.value, 1000);
+        assert_eq!(entries[0].value_share, 10.0);
+    }
+
+    #[test]
+    fn rankings_drop_groups_without_sales_and_break_ties_by_key() {
+        let groups = vec![
+            group("B", (50, 1, 1), (0, 0, 0)),
+            group("A", (50, 1, 1), (0, 0, 0)),
+            group("Z", (0, 0, 0), (70, 1, 1)),
+        ];
+        let (total, entries) = rank_groups(groups, "quantity", None);
+        let keys: Vec<_> = entries.iter().map(|e| e.key.as_deref().unwrap()).collect();
+        assert_eq!(keys, ["A", "B"]);
+        assert_eq!(total.value, 100);
+        assert!(entries.iter().all(|e| e.previous_rank.is_none()));
+    }
+
+    #[test]
+    fn share_of_empty_total() {
+        assert_eq!(share(5, 0), 0.0);
+        assert_eq!(share(1, 3), 33.3);
+    }
+}
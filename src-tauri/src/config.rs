@@ -19,6 +19,10 @@ pub struct Config {
     pub url: String,
     pub token: String,
     pub alert_period: u16,
+    #[serde(default)]
+    pub business_name: String, // printed at the top of receipts
+    #[serde(default)]
+    pub receipt_footer: String, // printed at the bottom of receipts
 }
 
 // Global, thread-safe, read-only once set
@@ -52,6 +56,8 @@ pub fn init_config(app: &App) -> Result<()> {
             url: "".into(),
             token: "".into(),
             alert_period: ALERT_PERIOD_DEFAULT,
+            business_name: "".into(),
+            receipt_footer: "".into(),
         }
     };
 
@@ -0,0 +1,355 @@
+use crate::db::{get_db_config, ignore_empty_baton_commit, sql_quote, to_sql_null_or_string};
+use crate::loan_match::{counterparty_pairs, rematch_pairs};
+use libsql_client::{Client, Transaction};
+use serde::{Deserialize, Serialize};
+use tokio::task;
+use uuid::Uuid;
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct Counterparty {
+    pub id: String, // UUID from frontend
+    pub name: String,
+    pub phone: Option<String>,
+    pub wechat: Option<String>,
+    pub address: Option<String>,
+    pub note: Option<String>,
+    #[serde(default)]
+    pub loan_count: i64, // loans and exchanges recorded with them; filled on read
+}
+
+/// Trimmed, non-empty counterparty name.
+fn clean_name(name: &str) -> Result<String, String> {
+    let name = name.trim();
+    if name.is_empty() {
+        return Err("请填写往来单位。".into());
+    }
+    Ok(name.to_string())
+}
+
+/// Id of the counterparty called `name`, registering it if it is new.
+pub async fn ensure_counterparty(tx: &Transaction<'_>, name: &str) -> Result<String, String> {
+    let name = clean_name(name)?;
+    let rs = tx
+        .execute(format!(
+            "SELECT id FROM Counterparty WHERE name = '{}'",
+            sql_quote(&name)
+        ))
+        .await
+        .map_err(|e| e.to_string())?;
+    if let Some(row) = rs.rows.first() {
+        return Ok(row
+            .try_column::<&str>("id")
+            .map_err(|e| e.to_string())?
+            .to_string());
+    }
+
+    let id = Uuid::new_v4().to_string();
+    tx.execute(format!(
+        "INSERT INTO Counterparty (id, name) VALUES ('{}', '{}');",
+        sql_quote(&id),
+        sql_quote(&name)
+    ))
+    .await
+    .map_err(|e| e.to_string())?;
+    Ok(id)
+}
+
+/// Name of the counterparty with `id`.
+async fn counterparty_name(tx: &Transaction<'_>, id: &str) -> Result<String, String> {
+    let rs = tx
+        .execute(format!(
+            "SELECT name FROM Counterparty WHERE id = '{}'",
+            sql_quote(id)
+        ))
+        .await
+        .map_err(|e| e.to_string())?;
+    let row = rs.rows.first().ok_or("往来单位不存在")?;
+    Ok(row
+        .try_column::<&str>("name")
+        .map_err(|e| e.to_string())?
+        .to_string())
+}
+
+#[tauri::command]
+pub async fn get_counterparties() -> Result<Vec<Counterparty>, String> {
+    task::spawn_blocking(move || {
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        rt.block_on(async move {
+            let config = get_db_config().await.map_err(|e| e.to_string())?;
+            let client = Client::from_config(config)
+                .await
+                .map_err(|e| e.to_string())?;
+
+            let sql = r#"
+                SELECT c.id, c.name, c.phone, c.wechat, c.address, c.note,
+                       (SELECT COUNT(*) FROM LoanHeader h WHERE h.counterparty_id = c.id)
+                         + (SELECT COUNT(*) FROM LoanExchange e WHERE e.counterparty_id = c.id)
+                           AS loan_count
+                FROM Counterparty c
+                ORDER BY c.name
+            "#;
+            let result = client.execute(sql).await.map_err(|e| e.to_string())?;
+
+            let text = |row: &libsql_client::Row, col: &str| {
+                row.try_column::<&str>(col).ok().map(|s| s.to_string())
+            };
+            let mut out = Vec::new();
+            for row in result.rows {
+                out.push(Counterparty {
+                    id: row
+                        .try_column::<&str>("id")
+                        .map_err(|e| e.to_string())?
+                        .to_string(),
+                    name: row
+                        .try_column::<&str>("name")
+                        .map_err(|e| e.to_string())?
+                        .to_string(),
+                    phone: text(&row, "phone"),
+                    wechat: text(&row, "wechat"),
+                    address: text(&row, "address"),
+                    note: text(&row, "note"),
+                    loan_count: row.try_column::<i64>("loan_count").unwrap_or(0),
+                });
+            }
+            Ok(out)
+        })
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+#[tauri::command]
+pub async fn create_counterparty(counterparty: Counterparty) -> Result<(), String> {
+    let name = clean_name(&counterparty.name)?;
+    task::spawn_blocking(move || {
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        rt.block_on(async move {
+            let config = get_db_config().await.map_err(|e| e.to_string())?;
+            let client = Client::from_config(config)
+                .await
+                .map_err(|e| e.to_string())?;
+
+            let exists = client
+                .execute(format!(
+                    "SELECT 1 FROM Counterparty WHERE name = '{}'",
+                    sql_quote(&name)
+                ))
+                .await
+                .map_err(|e| e.to_string())?;
+            if !exists.rows.is_empty() {
+                return Err(format!("往来单位已存在：{}", name));
+            }
+
+            client
+                .execute(format!(
+                    "INSERT INTO Counterparty (id, name, phone, wechat, address, note)
+                     VALUES ('{}', '{}', {}, {}, {}, {});",
+                    sql_quote(&counterparty.id),
+                    sql_quote(&name),
+                    to_sql_null_or_string(&counterparty.phone),
+                    to_sql_null_or_string(&counterparty.wechat),
+                    to_sql_null_or_string(&counterparty.address),
+                    to_sql_null_or_string(&counterparty.note)
+                ))
+                .await
+                .map_err(|e| e.to_string())?;
+            Ok(())
+        })
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+/// Update contact details; the name changes through `rename_counterparty`.
+#[tauri::command]
+pub async fn update_counterparty(counterparty: Counterparty) -> Result<(), String> {
+    task::spawn_blocking(move || {
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        rt.block_on(async move {
+            let config = get_db_config().await.map_err(|e| e.to_string())?;
+            let client = Client::from_config(config)
+                .await
+                .map_err(|e| e.to_string())?;
+
+            let res = client
+                .execute(format!(
+                    "UPDATE Counterparty
+                     SET phone = {}, wechat = {}, address = {}, note = {}
+                     WHERE id = '{}';",
+                    to_sql_null_or_string(&counterparty.phone),
+                    to_sql_null_or_string(&counterparty.wechat),
+                    to_sql_null_or_string(&counterparty.address),
+                    to_sql_null_or_string(&counterparty.note),
+                    sql_quote(&counterparty.id)
+                ))
+                .await
+                .map_err(|e| e.to_string())?;
+            if res.rows_affected == 0 {
+                return Err("往来单位不存在".into());
+            }
+            Ok(())
+        })
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+/// Rename a counterparty, along with the name on all their loans and exchanges. Renaming to
+/// the name of another counterparty is refused: merge them instead.
+#[tauri::command]
+pub async fn rename_counterparty(counterparty_id: String, new_name: String) -> Result<(), String> {
+    let new_name = clean_name(&new_name)?;
+    task::spawn_blocking(move || {
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        rt.block_on(async move {
+            let config = get_db_config().await.map_err(|e| e.to_string())?;
+            let client = Client::from_config(config)
+                .await
+                .map_err(|e| e.to_string())?;
+            let tx = client.transaction().await.map_err(|e| e.to_string())?;
+
+            counterparty_name(&tx, &counterparty_id).await?;
+            let taken = tx
+                .execute(format!(
+                    "SELECT 1 FROM Counterparty WHERE name = '{}' AND id <> '{}'",
+                    sql_quote(&new_name),
+                    sql_quote(&counterparty_id)
+                ))
+                .await
+                .map_err(|e| e.to_string())?;
+            if !taken.rows.is_empty() {
+                return Err(format!("往来单位已存在：{}，请使用合并。", new_name));
+            }
+
+            let id_q = sql_quote(&counterparty_id);
+            let name_q = sql_quote(&new_name);
+            tx.execute(format!(
+                "UPDATE Counterparty SET name = '{}' WHERE id = '{}';",
+                name_q, id_q
+            ))
+            .await
+            .map_err(|e| e.to_string())?;
+            for table in ["LoanHeader", "LoanExchange"] {
+                tx.execute(format!(
+                    "UPDATE {} SET counterparty = '{}' WHERE counterparty_id = '{}';",
+                    table, name_q, id_q
+                ))
+                .await
+                .map_err(|e| e.to_string())?;
+            }
+            // returns are matched by name; lines that now share it may settle each other
+            let pairs = counterparty_pairs(&tx, &new_name).await?;
+            rematch_pairs(&tx, &pairs).await?;
+
+            let res = tx.commit().await;
+            ignore_empty_baton_commit(res)?;
+            Ok(())
+        })
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+/// Move all loans and exchanges of `source_id` to `target_id` and remove `source_id`. Contact
+/// details the target lacks are taken from the source.
+#[tauri::command]
+pub async fn merge_counterparties(source_id: String, target_id: String) -> Result<(), String> {
+    if source_id == target_id {
+        return Err("不能与自身合并".into());
+    }
+    task::spawn_blocking(move || {
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        rt.block_on(async move {
+            let config = get_db_config().await.map_err(|e| e.to_string())?;
+            let client = Client::from_config(config)
+                .await
+                .map_err(|e| e.to_string())?;
+            let tx = client.transaction().await.map_err(|e| e.to_string())?;
+
+            counterparty_name(&tx, &source_id).await?;
+            let target_name = counterparty_name(&tx, &target_id).await?;
+            let src_q = sql_quote(&source_id);
+            let dst_q = sql_quote(&target_id);
+
+            for table in ["LoanHeader", "LoanExchange"] {
+                tx.execute(format!(
+                    "UPDATE {} SET counterparty_id = '{}', counterparty = '{}'
+                     WHERE counterparty_id = '{}';",
+                    table,
+                    dst_q,
+                    sql_quote(&target_name),
+                    src_q
+                ))
+                .await
+                .map_err(|e| e.to_string())?;
+            }
+            // the source's open returns may now settle the target's loans and vice versa
+            let pairs = counterparty_pairs(&tx, &target_name).await?;
+            rematch_pairs(&tx, &pairs).await?;
+
+            tx.execute(format!(
+                "UPDATE Counterparty
+                 SET phone   = COALESCE(phone,   (SELECT phone   FROM Counterparty WHERE id = '{src}')),
+                     wechat  = COALESCE(wechat,  (SELECT wechat  FROM Counterparty WHERE id = '{src}')),
+                     address = COALESCE(address, (SELECT address FROM Counterparty WHERE id = '{src}')),
+                     note    = COALESCE(note,    (SELECT note    FROM Counterparty WHERE id = '{src}'))
+                 WHERE id = '{dst}';",
+                src = src_q,
+                dst = dst_q
+            ))
+            .await
+            .map_err(|e| e.to_string())?;
+
+            tx.execute(format!("DELETE FROM Counterparty WHERE id = '{}';", src_q))
+                .await
+                .map_err(|e| e.to_string())?;
+
+            let res = tx.commit().await;
+            ignore_empty_baton_commit(res)?;
+            Ok(())
+        })
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+/// Delete a counterparty without loans or exchanges.
+#[tauri::command]
+pub async fn delete_counterparty(counterparty_id: String) -> Result<(), String> {
+    task::spawn_blocking(move || {
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        rt.block_on(async move {
+            let config = get_db_config().await.map_err(|e| e.to_string())?;
+            let client = Client::from_config(config)
+                .await
+                .map_err(|e| e.to_string())?;
+            let tx = client.transaction().await.map_err(|e| e.to_string())?;
+
+            let name = counterparty_name(&tx, &counterparty_id).await?;
+            let id_q = sql_quote(&counterparty_id);
+            let used = tx
+                .execute(format!(
+                    "SELECT 1 FROM LoanHeader WHERE counterparty_id = '{id}'
+                     UNION ALL
+                     SELECT 1 FROM LoanExchange WHERE counterparty_id = '{id}'
+                     LIMIT 1",
+                    id = id_q
+                ))
+                .await
+                .map_err(|e| e.to_string())?;
+            if !used.rows.is_empty() {
+                return Err(format!("仍有借货记录，无法删除：{}", name));
+            }
+
+            tx.execute(format!("DELETE FROM Counterparty WHERE id = '{}';", id_q))
+                .await
+                .map_err(|e| e.to_string())?;
+
+            let res = tx.commit().await;
+            ignore_empty_baton_commit(res)?;
+            Ok(())
+        })
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
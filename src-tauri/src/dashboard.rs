@@ -1,5 +1,6 @@
 use crate::config::get_alert_period;
 use crate::db::get_db_config;
+use crate::loan::{loan_values, loans_due, CounterpartyLoanValue};
 use libsql_client::Client;
 use serde::{Deserialize, Serialize};
 use tokio::task;
@@ -11,10 +12,24 @@ pub struct Config {
     pub expiring_soon_value: f64,
     pub expired_value: f64,
     pub net_loan_value: f64,
+    pub lent_out_value: f64,
+    pub borrowed_in_value: f64,
+    pub loan_valuation: String, // "current" | "recorded"
+    pub counterparty_loan_values: Vec<CounterpartyLoanValue>,
+    pub overdue_loan_count: usize,
 }
 
+/// `loan_valuation` picks the price open loans are valued at: "current"
+/// (default) or "recorded", the price when each line was entered or last
+/// edited ("loan_date" is still accepted for it). Exchange balances are
+/// included at their agreed value.
 #[tauri::command]
-pub async fn get_dashboard_summary() -> Result<Config, String> {
+pub async fn get_dashboard_summary(loan_valuation: Option<String>) -> Result<Config, String> {
+    let loan_valuation = match loan_valuation.as_deref() {
+        None | Some("current") => "current".to_string(),
+        Some("recorded" | "loan_date") => "recorded".to_string(),
+        Some(other) => return Err(format!("未知估值方式：{}", other)),
+    };
     task::spawn_blocking(move || {
         let rt = tokio::runtime::Runtime::new().unwrap();
         rt.block_on(async move {
@@ -24,22 +39,25 @@ pub async fn get_dashboard_summary() -> Result<Config, String> {
                 .await
                 .map_err(|e| e.to_string())?;
 
-            // get net loan value
-            let rs = client
-            .execute(
-                r#"
-                SELECT
-                    COALESCE(SUM(ll.quantity * ll.sign * COALESCE(p.price, 0)), 0) * 1.0
-                    AS net_loan_value
-                FROM LoanLedger ll
-                LEFT JOIN Product p ON p.name = ll.product_name;
-                "#
-            )
-            .await
-            .map_err(|e| e.to_string())?;
-            
-            let row = rs.rows.get(0).ok_or("No data")?;
-            let net_loan_value: f64 = row.try_column::<f64>("net_loan_value").unwrap_or(0.0);
+            // open loan and exchange values, lent out and borrowed in apart, per counterparty
+            let counterparty_loan_values =
+                loan_values(&client, loan_valuation == "recorded").await?;
+            let lent_out_value: f64 = counterparty_loan_values
+                .iter()
+                .map(|v| v.lent_out_value as f64)
+                .sum();
+            let borrowed_in_value: f64 = counterparty_loan_values
+                .iter()
+                .map(|v| v.borrowed_in_value as f64)
+                .sum();
+            let net_loan_value = lent_out_value - borrowed_in_value;
+
+            // open loan balances past their due date
+            let overdue_loan_count = loans_due(&client, 0)
+                .await?
+                .iter()
+                .filter(|d| d.days_left < 0)
+                .count();
 
             // Calculate total values by expiry status
             let sql = format!(
@@ -92,7 +110,12 @@ pub async fn get_dashboard_summary() -> Result<Config, String> {
                 total_sellable_value,
                 expiring_soon_value,
                 expired_value,
-                net_loan_value
+                net_loan_value,
+                lent_out_value,
+                borrowed_in_value,
+                loan_valuation,
+                counterparty_loan_values,
+                overdue_loan_count
             })
         })
     })
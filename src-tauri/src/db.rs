@@ -48,6 +48,16 @@ pub fn sql_quote(s: &str) -> String {
     s.replace('\'', "''")
 }
 
+/// Quote `s` for use inside a `LIKE '...' ESCAPE '\'` pattern, so `%` and `_`
+/// typed by the user match literally.
+pub fn sql_like_quote(s: &str) -> String {
+    sql_quote(
+        &s.replace('\\', "\\\\")
+            .replace('%', "\\%")
+            .replace('_', "\\_"),
+    )
+}
+
 pub fn to_sql_null_or_int(v: Option<i64>) -> String {
     match v {
         Some(n) => n.to_string(),
@@ -55,6 +65,13 @@ pub fn to_sql_null_or_int(v: Option<i64>) -> String {
     }
 }
 
+pub fn to_sql_null_or_real(v: Option<f64>) -> String {
+    match v {
+        Some(n) => n.to_string(),
+        None => "NULL".to_string(),
+    }
+}
+
 pub fn to_sql_null_or_blob_hex(base64_opt: &Option<String>) -> Result<String, String> {
     match base64_opt {
         None => Ok("NULL".to_string()),
@@ -0,0 +1,368 @@
+use crate::counterparty::ensure_counterparty;
+use crate::db::{
+    get_db_config, ignore_empty_baton_commit, sql_quote, to_sql_null_or_int, to_sql_null_or_string,
+};
+use crate::stock::{add_to_lot, apply_lot_delta};
+use libsql_client::Client;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use tokio::task;
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct ExchangeHeader {
+    pub id: String,   // UUID from frontend
+    pub date: String, // "YYYY-MM-DD"
+    pub counterparty: String,
+    pub note: Option<String>,
+    #[serde(default)]
+    pub cash: i64, // money they paid us (> 0) or we paid them (< 0) towards the balance
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct ExchangeItem {
+    pub id: String,   // UUID from frontend
+    pub side: String, // "give" | "receive"
+    pub product_name: String,
+    pub expiry: String,          // lot given or received
+    pub quantity: i64,           // > 0
+    pub unit_value: Option<i64>, // agreed value per unit; None = Product.price
+}
+
+#[derive(Debug, Serialize)]
+pub struct Exchange {
+    pub header: ExchangeHeader,
+    pub items: Vec<ExchangeItem>,
+    pub balance: i64, // value given minus value received and cash; > 0 they owe us
+}
+
+/// Stock change of an exchange line: given lots go out, received lots come in.
+fn side_delta(side: &str, quantity: i64) -> Result<i64, String> {
+    match side {
+        "give" => Ok(-quantity),
+        "receive" => Ok(quantity),
+        other => Err(format!("未知换货方向：{}", other)),
+    }
+}
+
+/// SQL for the balance exchange `alias` leaves: value given minus value
+/// received, less the cash that changed hands with it.
+pub fn exchange_value(alias: &str) -> String {
+    format!(
+        "(COALESCE((SELECT SUM(CASE i.side WHEN 'give' THEN 1 ELSE -1 END
+                               * i.quantity * i.unit_value)
+                     FROM LoanExchangeItem i WHERE i.exchange_id = {a}.id), 0) - {a}.cash)",
+        a = alias
+    )
+}
+
+/// Value balance of exchanges per counterparty, leaving out settled ones.
+pub async fn exchange_balances(client: &Client) -> Result<Vec<(String, i64)>, String> {
+    let sql = format!(
+        "SELECT e.counterparty, SUM({}) AS balance
+         FROM LoanExchange e
+         GROUP BY e.counterparty
+         HAVING balance <> 0
+         ORDER BY e.counterparty",
+        exchange_value("e")
+    );
+    let result = client.execute(sql).await.map_err(|e| e.to_string())?;
+
+    let mut out = Vec::new();
+    for row in result.rows {
+        out.push((
+            row.try_column::<&str>("counterparty")
+                .map_err(|e| e.to_string())?
+                .to_string(),
+            row.try_column::<i64>("balance")
+                .map_err(|e| e.to_string())?,
+        ));
+    }
+    Ok(out)
+}
+
+/// An exchange line from a `LoanExchangeItem` row.
+fn item_from_row(row: &libsql_client::Row) -> Result<ExchangeItem, String> {
+    let text = |col: &str| -> Result<String, String> {
+        Ok(row
+            .try_column::<&str>(col)
+            .map_err(|e| e.to_string())?
+            .to_string())
+    };
+    Ok(ExchangeItem {
+        id: text("id")?,
+        side: text("side")?,
+        product_name: text("product_name")?,
+        expiry: text("expiry")?,
+        quantity: row
+            .try_column::<i64>("quantity")
+            .map_err(|e| e.to_string())?,
+        unit_value: row.try_column::<i64>("unit_value").ok(),
+    })
+}
+
+/// Record a swap with one counterparty: the given lots leave stock and the
+/// received lots enter it in the same transaction. An exchange without items
+/// only records cash, settling (part of) the value balance.
+#[tauri::command]
+pub async fn create_exchange(
+    header: ExchangeHeader,
+    items: Vec<ExchangeItem>,
+) -> Result<(), String> {
+    if items.is_empty() {
+        if header.cash == 0 {
+            return Err("结清换货差额须填写金额".into());
+        }
+    } else if !items.iter().any(|it| it.side == "give")
+        || !items.iter().any(|it| it.side == "receive")
+    {
+        return Err("换货须同时包含换出和换入的产品".into());
+    }
+    for it in &items {
+        side_delta(&it.side, it.quantity)?;
+        if it.quantity <= 0 {
+            return Err(format!(
+                "数量必须为正数：{} ({})",
+                it.product_name, it.quantity
+            ));
+        }
+        if it.expiry.trim().is_empty() {
+            return Err(format!("必须提供到期日：{}", it.product_name));
+        }
+        if it.unit_value.is_some_and(|v| v < 0) {
+            return Err(format!("单价不能为负数：{}", it.product_name));
+        }
+    }
+
+    task::spawn_blocking(move || {
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        rt.block_on(async move {
+            let config = get_db_config().await.map_err(|e| e.to_string())?;
+            let client = Client::from_config(config)
+                .await
+                .map_err(|e| e.to_string())?;
+
+            // IMPORTANT: enable FKs
+            client
+                .execute("PRAGMA foreign_keys = ON;")
+                .await
+                .map_err(|e| e.to_string())?;
+
+            let tx = client.transaction().await.map_err(|e| e.to_string())?;
+
+            for it in &items {
+                let exists = tx
+                    .execute(format!(
+                        "SELECT 1 FROM Product WHERE name='{}' LIMIT 1;",
+                        sql_quote(&it.product_name)
+                    ))
+                    .await
+                    .map_err(|e| e.to_string())?;
+                if exists.rows.is_empty() {
+                    return Err(format!("产品不存在：{}", it.product_name));
+                }
+            }
+
+            let hdr_id_q = sql_quote(&header.id);
+            let counterparty_id = ensure_counterparty(&tx, &header.counterparty).await?;
+            tx.execute(format!(
+                "INSERT INTO LoanExchange (id, date, counterparty, counterparty_id, note, cash)
+                 VALUES ('{}', '{}', '{}', '{}', {}, {});",
+                hdr_id_q,
+                sql_quote(&header.date),
+                sql_quote(header.counterparty.trim()),
+                sql_quote(&counterparty_id),
+                to_sql_null_or_string(&header.note),
+                header.cash
+            ))
+            .await
+            .map_err(|e| e.to_string())?;
+
+            for it in &items {
+                let name_q = sql_quote(&it.product_name);
+                tx.execute(format!(
+                    "INSERT INTO LoanExchangeItem
+                       (id, exchange_id, side, product_name, expiry, quantity, unit_value)
+                     VALUES ('{}', '{}', '{}', '{}', '{}', {},
+                             COALESCE({}, (SELECT price FROM Product WHERE name = '{}'), 0));",
+                    sql_quote(&it.id),
+                    hdr_id_q,
+                    sql_quote(&it.side),
+                    name_q,
+                    sql_quote(&it.expiry),
+                    it.quantity,
+                    to_sql_null_or_int(it.unit_value),
+                    name_q
+                ))
+                .await
+                .map_err(|e| e.to_string())?;
+            }
+
+            // received lots first, so a lot on both sides never runs short midway
+            let mut moves: Vec<&ExchangeItem> = items.iter().collect();
+            moves.sort_by_key(|it| it.side != "receive");
+            for it in moves {
+                apply_lot_delta(
+                    &tx,
+                    &it.product_name,
+                    &it.expiry,
+                    side_delta(&it.side, it.quantity)?,
+                )
+                .await?;
+            }
+
+            let res = tx.commit().await;
+            ignore_empty_baton_commit(res)?;
+            Ok(())
+        })
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+/// Delete an exchange and undo its stock change; fails when a received lot
+/// no longer has the stock to give back.
+#[tauri::command]
+pub async fn delete_exchange(exchange_id: String) -> Result<(), String> {
+    task::spawn_blocking(move || {
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        rt.block_on(async move {
+            let config = get_db_config().await.map_err(|e| e.to_string())?;
+            let client = Client::from_config(config)
+                .await
+                .map_err(|e| e.to_string())?;
+
+            // IMPORTANT: enable FKs to ensure proper cascade behavior
+            client
+                .execute("PRAGMA foreign_keys = ON;")
+                .await
+                .map_err(|e| e.to_string())?;
+
+            let tx = client.transaction().await.map_err(|e| e.to_string())?;
+
+            // given lots come back before received lots go out
+            let rs = tx
+                .execute(format!(
+                    "SELECT id, side, product_name, expiry, quantity, unit_value
+                     FROM LoanExchangeItem WHERE exchange_id = '{}'",
+                    sql_quote(&exchange_id)
+                ))
+                .await
+                .map_err(|e| e.to_string())?;
+            let items = rs
+                .rows
+                .iter()
+                .map(item_from_row)
+                .collect::<Result<Vec<_>, _>>()?;
+            for it in items.iter().filter(|it| it.side == "give") {
+                add_to_lot(&tx, &it.product_name, &it.expiry, it.quantity).await?;
+            }
+            for it in items.iter().filter(|it| it.side == "receive") {
+                apply_lot_delta(&tx, &it.product_name, &it.expiry, -it.quantity).await?;
+            }
+
+            let id_q = sql_quote(&exchange_id);
+            tx.execute(format!(
+                "DELETE FROM LoanExchangeItem WHERE exchange_id = '{}';",
+                id_q
+            ))
+            .await
+            .map_err(|e| e.to_string())?;
+            let res = tx
+                .execute(format!("DELETE FROM LoanExchange WHERE id = '{}';", id_q))
+                .await
+                .map_err(|e| e.to_string())?;
+            if res.rows_affected == 0 {
+                return Err("换货记录不存在".into());
+            }
+
+            let res = tx.commit().await;
+            ignore_empty_baton_commit(res)?;
+            Ok(())
+        })
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+/// Exchanges, newest first, optionally with one counterparty only.
+#[tauri::command]
+pub async fn get_exchanges(counterparty: Option<String>) -> Result<Vec<Exchange>, String> {
+    task::spawn_blocking(move || {
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        rt.block_on(async move {
+            let config = get_db_config().await.map_err(|e| e.to_string())?;
+            let client = Client::from_config(config)
+                .await
+                .map_err(|e| e.to_string())?;
+
+            let filter = counterparty
+                .as_deref()
+                .map(|c| format!("WHERE e.counterparty = '{}'", sql_quote(c)))
+                .unwrap_or_default();
+            let items_rs = client
+                .execute(format!(
+                    "SELECT i.exchange_id, i.id, i.side, i.product_name, i.expiry,
+                            i.quantity, i.unit_value
+                     FROM LoanExchangeItem i
+                     JOIN LoanExchange e ON e.id = i.exchange_id
+                     {}
+                     ORDER BY i.side, i.product_name",
+                    filter
+                ))
+                .await
+                .map_err(|e| e.to_string())?;
+            let mut items_by_exchange: BTreeMap<String, Vec<ExchangeItem>> = BTreeMap::new();
+            for row in &items_rs.rows {
+                let exchange_id = row
+                    .try_column::<&str>("exchange_id")
+                    .map_err(|e| e.to_string())?;
+                items_by_exchange
+                    .entry(exchange_id.to_string())
+                    .or_default()
+                    .push(item_from_row(row)?);
+            }
+
+            let rs = client
+                .execute(format!(
+                    "SELECT e.id, e.date, e.counterparty, e.note, e.cash FROM LoanExchange e {}
+                     ORDER BY e.date DESC, e.id",
+                    filter
+                ))
+                .await
+                .map_err(|e| e.to_string())?;
+
+            let mut out = Vec::new();
+            for row in rs.rows {
+                let header = ExchangeHeader {
+                    id: row
+                        .try_column::<&str>("id")
+                        .map_err(|e| e.to_string())?
+                        .to_string(),
+                    date: row
+                        .try_column::<&str>("date")
+                        .map_err(|e| e.to_string())?
+                        .to_string(),
+                    counterparty: row
+                        .try_column::<&str>("counterparty")
+                        .map_err(|e| e.to_string())?
+                        .to_string(),
+                    note: row.try_column::<&str>("note").ok().map(|s| s.to_string()),
+                    cash: row.try_column::<i64>("cash").unwrap_or(0),
+                };
+                let items = items_by_exchange.remove(&header.id).unwrap_or_default();
+                let mut balance = -header.cash;
+                for it in &items {
+                    balance -= side_delta(&it.side, it.quantity)? * it.unit_value.unwrap_or(0);
+                }
+                out.push(Exchange {
+                    header,
+                    items,
+                    balance,
+                });
+            }
+            Ok(out)
+        })
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
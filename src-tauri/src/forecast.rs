@@ -0,0 +1,393 @@
+use crate::analytics::{self, Granularity};
+use crate::db::get_db_config;
+use chrono::{Duration, NaiveDate};
+use libsql_client::Client;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use tokio::task;
+
+/// z-score of the two-sided 95% interval.
+const Z_95: f64 = 1.96;
+
+#[derive(Debug, Serialize)]
+pub struct HistoryPoint {
+    pub period: String,
+    pub start: String,
+    pub quantity: i64, // sold minus returned
+}
+
+#[derive(Debug, Serialize)]
+pub struct ForecastPoint {
+    pub period: String,
+    pub start: String, // "YYYY-MM-DD"
+    pub end: String,   // "YYYY-MM-DD"
+    pub quantity: f64, // point forecast
+    pub lower: f64,    // 95% range, never below 0
+    pub upper: f64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ProductForecast {
+    pub product_name: String,
+    pub method: String, // model actually used, see `forecast_series`
+    pub history: Vec<HistoryPoint>,
+    pub forecast: Vec<ForecastPoint>,
+}
+
+/// Point forecast with its 95% range: (point, lower, upper).
+pub(crate) type Band = (f64, f64, f64);
+
+/// Point forecasts and residual standard deviation for the next `horizon` periods.
+struct Projection {
+    method: &'static str,
+    points: Vec<f64>,
+    sigma: f64,
+}
+
+fn std_dev(errors: &[f64]) -> f64 {
+    if errors.is_empty() {
+        return 0.0;
+    }
+    (errors.iter().map(|e| e * e).sum::<f64>() / errors.len() as f64).sqrt()
+}
+
+/// Mean of the last `window` periods, flat over the horizon.
+fn moving_average(history: &[f64], window: usize, horizon: usize) -> Projection {
+    let window = window.clamp(1, history.len().max(1));
+    let mean = |s: &[f64]| s.iter().sum::<f64>() / s.len().max(1) as f64;
+
+    // one-step-ahead errors wherever a full window precedes the value
+    let errors: Vec<f64> = (window..history.len())
+        .map(|i| history[i] - mean(&history[i - window..i]))
+        .collect();
+
+    let level = mean(&history[history.len().saturating_sub(window)..]);
+    Projection {
+        method: "moving_average",
+        points: vec![level; horizon],
+        sigma: std_dev(&errors),
+    }
+}
+
+/// Holt's linear trend smoothing, optionally with additive seasonality
+/// (Holt-Winters) when `season` > 0. Returns the fitted one-step errors.
+fn smooth(
+    history: &[f64],
+    season: usize,
+    alpha: f64,
+    beta: f64,
+    gamma: f64,
+    horizon: usize,
+) -> (Vec<f64>, Vec<f64>) {
+    let n = history.len();
+    let (mut level, mut trend, mut seasonal) = if season > 0 {
+        // initialise from the first two seasons
+        let first = history[..season].iter().sum::<f64>() / season as f64;
+        let second = history[season..2 * season].iter().sum::<f64>() / season as f64;
+        let seasonal: Vec<f64> = (0..season).map(|i| history[i] - first).collect();
+        (first, (second - first) / season as f64, seasonal)
+    } else {
+        (history[0], 0.0, Vec::new())
+    };
+
+    let start = if season > 0 { season } else { 1 };
+    let mut errors = Vec::with_capacity(n);
+    for (t, &y) in history.iter().enumerate().skip(start) {
+        let s = if season > 0 {
+            seasonal[t % season]
+        } else {
+            0.0
+        };
+        errors.push(y - (level + trend + s));
+
+        let prev_level = level;
+        level = alpha * (y - s) + (1.0 - alpha) * (level + trend);
+        trend = beta * (level - prev_level) + (1.0 - beta) * trend;
+        if season > 0 {
+            seasonal[t % season] = gamma * (y - level) + (1.0 - gamma) * s;
+        }
+    }
+
+    let points = (1..=horizon)
+        .map(|h| {
+            let s = if season > 0 {
+                seasonal[(n + h - 1) % season]
+            } else {
+                0.0
+            };
+            level + trend * h as f64 + s
+        })
+        .collect();
+    (points, errors)
+}
+
+/// Exponential smoothing with the smoothing factors picked by a small grid
+/// search on one-step error. Seasonal (Holt-Winters) needs two full seasons
+/// of history; with less it falls back to non-seasonal smoothing.
+fn exponential_smoothing(history: &[f64], season: usize, horizon: usize) -> Projection {
+    let seasonal = season > 1 && history.len() >= 2 * season;
+    let season = if seasonal { season } else { 0 };
+    let grid = [0.1, 0.2, 0.3, 0.5, 0.7, 0.9];
+    let betas: &[f64] = &[0.0, 0.05, 0.1, 0.2];
+    let gammas: &[f64] = if seasonal {
+        &[0.05, 0.1, 0.3, 0.5]
+    } else {
+        &[0.0]
+    };
+
+    let mut best: Option<(f64, Vec<f64>, Vec<f64>)> = None;
+    for &alpha in &grid {
+        for &beta in betas {
+            for &gamma in gammas {
+                let (points, errors) = smooth(history, season, alpha, beta, gamma, horizon);
+                let sse: f64 = errors.iter().map(|e| e * e).sum();
+                let better = match &best {
+                    Some((b, _, _)) => sse < *b,
+                    None => true,
+                };
+                if better {
+                    best = Some((sse, points, errors));
+                }
+            }
+        }
+    }
+    let (_, points, errors) = best.unwrap();
+    Projection {
+        method: if seasonal {
+            "holt_winters"
+        } else {
+            "exponential_smoothing"
+        },
+        points,
+        sigma: std_dev(&errors),
+    }
+}
+
+/// Forecast `horizon` periods after `history` with `method`
+/// ("moving_average" or "exponential_smoothing"); `season` is the number of
+/// periods per year. Returns (method used, [(point, lower, upper)]).
+pub(crate) fn forecast_series(
+    history: &[f64],
+    method: &str,
+    season: usize,
+    horizon: usize,
+) -> Result<(&'static str, Vec<Band>), String> {
+    if history.is_empty() {
+        return Ok(("moving_average", vec![(0.0, 0.0, 0.0); horizon]));
+    }
+    let p = match method {
+        "moving_average" => {
+            // about a month of weeks, or a quarter of months
+            let window = if season >= 52 { 4 } else { 3 };
+            moving_average(history, window, horizon)
+        }
+        "exponential_smoothing" if history.len() >= 2 => {
+            exponential_smoothing(history, season, horizon)
+        }
+        "exponential_smoothing" => moving_average(history, 1, horizon),
+        other => return Err(format!("不支持的预测方法：{}", other)),
+    };
+
+    // the range widens with distance, as errors add up step by step
+    Ok((
+        p.method,
+        p.points
+            .iter()
+            .enumerate()
+            .map(|(i, &q)| {
+                let q = q.max(0.0);
+                let spread = Z_95 * p.sigma * ((i + 1) as f64).sqrt();
+                (q, (q - spread).max(0.0), q + spread)
+            })
+            .collect(),
+    ))
+}
+
+fn round2(x: f64) -> f64 {
+    (x * 100.0).round() / 100.0
+}
+
+/// Net quantity per product in complete `granularity` buckets of the last
+/// `periods` periods (the current, unfinished period is excluded).
+pub(crate) async fn product_history(
+    client: &Client,
+    granularity: Granularity,
+    periods: usize,
+) -> Result<(Vec<NaiveDate>, BTreeMap<String, Vec<f64>>), String> {
+    let current = granularity.start_of(analytics::today());
+    let to = current - Duration::days(1);
+    let mut from = current;
+    for _ in 0..periods {
+        from = granularity.start_of(from - Duration::days(1));
+    }
+
+    let rows = analytics::daily_sales(client, from, to, Some("product")).await?;
+    let buckets = analytics::bucketize(&rows, from, to, granularity, true)?;
+    let starts = buckets
+        .iter()
+        .map(|b| analytics::parse_date(&b.start))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let mut series: BTreeMap<String, Vec<f64>> = BTreeMap::new();
+    for (i, b) in buckets.iter().enumerate() {
+        for g in &b.breakdown {
+            let Some(name) = &g.key else { continue };
+            series
+                .entry(name.clone())
+                .or_insert_with(|| vec![0.0; buckets.len()])[i] = g.quantity as f64;
+        }
+    }
+    Ok((starts, series))
+}
+
+/// Demand forecast per product for the next `horizon` weeks or months, from the
+/// last `history_periods` complete periods of net sales. `method` is
+/// "exponential_smoothing" (default; seasonal once two years of history exist)
+/// or "moving_average". Products without sales in that history are left out.
+#[tauri::command]
+pub async fn get_demand_forecast(
+    product_name: Option<String>,
+    granularity: Option<String>,
+    horizon: Option<u32>,
+    method: Option<String>,
+    history_periods: Option<u32>,
+) -> Result<Vec<ProductForecast>, String> {
+    let granularity = Granularity::parse(granularity.as_deref().unwrap_or("month"))?;
+    let season = match granularity {
+        Granularity::Week => 52,
+        Granularity::Month => 12,
+        _ => return Err("预测仅支持按周或按月。".into()),
+    };
+    let horizon = horizon.unwrap_or(3).clamp(1, season as u32) as usize;
+    let periods = history_periods.map(|n| n as usize).unwrap_or(season * 2);
+    let periods = periods.clamp(1, season * 5);
+    let method = method.unwrap_or_else(|| "exponential_smoothing".to_string());
+
+    task::spawn_blocking(move || {
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        rt.block_on(async move {
+            let config = get_db_config().await.map_err(|e| e.to_string())?;
+            let client = Client::from_config(config)
+                .await
+                .map_err(|e| e.to_string())?;
+
+            let (starts, series) = product_history(&client, granularity, periods).await?;
+
+            let mut out = Vec::new();
+            for (name, history) in series {
+                if product_name.as_deref().is_some_and(|p| p != name) {
+                    continue;
+                }
+                let (used, points) = forecast_series(&history, &method, season, horizon)?;
+
+                let mut start = granularity.start_of(analytics::today());
+                let mut forecast = Vec::with_capacity(horizon);
+                for (q, lo, hi) in points {
+                    let next = granularity.next(start);
+                    forecast.push(ForecastPoint {
+                        period: granularity.label(start),
+                        start: start.format("%Y-%m-%d").to_string(),
+                        end: (next - Duration::days(1)).format("%Y-%m-%d").to_string(),
+                        quantity: round2(q),
+                        lower: round2(lo),
+                        upper: round2(hi),
+                    });
+                    start = next;
+                }
+
+                out.push(ProductForecast {
+                    product_name: name,
+                    method: used.to_string(),
+                    history: starts
+                        .iter()
+                        .zip(&history)
+                        .map(|(s, q)| HistoryPoint {
+                            period: granularity.label(*s),
+                            start: s.format("%Y-%m-%d").to_string(),
+                            quantity: *q as i64,
+                        })
+                        .collect(),
+                    forecast,
+                });
+            }
+            Ok(out)
+        })
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn empty_history_forecasts_zero() {
+        let (method, bands) = forecast_series(&[], "exponential_smoothing", 12, 3).unwrap();
+        assert_eq!(method, "moving_average");
+        assert_eq!(bands, vec![(0.0, 0.0, 0.0); 3]);
+    }
+
+    #[test]
+    fn moving_average_of_the_last_window() {
+        let (method, bands) =
+            forecast_series(&[1.0, 2.0, 3.0, 6.0], "moving_average", 12, 2).unwrap();
+        assert_eq!(method, "moving_average");
+        assert_eq!(bands.len(), 2);
+        assert!((bands[0].0 - 11.0 / 3.0).abs() < 1e-9);
+        assert_eq!(bands[0].0, bands[1].0);
+        // the range widens with distance
+        assert!(bands[1].2 - bands[1].0 > bands[0].2 - bands[0].0);
+    }
+
+    #[test]
+    fn flat_history_has_no_spread() {
+        let history = [5.0; 10];
+        for method in ["moving_average", "exponential_smoothing"] {
+            let (_, bands) = forecast_series(&history, method, 52, 4).unwrap();
+            for (q, lower, upper) in bands {
+                assert!((q - 5.0).abs() < 1e-9, "{}: {}", method, q);
+                assert!((upper - lower).abs() < 1e-9);
+            }
+        }
+    }
+
+    #[test]
+    fn smoothing_needs_two_points() {
+        let (method, bands) = forecast_series(&[7.0], "exponential_smoothing", 12, 1).unwrap();
+        assert_eq!(method, "moving_average");
+        assert_eq!(bands[0].0, 7.0);
+    }
+
+    #[test]
+    fn seasonal_only_with_two_full_seasons() {
+        let pattern = [10.0, 20.0, 30.0, 20.0];
+        let history: Vec<f64> = pattern.iter().cycle().take(8).copied().collect();
+        let (method, bands) = forecast_series(&history, "exponential_smoothing", 4, 4).unwrap();
+        assert_eq!(method, "holt_winters");
+        for (band, expected) in bands.iter().zip(pattern) {
+            assert!(
+                (band.0 - expected).abs() < 1.0,
+                "{} vs {}",
+                band.0,
+                expected
+            );
+        }
+
+        let (method, _) = forecast_series(&history[..7], "exponential_smoothing", 4, 4).unwrap();
+        assert_eq!(method, "exponential_smoothing");
+    }
+
+    #[test]
+    fn falling_demand_never_goes_below_zero() {
+        let history: Vec<f64> = (0..12).map(|i| 60.0 - 5.0 * i as f64).collect();
+        let (_, bands) = forecast_series(&history, "exponential_smoothing", 12, 6).unwrap();
+        assert!(bands.iter().all(|&(q, lower, _)| q >= 0.0 && lower >= 0.0));
+        assert_eq!(bands[5].0, 0.0);
+    }
+
+    #[test]
+    fn unknown_method() {
+        assert!(forecast_series(&[1.0], "arima", 12, 1).is_err());
+    }
+}
@@ -1,26 +1,59 @@
+mod analytics;
 mod config;
+mod counterparty;
 mod dashboard;
 mod db;
+mod exchange;
+mod forecast;
 mod loan;
+mod loan_match;
+mod payment;
 mod product;
+mod profit;
+mod receipt;
+mod recurring;
+mod refill;
+mod returns;
 mod sales;
+mod statement;
 mod stock;
 mod summary;
 
+use analytics::{get_sales_comparison, get_sales_rankings, get_sales_series};
 use config::{get_alert_period, get_config, init_config, wire_verify_on_startup, write_config};
+use counterparty::{
+    create_counterparty, delete_counterparty, get_counterparties, merge_counterparties,
+    rename_counterparty, update_counterparty,
+};
 use dashboard::get_dashboard_summary;
 use db::verify_credentials;
+use exchange::{create_exchange, delete_exchange, get_exchanges};
+use forecast::get_demand_forecast;
 use loan::{
-    create_loan, delete_loan, get_loan_history, get_loan_items, get_loan_summary,
-    get_transaction_details, update_loan,
+    create_loan, delete_loan, get_loan_due_report, get_loan_history, get_loan_items,
+    get_loan_summary, get_transaction_details, settle_loan, update_loan,
+};
+use loan_match::{get_loan_aging, get_return_matches, set_return_matches};
+use payment::{delete_payment, get_receivables, get_sale_payments, record_payment};
+use product::{
+    add_product, delete_product, delete_product_points, get_all_products, get_product,
+    get_product_point_history, set_product_points, update_product,
+};
+use profit::get_profit_report;
+use receipt::export_sale_receipt;
+use recurring::{
+    create_recurring_sale, delete_recurring_sale, generate_recurring_sale, get_recurring_sales,
+    skip_recurring_sale, update_recurring_sale,
 };
-use product::{add_product, delete_product, get_all_products, get_product, update_product};
+use refill::get_refill_reminders;
+use returns::{create_sales_return, delete_sales_return, get_sales_returns};
 use sales::{
     delete_sale, get_monthly_sales, get_monthly_sales_stats, get_sales_history, get_sales_items,
     update_sale,
 };
+use statement::{export_loan_statement, get_loan_statement};
 use stock::{add_stock, edit_stock, get_in_stock_products, get_stock_lots, remove_stock};
-use summary::{get_stock_histogram, get_stock_overview};
+use summary::{get_expiry_risk, get_stock_histogram, get_stock_overview};
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
@@ -38,6 +71,9 @@ pub fn run() {
             delete_product,
             add_product,
             update_product,
+            get_product_point_history,
+            set_product_points,
+            delete_product_points,
             add_stock,
             remove_stock,
             edit_stock,
@@ -45,6 +81,8 @@ pub fn run() {
             get_stock_lots,
             get_stock_overview,
             get_stock_histogram,
+            get_expiry_risk,
+            get_profit_report,
             get_config,
             write_config,
             get_alert_period,
@@ -55,13 +93,48 @@ pub fn run() {
             get_sales_items,
             get_monthly_sales,
             get_monthly_sales_stats,
+            get_refill_reminders,
+            get_recurring_sales,
+            create_recurring_sale,
+            update_recurring_sale,
+            delete_recurring_sale,
+            generate_recurring_sale,
+            skip_recurring_sale,
+            get_sales_series,
+            get_sales_comparison,
+            get_sales_rankings,
+            get_demand_forecast,
+            create_sales_return,
+            delete_sales_return,
+            get_sales_returns,
+            record_payment,
+            delete_payment,
+            get_sale_payments,
+            get_receivables,
+            export_sale_receipt,
             create_loan,
             delete_loan,
             update_loan,
+            settle_loan,
             get_loan_history,
             get_loan_items,
             get_loan_summary,
             get_transaction_details,
+            get_loan_due_report,
+            get_loan_statement,
+            export_loan_statement,
+            get_return_matches,
+            set_return_matches,
+            get_loan_aging,
+            create_exchange,
+            delete_exchange,
+            get_exchanges,
+            get_counterparties,
+            create_counterparty,
+            update_counterparty,
+            rename_counterparty,
+            merge_counterparties,
+            delete_counterparty,
             get_dashboard_summary,
         ])
         .run(tauri::generate_context!())
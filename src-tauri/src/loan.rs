@@ -1,7 +1,20 @@
-use crate::db::{get_db_config, ignore_empty_baton_commit, sql_quote, to_sql_null_or_string};
-use libsql_client::Client;
+use crate::analytics;
+use crate::counterparty::ensure_counterparty;
+use crate::db::{
+    get_db_config, ignore_empty_baton_commit, sql_quote, to_sql_null_or_int, to_sql_null_or_string,
+};
+use crate::exchange::{exchange_balances, exchange_value};
+use crate::loan_match::{
+    clear_loan_matches, line_balances, loan_matches, loan_pairs, rematch_loan, rematch_pairs,
+    LineBalance,
+};
+use crate::sales::{insert_sale, SalesHeader, SalesItem};
+use crate::stock::{apply_lot_delta, insert_receipt, StockChange};
+use libsql_client::{Client, Transaction};
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use tokio::task;
+use uuid::Uuid;
 
 #[derive(Debug, Deserialize, Serialize)]
 pub struct LoanHeader {
@@ -10,6 +23,8 @@ pub struct LoanHeader {
     pub direction: String, // "loan_in" | "loan_out" | "return_in" | "return_out"
     pub counterparty: String,
     pub note: Option<String>,
+    #[serde(default)]
+    pub due_date: Option<String>, // "YYYY-MM-DD", expected return; optional
 }
 
 #[derive(Debug, Deserialize, Serialize)]
@@ -28,6 +43,44 @@ pub struct LoanSummary {
     pub product_type: Option<String>,
     pub net_quantity: i64,
     pub direction: String,
+    pub kind: String,   // "loan" | "exchange" (value balance of swaps, no product)
+    pub net_value: i64, // loans at current price; > 0 they owe us
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LoanDue {
+    pub counterparty: String,
+    pub product_name: String,
+    pub product_type: Option<String>,
+    pub net_quantity: i64,  // like LoanSummary: > 0 lent out, < 0 borrowed in
+    pub due_quantity: i64,  // units of the balance from loans due by the cutoff
+    pub due_date: String,   // earliest due date among the open loans
+    pub days_left: i64,     // negative = overdue
+    pub open_since: String, // date of the oldest open loan
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CounterpartyLoanValue {
+    pub counterparty: String,
+    pub lent_out_value: i64,    // our stock they hold
+    pub borrowed_in_value: i64, // their stock we hold, as a positive amount
+}
+
+#[derive(Debug, Deserialize)]
+pub struct LoanSettlement {
+    pub counterparty: String,
+    pub product_name: String,
+    pub quantity: i64,          // > 0, at most the open balance
+    pub date: String,           // "YYYY-MM-DD"
+    pub price: Option<i64>,     // unit price; None = Product.price (sale) / Product.cost (purchase)
+    pub expiry: Option<String>, // lot; None = the lots outstanding with the counterparty
+    pub note: Option<String>,
+    #[serde(default)]
+    pub payment_method: Option<String>,
+    #[serde(default)]
+    pub paid: bool, // sale paid in full now
 }
 
 #[derive(Debug, Serialize)]
@@ -36,6 +89,7 @@ pub struct TransactionDetail {
     pub date: String,
     pub direction: String,
     pub quantity: i64,
+    pub expiry: Option<String>,
     pub note: Option<String>,
 }
 
@@ -48,6 +102,369 @@ fn dir_delta(direction: &str, qty: i64) -> Result<i64, String> {
     }
 }
 
+/// A due date, when given, must be a date no earlier than the loan itself.
+fn check_due_date(header: &LoanHeader) -> Result<(), String> {
+    if let Some(due) = &header.due_date {
+        analytics::parse_date(due)?;
+        if due < &header.date {
+            return Err(format!("归还日期不能早于借货日期：{}", due));
+        }
+    }
+    Ok(())
+}
+
+/// Open loan lines per (counterparty, product), oldest first. Their open
+/// quantities add up to the `LoanLedger` net quantity; a return larger than
+/// what is open opens a balance of its own.
+async fn open_loans(
+    client: &Client,
+) -> Result<BTreeMap<(String, String), Vec<LineBalance>>, String> {
+    let mut open: BTreeMap<(String, String), Vec<LineBalance>> = BTreeMap::new();
+    for line in line_balances(client).await? {
+        if line.outstanding != 0 {
+            open.entry((line.counterparty.clone(), line.product_name.clone()))
+                .or_default()
+                .push(line);
+        }
+    }
+    Ok(open)
+}
+
+/// Open balances with a loan due within `within_days` days from today (or
+/// already overdue), most overdue first.
+pub async fn loans_due(client: &Client, within_days: i64) -> Result<Vec<LoanDue>, String> {
+    let today = analytics::today();
+    let cutoff = (today + chrono::Duration::days(within_days))
+        .format("%Y-%m-%d")
+        .to_string();
+
+    let mut out = Vec::new();
+    for ((counterparty, product_name), loans) in open_loans(client).await? {
+        let due: Vec<&LineBalance> = loans
+            .iter()
+            .filter(|l| l.due_date.as_deref().is_some_and(|d| d <= cutoff.as_str()))
+            .collect();
+        let Some(due_date) = due.iter().filter_map(|l| l.due_date.as_deref()).min() else {
+            continue;
+        };
+        out.push(LoanDue {
+            days_left: (analytics::parse_date(due_date)? - today).num_days(),
+            due_date: due_date.to_string(),
+            due_quantity: due.iter().map(|l| l.outstanding).sum(),
+            net_quantity: loans.iter().map(|l| l.outstanding).sum(),
+            open_since: loans[0].date.clone(),
+            product_type: loans[0].product_type.clone(),
+            counterparty,
+            product_name,
+        });
+    }
+
+    out.sort_by(|a, b| {
+        (a.days_left, &a.counterparty, &a.product_name).cmp(&(
+            b.days_left,
+            &b.counterparty,
+            &b.product_name,
+        ))
+    });
+    Ok(out)
+}
+
+/// Value of the open loan lines and exchange balances per counterparty, lent
+/// out and borrowed in apart, largest first. With `at_recorded_price` each line
+/// is valued at the member price recorded when it was entered; otherwise, and
+/// for lines entered before prices were kept, at the current price. Exchange
+/// balances keep their agreed value either way.
+pub async fn loan_values(
+    client: &Client,
+    at_recorded_price: bool,
+) -> Result<Vec<CounterpartyLoanValue>, String> {
+    let mut totals: BTreeMap<String, (i64, i64)> = BTreeMap::new();
+    for line in line_balances(client).await? {
+        if line.outstanding == 0 {
+            continue;
+        }
+        let price = if at_recorded_price {
+            line.loan_price.or(line.current_price)
+        } else {
+            line.current_price
+        };
+        let value = line.outstanding * price.unwrap_or(0);
+        let (lent, borrowed) = totals.entry(line.counterparty).or_default();
+        if value > 0 {
+            *lent += value;
+        } else {
+            *borrowed -= value;
+        }
+    }
+    for (counterparty, balance) in exchange_balances(client).await? {
+        let (lent, borrowed) = totals.entry(counterparty).or_default();
+        if balance > 0 {
+            *lent += balance;
+        } else {
+            *borrowed -= balance;
+        }
+    }
+
+    let mut out: Vec<CounterpartyLoanValue> = totals
+        .into_iter()
+        .filter(|(_, (lent, borrowed))| *lent != 0 || *borrowed != 0)
+        .map(|(counterparty, (lent, borrowed))| CounterpartyLoanValue {
+            counterparty,
+            lent_out_value: lent,
+            borrowed_in_value: borrowed,
+        })
+        .collect();
+    out.sort_by_key(|v| std::cmp::Reverse(v.lent_out_value.max(v.borrowed_in_value)));
+    Ok(out)
+}
+
+/// Signed stock change per (product, expiry) lot.
+type LotEffect = BTreeMap<(String, String), i64>;
+
+/// The stock effect recorded for a loan in `LoanStockMove`.
+async fn recorded_effect(tx: &Transaction<'_>, loan_id: &str) -> Result<LotEffect, String> {
+    let rs = tx
+        .execute(format!(
+            "SELECT product_name, expiry, SUM(delta) AS delta
+             FROM LoanStockMove WHERE loan_id = '{}'
+             GROUP BY product_name, expiry",
+            sql_quote(loan_id)
+        ))
+        .await
+        .map_err(|e| e.to_string())?;
+
+    let mut effect = LotEffect::new();
+    for row in rs.rows {
+        let name = row
+            .try_column::<&str>("product_name")
+            .map_err(|e| e.to_string())?;
+        let expiry = row
+            .try_column::<&str>("expiry")
+            .map_err(|e| e.to_string())?;
+        let delta = row.try_column::<i64>("delta").map_err(|e| e.to_string())?;
+        effect.insert((name.to_string(), expiry.to_string()), delta);
+    }
+    Ok(effect)
+}
+
+/// Told to the user when an edit or delete leaves stock alone because the
+/// loan was recorded before its stock moves were kept.
+const UNTRACKED_STOCK_NOTE: &str =
+    "该记录创建于库存联动之前，无法确定当时调整的批次，库存未随之调整；如有需要请手动调整库存。";
+
+/// Whether a loan was recorded before `LoanStockMove` kept its stock effect.
+async fn stock_untracked(tx: &Transaction<'_>, loan_id: &str) -> Result<bool, String> {
+    let rs = tx
+        .execute(format!(
+            "SELECT 1 FROM LoanHeader WHERE id = '{}' AND stock_tracked IS NULL",
+            sql_quote(loan_id)
+        ))
+        .await
+        .map_err(|e| e.to_string())?;
+    Ok(!rs.rows.is_empty())
+}
+
+/// Lots of `product_name` a return settles: those still out with (or in
+/// from) the counterparty through its other loans, earliest expiry first, with
+/// the quantity outstanding. Empty for loans.
+async fn outstanding_lots(
+    tx: &Transaction<'_>,
+    header: &LoanHeader,
+    product_name: &str,
+) -> Result<Vec<(String, i64)>, String> {
+    // a return in undoes stock lent out (negative moves), a return out the reverse
+    let sign = match header.direction.as_str() {
+        "return_in" => -1,
+        "return_out" => 1,
+        _ => return Ok(Vec::new()),
+    };
+    let rs = tx
+        .execute(format!(
+            "SELECT m.expiry, SUM(m.delta) * {} AS outstanding
+             FROM LoanStockMove m
+             JOIN LoanHeader h ON h.id = m.loan_id
+             WHERE h.counterparty = '{}' AND m.product_name = '{}' AND h.id <> '{}'
+             GROUP BY m.expiry
+             HAVING outstanding > 0
+             ORDER BY m.expiry",
+            sign,
+            sql_quote(&header.counterparty),
+            sql_quote(product_name),
+            sql_quote(&header.id)
+        ))
+        .await
+        .map_err(|e| e.to_string())?;
+
+    let mut lots = Vec::new();
+    for row in rs.rows {
+        let expiry = row
+            .try_column::<&str>("expiry")
+            .map_err(|e| e.to_string())?;
+        let outstanding = row
+            .try_column::<i64>("outstanding")
+            .map_err(|e| e.to_string())?;
+        lots.push((expiry.to_string(), outstanding));
+    }
+    Ok(lots)
+}
+
+/// Give every line a lot. Lines without an expiry take the lots the loan
+/// already touched for that product (`previous`) or, for returns, the lots
+/// still outstanding with the counterparty, in expiry order with any extra
+/// quantity on the last one; a line spanning several lots is split.
+async fn resolve_lots(
+    tx: &Transaction<'_>,
+    header: &LoanHeader,
+    items: Vec<LoanItem>,
+    previous: &LotEffect,
+) -> Result<Vec<LoanItem>, String> {
+    let mut available: BTreeMap<String, Vec<(String, i64)>> = BTreeMap::new();
+    let mut resolved = Vec::with_capacity(items.len());
+    for it in items {
+        if it.expiry.as_deref().is_some_and(|e| !e.is_empty()) {
+            resolved.push(it);
+            continue;
+        }
+
+        if !available.contains_key(&it.product_name) {
+            let mut lots: Vec<(String, i64)> = previous
+                .iter()
+                .filter(|((name, _), _)| *name == it.product_name)
+                .map(|((_, expiry), delta)| (expiry.clone(), delta.abs()))
+                .collect();
+            if lots.is_empty() {
+                lots = outstanding_lots(tx, header, &it.product_name).await?;
+            }
+            available.insert(it.product_name.clone(), lots);
+        }
+        let lots = available.get_mut(&it.product_name).unwrap();
+        if lots.is_empty() {
+            return Err(format!("必须提供到期日：{}", it.product_name));
+        }
+        resolved.extend(split_over_lots(it, lots));
+    }
+    Ok(resolved)
+}
+
+/// Split `it` over `lots` (expiry, room) in order, using up their room; what
+/// no lot has room for goes on the last one. The first piece keeps the id.
+fn split_over_lots(it: LoanItem, lots: &mut [(String, i64)]) -> Vec<LoanItem> {
+    let last = lots.len().saturating_sub(1);
+    let mut left = it.quantity;
+    let mut id = Some(it.id);
+    let mut out = Vec::new();
+    for (i, (expiry, room)) in lots.iter_mut().enumerate() {
+        let take = if i == last { left } else { left.min(*room) };
+        if take <= 0 {
+            continue;
+        }
+        *room -= take;
+        left -= take;
+        out.push(LoanItem {
+            id: id.take().unwrap_or_else(|| Uuid::new_v4().to_string()),
+            product_name: it.product_name.clone(),
+            quantity: take,
+            expiry: Some(expiry.clone()),
+        });
+    }
+    out
+}
+
+/// The stock effect of `items` (all with a lot) for `direction`.
+fn effect_of(direction: &str, items: &[LoanItem]) -> Result<LotEffect, String> {
+    let mut effect = LotEffect::new();
+    for it in items {
+        let expiry = it
+            .expiry
+            .as_ref()
+            .ok_or_else(|| format!("必须提供到期日：{}", it.product_name))?;
+        *effect
+            .entry((it.product_name.clone(), expiry.clone()))
+            .or_insert(0) += dir_delta(direction, it.quantity)?;
+    }
+    effect.retain(|_, d| *d != 0);
+    Ok(effect)
+}
+
+/// Move stock from the `previous` effect of a loan to `next`, lot by lot (taking
+/// stock fails if a lot cannot cover it), and record `next` as the loan's effect.
+async fn apply_effect(
+    tx: &Transaction<'_>,
+    loan_id: &str,
+    previous: &LotEffect,
+    next: &LotEffect,
+) -> Result<(), String> {
+    let lots: std::collections::BTreeSet<&(String, String)> =
+        previous.keys().chain(next.keys()).collect();
+    for lot in lots {
+        let diff = next.get(lot).copied().unwrap_or(0) - previous.get(lot).copied().unwrap_or(0);
+        apply_lot_delta(tx, &lot.0, &lot.1, diff).await?;
+    }
+
+    tx.execute(format!(
+        "DELETE FROM LoanStockMove WHERE loan_id = '{}';",
+        sql_quote(loan_id)
+    ))
+    .await
+    .map_err(|e| e.to_string())?;
+    for ((name, expiry), delta) in next {
+        tx.execute(format!(
+            "INSERT INTO LoanStockMove (id, loan_id, product_name, expiry, delta)
+             VALUES ('{}', '{}', '{}', '{}', {});",
+            Uuid::new_v4(),
+            sql_quote(loan_id),
+            sql_quote(name),
+            sql_quote(expiry),
+            delta
+        ))
+        .await
+        .map_err(|e| e.to_string())?;
+    }
+    Ok(())
+}
+
+/// Insert a loan header and its items inside an existing transaction;
+/// `stock_tracked` says whether its stock effect goes into `LoanStockMove`.
+async fn insert_loan(
+    tx: &Transaction<'_>,
+    header: &LoanHeader,
+    items: &[LoanItem],
+    stock_tracked: bool,
+) -> Result<(), String> {
+    let hdr_id_q = sql_quote(&header.id);
+    let counterparty_id = ensure_counterparty(tx, &header.counterparty).await?;
+    let sql_header = format!(
+        "INSERT INTO LoanHeader
+           (id, date, direction, counterparty, counterparty_id, note, due_date, stock_tracked)
+         VALUES ('{}','{}','{}','{}','{}', {}, {}, {});",
+        hdr_id_q,
+        sql_quote(&header.date),
+        sql_quote(&header.direction),
+        sql_quote(header.counterparty.trim()),
+        sql_quote(&counterparty_id),
+        to_sql_null_or_string(&header.note),
+        to_sql_null_or_string(&header.due_date),
+        stock_tracked as i64
+    );
+    tx.execute(sql_header).await.map_err(|e| e.to_string())?;
+
+    for it in items {
+        let name_q = sql_quote(&it.product_name);
+        let sql_item = format!(
+            "INSERT INTO LoanItem (id, loan_id, product_name, quantity, expiry, unit_price)
+             VALUES ('{}','{}','{}', {}, {}, (SELECT price FROM Product WHERE name = '{}'));",
+            sql_quote(&it.id),
+            hdr_id_q,
+            name_q,
+            it.quantity,
+            to_sql_null_or_string(&it.expiry),
+            name_q
+        );
+        tx.execute(sql_item).await.map_err(|e| e.to_string())?;
+    }
+    Ok(())
+}
+
 #[tauri::command]
 pub async fn create_loan(
     header: LoanHeader,
@@ -65,6 +482,7 @@ pub async fn create_loan(
             ));
         }
     }
+    check_due_date(&header)?;
 
     task::spawn_blocking(move || {
         let rt = tokio::runtime::Runtime::new().unwrap();
@@ -84,13 +502,6 @@ pub async fn create_loan(
             // 2) begin tx
             let tx = client.transaction().await.map_err(|e| e.to_string())?;
 
-            // normalize / quote
-            let hdr_id_q = sql_quote(&header.id);
-            let date_q = sql_quote(&header.date);
-            let dir_q = sql_quote(&header.direction);
-            let cp_q = sql_quote(&header.counterparty);
-            let note_sql = to_sql_null_or_string(&header.note);
-
             // 3) checks inside the tx
 
             // 3a) direction is valid
@@ -116,97 +527,179 @@ pub async fn create_loan(
                 }
             }
 
-            // 3c) if decreasing stock, ensure not going negative for (name, expiry)
+            // 3c) every line needs a lot when stock is adjusted; returns
+            // without one go back to the lots the counterparty still holds
             let will_adjust = adjust_stock.unwrap_or(true);
-            if will_adjust {
-                for it in &items {
-                    let delta = dir_delta(&header.direction, it.quantity)?;
-                    if delta < 0 {
-                        let name_q = sql_quote(&it.product_name);
-                        let expiry = it
-                            .expiry
-                            .as_ref()
-                            .ok_or_else(|| format!("必须提供到期日：{}", it.product_name))?;
-                        let expiry_q = sql_quote(expiry);
-                        let rs = tx
-                            .execute(format!(
-                                "SELECT quantity FROM Stock WHERE name='{}' AND expiry='{}';",
-                                name_q, expiry_q
-                            ))
-                            .await
-                            .map_err(|e| e.to_string())?;
-                        let current: i64 = rs
-                            .rows
-                            .get(0)
-                            .and_then(|row| row.try_column::<i64>("quantity").ok())
-                            .unwrap_or(0);
-                        if current + delta < 0 {
-                            return Err(format!(
-                                "库存不足：{}（到期 {}）当前 {}，欲减少 {}",
-                                it.product_name, expiry, current, -delta
-                            ));
-                        }
-                    }
-                }
-            }
+            let (items, effect) = if will_adjust {
+                let items = resolve_lots(&tx, &header, items, &LotEffect::new()).await?;
+                let effect = effect_of(&header.direction, &items)?;
+                (items, effect)
+            } else {
+                (items, LotEffect::new())
+            };
+
+            // 4) insert header and items, matching returns to the loans they settle
+            insert_loan(&tx, &header, &items, will_adjust).await?;
+            rematch_loan(&tx, &header.id, &[]).await?;
+
+            // 4c) adjust Stock if requested (taking stock fails if a lot runs short),
+            // remembering the effect so edits and deletes can redo or undo it
+            apply_effect(&tx, &header.id, &LotEffect::new(), &effect).await?;
 
-            // 4) perform inserts
+            // 5) commit using your helper
+            let res = tx.commit().await;
+            ignore_empty_baton_commit(res)?;
+            Ok(())
+        })
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
 
-            // 4a) header
-            let sql_header = format!(
-                "INSERT INTO LoanHeader (id, date, direction, counterparty, note)
-                 VALUES ('{}','{}','{}','{}', {});",
-                hdr_id_q, date_q, dir_q, cp_q, note_sql
-            );
-            tx.execute(sql_header).await.map_err(|e| e.to_string())?;
+/// Settle part of an open loan balance by sale instead of return. When the
+/// counterparty keeps stock we lent, a `return_in` closes the balance and a
+/// sale to them records the same lots; when we keep stock we borrowed, a
+/// `return_out` closes it and a stock receipt records the purchase at the
+/// agreed price. The goods never come back, so neither record moves stock:
+/// the return is kept without a stock effect and the sale or receipt only
+/// records the lots, and deleting either one later moves stock at most once.
+/// Returns the id of the settling loan record.
+#[tauri::command]
+pub async fn settle_loan(settlement: LoanSettlement) -> Result<String, String> {
+    if settlement.quantity <= 0 {
+        return Err(format!(
+            "数量必须为正数：{} ({})",
+            settlement.product_name, settlement.quantity
+        ));
+    }
+    if settlement.price.is_some_and(|p| p < 0) {
+        return Err(format!("单价不能为负数：{}", settlement.product_name));
+    }
+    if settlement.date.trim().is_empty() {
+        return Err("请选择结算日期。".into());
+    }
 
-            // 4b) items
-            for it in &items {
-                let it_id_q = sql_quote(&it.id);
-                let name_q = sql_quote(&it.product_name);
-                let sql_item = format!(
-                    "INSERT INTO LoanItem (id, loan_id, product_name, quantity)
-                     VALUES ('{}','{}','{}', {});",
-                    it_id_q, hdr_id_q, name_q, it.quantity
-                );
-                tx.execute(sql_item).await.map_err(|e| e.to_string())?;
+    task::spawn_blocking(move || {
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        rt.block_on(async move {
+            let config = get_db_config().await.map_err(|e| e.to_string())?;
+            let client = Client::from_config(config)
+                .await
+                .map_err(|e| e.to_string())?;
+
+            // IMPORTANT: enable FKs
+            client
+                .execute("PRAGMA foreign_keys = ON;")
+                .await
+                .map_err(|e| e.to_string())?;
+
+            let tx = client.transaction().await.map_err(|e| e.to_string())?;
+
+            // 1. Open balance: > 0 the counterparty holds our stock, < 0 we hold theirs
+            let rs = tx
+                .execute(format!(
+                    "SELECT COALESCE(SUM(quantity * sign), 0) AS net_quantity
+                     FROM LoanLedger
+                     WHERE counterparty = '{}' AND product_name = '{}'",
+                    sql_quote(&settlement.counterparty),
+                    sql_quote(&settlement.product_name)
+                ))
+                .await
+                .map_err(|e| e.to_string())?;
+            let net = rs
+                .rows
+                .first()
+                .and_then(|row| row.try_column::<i64>("net_quantity").ok())
+                .unwrap_or(0);
+            if net == 0 {
+                return Err(format!(
+                    "没有未结清的借货：{} - {}",
+                    settlement.counterparty, settlement.product_name
+                ));
+            }
+            if settlement.quantity > net.abs() {
+                return Err(format!(
+                    "结算数量超出未结清数量：{}（未结清 {}）",
+                    settlement.product_name,
+                    net.abs()
+                ));
             }
 
-            // 4c) adjust Stock if requested (UPSERT on (name, expiry))
-            if will_adjust {
+            // 2. Close the balance with a return from the lots outstanding
+            let sold = net > 0;
+            let header = LoanHeader {
+                id: Uuid::new_v4().to_string(),
+                date: settlement.date.clone(),
+                direction: if sold { "return_in" } else { "return_out" }.to_string(),
+                counterparty: settlement.counterparty.clone(),
+                note: Some(
+                    settlement
+                        .note
+                        .clone()
+                        .unwrap_or_else(|| if sold { "转为销售" } else { "转为购买" }.to_string()),
+                ),
+                due_date: None,
+            };
+            let items = vec![LoanItem {
+                id: Uuid::new_v4().to_string(),
+                product_name: settlement.product_name.clone(),
+                quantity: settlement.quantity,
+                expiry: settlement.expiry.clone(),
+            }];
+            let items = resolve_lots(&tx, &header, items, &LotEffect::new()).await?;
+            insert_loan(&tx, &header, &items, false).await?;
+            rematch_loan(&tx, &header.id, &[]).await?;
+
+            // 3. Record the sale or purchase of the same lots, without moving stock
+            if sold {
+                let sale = SalesHeader {
+                    id: Uuid::new_v4().to_string(),
+                    date: settlement.date.clone(),
+                    note: Some(format!("借货转销售：{}", settlement.counterparty)),
+                    customer: Some(settlement.counterparty.clone()),
+                    payment_method: settlement.payment_method.clone(),
+                    payment_status: Some(
+                        if settlement.paid { "paid" } else { "unpaid" }.to_string(),
+                    ),
+                    ..Default::default()
+                };
+                let sale_items: Vec<SalesItem> = items
+                    .iter()
+                    .map(|it| SalesItem {
+                        id: Uuid::new_v4().to_string(),
+                        product_name: it.product_name.clone(),
+                        quantity: it.quantity,
+                        expiry: it.expiry.clone().unwrap_or_default(),
+                        price: settlement.price,
+                    })
+                    .collect();
+                insert_sale(&tx, &sale, &sale_items).await?;
+            } else {
                 for it in &items {
-                    let delta = dir_delta(&header.direction, it.quantity)?;
-                    let name_q = sql_quote(&it.product_name);
-                    let expiry = it
-                        .expiry
-                        .as_ref()
-                        .ok_or_else(|| format!("必须提供到期日：{}", it.product_name))?;
-                    let expiry_q = sql_quote(expiry);
-                    let upsert = format!(
-                        "INSERT INTO Stock (id, name, expiry, quantity)
-                         VALUES (lower(hex(randomblob(16))), '{name}', '{expiry}', {delta})
-                         ON CONFLICT(name, expiry)
-                         DO UPDATE SET quantity = quantity + {delta};",
-                        name = name_q,
-                        expiry = expiry_q,
-                        delta = delta
-                    );
-                    tx.execute(upsert).await.map_err(|e| e.to_string())?;
+                    let change = StockChange {
+                        name: it.product_name.clone(),
+                        expiry_date: it.expiry.clone().unwrap_or_default(),
+                        qty: it.quantity,
+                        price: settlement.price,
+                        cost: settlement.price,
+                    };
+                    insert_receipt(&tx, &settlement.date, &change).await?;
                 }
             }
 
-            // 5) commit using your helper
             let res = tx.commit().await;
             ignore_empty_baton_commit(res)?;
-            Ok(())
+            Ok(header.id)
         })
     })
     .await
     .map_err(|e| e.to_string())?
 }
 
+/// Delete a loan and undo its stock effect. Returns a note for the user
+/// when the loan predates stock tracking and stock was left alone.
 #[tauri::command]
-pub async fn delete_loan(loan_id: String) -> Result<(), String> {
+pub async fn delete_loan(loan_id: String) -> Result<Option<String>, String> {
     task::spawn_blocking(move || {
         let rt = tokio::runtime::Runtime::new().unwrap();
         rt.block_on(async move {
@@ -226,7 +719,16 @@ pub async fn delete_loan(loan_id: String) -> Result<(), String> {
 
             let loan_id_q = sql_quote(&loan_id);
 
-            // 1. First delete the loan items (child records)
+            // 0. Undo the loan's stock effect, if it had one
+            let note = stock_untracked(&tx, &loan_id)
+                .await?
+                .then(|| UNTRACKED_STOCK_NOTE.to_string());
+            let previous = recorded_effect(&tx, &loan_id).await?;
+            apply_effect(&tx, &loan_id, &previous, &LotEffect::new()).await?;
+
+            // 1. First delete the loan items (child records) and their matches
+            let pairs = loan_pairs(&tx, &loan_id).await?;
+            clear_loan_matches(&tx, &loan_id).await?;
             let delete_items_sql = format!("DELETE FROM LoanItem WHERE loan_id = '{}';", loan_id_q);
             tx.execute(delete_items_sql)
                 .await
@@ -238,19 +740,28 @@ pub async fn delete_loan(loan_id: String) -> Result<(), String> {
                 .await
                 .map_err(|e| e.to_string())?;
 
+            // 3. Returns that settled the deleted lines settle the next oldest ones
+            rematch_pairs(&tx, &pairs).await?;
+
             // Commit the transaction
             let res = tx.commit().await;
             ignore_empty_baton_commit(res)?;
 
-            Ok(())
+            Ok(note)
         })
     })
     .await
     .map_err(|e| e.to_string())?
 }
 
+/// Update a loan, moving stock to match the edit. Returns a note for the
+/// user when the loan predates stock tracking and stock was left alone.
 #[tauri::command]
-pub async fn update_loan(header: LoanHeader, items: Vec<LoanItem>) -> Result<(), String> {
+pub async fn update_loan(
+    header: LoanHeader,
+    items: Vec<LoanItem>,
+) -> Result<Option<String>, String> {
+    check_due_date(&header)?;
     task::spawn_blocking(move || {
         let rt = tokio::runtime::Runtime::new().unwrap();
         rt.block_on(async move {
@@ -271,7 +782,7 @@ pub async fn update_loan(header: LoanHeader, items: Vec<LoanItem>) -> Result<(),
             let loan_id_q = sql_quote(&header.id);
             let date_q = sql_quote(&header.date);
             let dir_q = sql_quote(&header.direction);
-            let cp_q = sql_quote(&header.counterparty);
+            let cp_q = sql_quote(header.counterparty.trim());
             let note_sql = to_sql_null_or_string(&header.note);
 
             // 1. Verify direction is valid
@@ -297,48 +808,187 @@ pub async fn update_loan(header: LoanHeader, items: Vec<LoanItem>) -> Result<(),
                 }
             }
 
-            // 3. Update loan header
+            // 3. Loans that moved stock move it again to match the edit: the
+            // difference per lot between the old and new effect is applied
+            let note = stock_untracked(&tx, &header.id)
+                .await?
+                .then(|| UNTRACKED_STOCK_NOTE.to_string());
+            let previous = recorded_effect(&tx, &header.id).await?;
+            let items = if previous.is_empty() {
+                items
+            } else {
+                let items = resolve_lots(&tx, &header, items, &previous).await?;
+                let next = effect_of(&header.direction, &items)?;
+                apply_effect(&tx, &header.id, &previous, &next).await?;
+                items
+            };
+
+            // 4. Update loan header, remembering the pairs the old lines belonged to
+            let old_pairs = loan_pairs(&tx, &header.id).await?;
+            let counterparty_id = ensure_counterparty(&tx, &header.counterparty).await?;
             let update_header_sql = format!(
                 "UPDATE LoanHeader 
-                 SET date = '{}', direction = '{}', counterparty = '{}', note = {}
+                 SET date = '{}', direction = '{}', counterparty = '{}', counterparty_id = '{}',
+                     note = {}, due_date = {}
                  WHERE id = '{}';",
-                date_q, dir_q, cp_q, note_sql, loan_id_q
+                date_q,
+                dir_q,
+                cp_q,
+                sql_quote(&counterparty_id),
+                note_sql,
+                to_sql_null_or_string(&header.due_date),
+                loan_id_q
             );
             tx.execute(update_header_sql)
                 .await
                 .map_err(|e| e.to_string())?;
 
-            // 4. Delete existing loan items
+            // 5. Delete existing loan items, keeping their matches and the
+            // prices recorded with them aside
+            let kept = loan_matches(&tx, &header.id).await?;
+            let rs = tx
+                .execute(format!(
+                    "SELECT product_name, MAX(unit_price) AS unit_price
+                     FROM LoanItem WHERE loan_id = '{}' AND unit_price IS NOT NULL
+                     GROUP BY product_name",
+                    loan_id_q
+                ))
+                .await
+                .map_err(|e| e.to_string())?;
+            let mut prices: BTreeMap<String, i64> = BTreeMap::new();
+            for row in rs.rows {
+                let name = row
+                    .try_column::<&str>("product_name")
+                    .map_err(|e| e.to_string())?;
+                let price = row
+                    .try_column::<i64>("unit_price")
+                    .map_err(|e| e.to_string())?;
+                prices.insert(name.to_string(), price);
+            }
+            clear_loan_matches(&tx, &header.id).await?;
             let delete_items_sql = format!("DELETE FROM LoanItem WHERE loan_id = '{}';", loan_id_q);
             tx.execute(delete_items_sql)
                 .await
                 .map_err(|e| e.to_string())?;
 
-            // 5. Insert new loan items
+            // 6. Insert new loan items; products new to the loan take today's price
             for it in &items {
                 let it_id_q = sql_quote(&it.id);
                 let name_q = sql_quote(&it.product_name);
                 let sql_item = format!(
-                    "INSERT INTO LoanItem (id, loan_id, product_name, quantity)
-                     VALUES ('{}','{}','{}', {});",
-                    it_id_q, loan_id_q, name_q, it.quantity
+                    "INSERT INTO LoanItem (id, loan_id, product_name, quantity, expiry, unit_price)
+                     VALUES ('{}','{}','{}', {}, {},
+                             COALESCE({}, (SELECT price FROM Product WHERE name = '{}')));",
+                    it_id_q,
+                    loan_id_q,
+                    name_q,
+                    it.quantity,
+                    to_sql_null_or_string(&it.expiry),
+                    to_sql_null_or_int(prices.get(&it.product_name).copied()),
+                    name_q
                 );
                 tx.execute(sql_item).await.map_err(|e| e.to_string())?;
             }
 
+            // 7. Restore the matches that still fit and match the rest FIFO, also
+            // for returns that had settled lines the edit removed
+            rematch_loan(&tx, &header.id, &kept).await?;
+            rematch_pairs(&tx, &old_pairs).await?;
+
             // Commit the transaction
             let res = tx.commit().await;
             ignore_empty_baton_commit(res)?;
 
-            Ok(())
+            Ok(note)
         })
     })
     .await
     .map_err(|e| e.to_string())?
 }
 
+/// Filters and page position for `get_loan_history`.
+/// Every field is optional; the default is the newest 50 loans.
+#[derive(Debug, Default, Deserialize)]
+pub struct LoanHistoryFilter {
+    pub loan_id: Option<String>,           // one loan only
+    pub counterparty: Option<String>,      // exact name
+    pub direction: Option<String>,         // a loan direction, or "exchange"
+    pub product_name: Option<String>,      // loans containing this product
+    pub date_from: Option<String>,         // "YYYY-MM-DD", inclusive
+    pub date_to: Option<String>,           // "YYYY-MM-DD", inclusive
+    pub status: Option<String>,            // "open" | "closed"
+    pub limit: Option<u32>,                // page size, default 50
+    pub cursor: Option<LoanHistoryCursor>, // `next_cursor` of the previous page
+}
+
+/// Position after the last row of a page: its date and id.
+#[derive(Debug, Deserialize, Serialize)]
+pub struct LoanHistoryCursor {
+    pub date: String,
+    pub id: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct LoanHistoryRow {
+    pub kind: String, // "loan" | "exchange"; exchanges carry direction "exchange"
+    pub header: LoanHeader,
+    pub item_count: i64,
+    pub total_quantity: i64,
+    pub total_value: i64, // at the price recorded with each line; exchanges: their balance
+}
+
+#[derive(Debug, Serialize)]
+pub struct LoanHistoryPage {
+    pub loans: Vec<LoanHistoryRow>,
+    pub next_cursor: Option<LoanHistoryCursor>, // None = last page
+}
+
 #[tauri::command]
-pub async fn get_loan_history() -> Result<Vec<LoanHeader>, String> {
+pub async fn get_loan_history(
+    filter: Option<LoanHistoryFilter>,
+) -> Result<LoanHistoryPage, String> {
+    let filter = filter.unwrap_or_default();
+    let limit = filter.limit.unwrap_or(50).clamp(1, 500);
+    let open_only = match filter.status.as_deref().filter(|s| !s.is_empty()) {
+        None => None,
+        Some("open") => Some(true),
+        Some("closed") => Some(false),
+        Some(other) => return Err(format!("不支持的状态：{}", other)),
+    };
+
+    let mut conds: Vec<String> = Vec::new();
+    if let Some(id) = filter.loan_id.as_deref().filter(|s| !s.is_empty()) {
+        conds.push(format!("h.id = '{}'", sql_quote(id)));
+    }
+    if let Some(c) = filter.counterparty.as_deref().filter(|s| !s.is_empty()) {
+        conds.push(format!("h.counterparty = '{}'", sql_quote(c)));
+    }
+    if let Some(d) = filter.direction.as_deref().filter(|s| !s.is_empty()) {
+        conds.push(format!("h.direction = '{}'", sql_quote(d)));
+    }
+    if let Some(name) = filter.product_name.as_deref().filter(|s| !s.is_empty()) {
+        conds.push(format!(
+            "(EXISTS (SELECT 1 FROM LoanItem f WHERE f.loan_id = h.id AND f.product_name = '{n}')
+              OR EXISTS (SELECT 1 FROM LoanExchangeItem f
+                         WHERE f.exchange_id = h.id AND f.product_name = '{n}'))",
+            n = sql_quote(name)
+        ));
+    }
+    if let Some(from) = filter.date_from.as_deref().filter(|s| !s.is_empty()) {
+        conds.push(format!("h.date >= '{}'", sql_quote(from)));
+    }
+    if let Some(to) = filter.date_to.as_deref().filter(|s| !s.is_empty()) {
+        conds.push(format!("h.date <= '{}'", sql_quote(to)));
+    }
+    // keyset pagination: strictly after the cursor row, newest first
+    if let Some(c) = &filter.cursor {
+        conds.push(format!(
+            "(h.date < '{d}' OR (h.date = '{d}' AND h.id < '{id}'))",
+            d = sql_quote(&c.date),
+            id = sql_quote(&c.id)
+        ));
+    }
+
     task::spawn_blocking(move || {
         let rt = tokio::runtime::Runtime::new().unwrap();
         rt.block_on(async move {
@@ -347,16 +997,80 @@ pub async fn get_loan_history() -> Result<Vec<LoanHeader>, String> {
                 .await
                 .map_err(|e| e.to_string())?;
 
-            // Query to get all loan headers ordered by date (newest first)
-            let sql = r#"
-                SELECT id, date, direction, counterparty, note
-                FROM LoanHeader
-                ORDER BY date DESC, id DESC
-            "#;
+            // A loan is open while any of its lines is; only worked out when asked,
+            // since it takes a pass over every loan line.
+            if let Some(open_only) = open_only {
+                let mut open_ids: Vec<String> = line_balances(&client)
+                    .await?
+                    .into_iter()
+                    .filter(|l| l.outstanding != 0)
+                    .map(|l| format!("'{}'", sql_quote(&l.loan_id)))
+                    .collect();
+                open_ids.sort();
+                open_ids.dedup();
+                // exchanges have no lines to settle and are left out
+                conds.push(format!(
+                    "h.kind = 'loan' AND h.id {} ({})",
+                    if open_only { "IN" } else { "NOT IN" },
+                    open_ids.join(", ")
+                ));
+            }
+            let where_clause = if conds.is_empty() {
+                String::new()
+            } else {
+                format!("WHERE {}", conds.join(" AND "))
+            };
+
+            // Exchanges are listed with the loans; one extra row is fetched to
+            // know whether another page follows.
+            let sql = format!(
+                r#"
+                WITH entry AS (
+                    SELECT 'loan' AS kind, id, date, direction, counterparty, note, due_date,
+                           0 AS cash
+                    FROM LoanHeader
+                    UNION ALL
+                    SELECT 'exchange', id, date, 'exchange', counterparty, note, NULL, cash
+                    FROM LoanExchange
+                ),
+                page AS (
+                    SELECT h.*
+                    FROM entry h
+                    {where_clause}
+                    ORDER BY h.date DESC, h.id DESC
+                    LIMIT {fetch}
+                )
+                SELECT page.kind, page.id, page.date, page.direction, page.counterparty,
+                       page.note, page.due_date,
+                       CASE page.kind
+                           WHEN 'exchange' THEN (SELECT COUNT(*) FROM LoanExchangeItem x
+                                                 WHERE x.exchange_id = page.id)
+                           ELSE COUNT(i.id)
+                       END AS item_count,
+                       CASE page.kind
+                           WHEN 'exchange' THEN (SELECT COALESCE(SUM(x.quantity), 0)
+                                                 FROM LoanExchangeItem x
+                                                 WHERE x.exchange_id = page.id)
+                           ELSE COALESCE(SUM(i.quantity), 0)
+                       END AS total_quantity,
+                       CASE page.kind
+                           WHEN 'exchange' THEN {exchange_value}
+                           ELSE COALESCE(SUM(i.quantity * COALESCE(i.unit_price, p.price, 0)), 0)
+                       END AS total_value
+                FROM page
+                LEFT JOIN LoanItem i ON i.loan_id = page.id
+                LEFT JOIN Product p ON p.name = i.product_name
+                GROUP BY page.id
+                ORDER BY page.date DESC, page.id DESC
+                "#,
+                where_clause = where_clause,
+                fetch = limit + 1,
+                exchange_value = exchange_value("page")
+            );
 
             let result = client.execute(sql).await.map_err(|e| e.to_string())?;
 
-            let mut loan_headers = Vec::new();
+            let mut loans = Vec::new();
 
             for row in result.rows {
                 let id = row
@@ -380,17 +1094,38 @@ pub async fn get_loan_history() -> Result<Vec<LoanHeader>, String> {
                     .to_string();
 
                 let note = row.try_column::<&str>("note").ok().map(|s| s.to_string());
+                let due_date = row
+                    .try_column::<&str>("due_date")
+                    .ok()
+                    .map(|s| s.to_string());
 
-                loan_headers.push(LoanHeader {
-                    id,
-                    date,
-                    direction,
-                    counterparty,
-                    note,
+                loans.push(LoanHistoryRow {
+                    kind: row.try_column::<&str>("kind").unwrap_or("loan").to_string(),
+                    header: LoanHeader {
+                        id,
+                        date,
+                        direction,
+                        counterparty,
+                        note,
+                        due_date,
+                    },
+                    item_count: row.try_column::<i64>("item_count").unwrap_or(0),
+                    total_quantity: row.try_column::<i64>("total_quantity").unwrap_or(0),
+                    total_value: row.try_column::<i64>("total_value").unwrap_or(0),
                 });
             }
 
-            Ok(loan_headers)
+            let next_cursor = if loans.len() > limit as usize {
+                loans.truncate(limit as usize);
+                loans.last().map(|last| LoanHistoryCursor {
+                    date: last.header.date.clone(),
+                    id: last.header.id.clone(),
+                })
+            } else {
+                None
+            };
+
+            Ok(LoanHistoryPage { loans, next_cursor })
         })
     })
     .await
@@ -410,10 +1145,10 @@ pub async fn get_loan_items(loan_id: String) -> Result<Vec<LoanItem>, String> {
             // Query to get all items for a specific loan
             let sql = format!(
                 r#"
-                SELECT id, product_name, quantity
+                SELECT id, product_name, quantity, expiry
                 FROM LoanItem
                 WHERE loan_id = '{}'
-                ORDER BY product_name
+                ORDER BY product_name, expiry
                 "#,
                 sql_quote(&loan_id)
             );
@@ -437,11 +1172,13 @@ pub async fn get_loan_items(loan_id: String) -> Result<Vec<LoanItem>, String> {
                     .try_column::<i64>("quantity")
                     .map_err(|_| "Failed to get quantity from loan item".to_string())?;
 
+                let expiry = row.try_column::<&str>("expiry").ok().map(|s| s.to_string());
+
                 loan_items.push(LoanItem {
                     id,
                     product_name,
                     quantity,
-                    expiry: None,
+                    expiry,
                 });
             }
 
@@ -469,6 +1206,7 @@ pub async fn get_loan_summary() -> Result<Vec<LoanSummary>, String> {
                     product_name,
                     p.type as product_type,
                     SUM(quantity * sign) as net_quantity,
+                    SUM(quantity * sign) * COALESCE(p.price, 0) as net_value,
                     CASE 
                         WHEN SUM(quantity * sign) > 0 THEN 'loan_out'
                         WHEN SUM(quantity * sign) < 0 THEN 'loan_in'
@@ -476,7 +1214,7 @@ pub async fn get_loan_summary() -> Result<Vec<LoanSummary>, String> {
                     END as direction
                 FROM LoanLedger
                 LEFT JOIN Product p ON LoanLedger.product_name = p.name
-                GROUP BY counterparty, product_name, p.type
+                GROUP BY counterparty, product_name, p.type, p.price
                 HAVING net_quantity != 0
                 ORDER BY counterparty, product_name
             "#;
@@ -516,9 +1254,31 @@ pub async fn get_loan_summary() -> Result<Vec<LoanSummary>, String> {
                     product_type,
                     net_quantity,
                     direction,
+                    kind: "loan".to_string(),
+                    net_value: row.try_column::<i64>("net_value").unwrap_or(0),
                 });
             }
 
+            // swaps count by value: one row per counterparty with a difference open
+            for (counterparty, balance) in exchange_balances(&client).await? {
+                loan_summaries.push(LoanSummary {
+                    counterparty,
+                    product_name: String::new(),
+                    product_type: None,
+                    net_quantity: 0,
+                    direction: if balance > 0 { "loan_out" } else { "loan_in" }.to_string(),
+                    kind: "exchange".to_string(),
+                    net_value: balance,
+                });
+            }
+            loan_summaries.sort_by(|a, b| {
+                (&a.counterparty, &a.kind, &a.product_name).cmp(&(
+                    &b.counterparty,
+                    &b.kind,
+                    &b.product_name,
+                ))
+            });
+
             Ok(loan_summaries)
         })
     })
@@ -526,6 +1286,26 @@ pub async fn get_loan_summary() -> Result<Vec<LoanSummary>, String> {
     .map_err(|e| e.to_string())?
 }
 
+/// Open loan balances per counterparty and product that are overdue or due
+/// within `within_days` days (default 7). Returns are matched against the
+/// oldest loans first, so a balance is due when any loan still open in it is.
+#[tauri::command]
+pub async fn get_loan_due_report(within_days: Option<u32>) -> Result<Vec<LoanDue>, String> {
+    let within = within_days.unwrap_or(7).min(3650) as i64;
+    task::spawn_blocking(move || {
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        rt.block_on(async move {
+            let config = get_db_config().await.map_err(|e| e.to_string())?;
+            let client = Client::from_config(config)
+                .await
+                .map_err(|e| e.to_string())?;
+            loans_due(&client, within).await
+        })
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
 #[tauri::command]
 pub async fn get_transaction_details(
     counterparty: String,
@@ -548,13 +1328,15 @@ pub async fn get_transaction_details(
                     h.id,
                     h.date,
                     h.direction,
-                    i.quantity,
+                    SUM(i.quantity) AS quantity,
+                    i.expiry,
                     h.note
                 FROM LoanHeader h
                 JOIN LoanItem i ON h.id = i.loan_id
                 WHERE h.counterparty = '{}'
                 AND i.product_name = '{}'
-                ORDER BY h.date DESC, h.id DESC
+                GROUP BY h.id, i.expiry
+                ORDER BY h.date DESC, h.id DESC, i.expiry
                 "#,
                 cp_q, pn_q
             );
@@ -579,6 +1361,7 @@ pub async fn get_transaction_details(
                 let quantity = row
                     .try_column::<i64>("quantity")
                     .map_err(|s| s.to_string())?;
+                let expiry = row.try_column::<&str>("expiry").ok().map(|s| s.to_string());
                 let note = row.try_column::<&str>("note").ok().map(|s| s.to_string());
 
                 transactions.push(TransactionDetail {
@@ -586,6 +1369,7 @@ pub async fn get_transaction_details(
                     date,
                     direction,
                     quantity,
+                    expiry,
                     note,
                 });
             }
@@ -596,3 +1380,74 @@ pub async fn get_transaction_details(
     .await
     .map_err(|e| e.to_string())?
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn item(quantity: i64) -> LoanItem {
+        LoanItem {
+            id: "line-1".to_string(),
+            product_name: "A".to_string(),
+            quantity,
+            expiry: None,
+        }
+    }
+
+    fn lots(rooms: &[(&str, i64)]) -> Vec<(String, i64)> {
+        rooms.iter().map(|(e, r)| (e.to_string(), *r)).collect()
+    }
+
+    fn pieces(items: &[LoanItem]) -> Vec<(&str, i64)> {
+        items
+            .iter()
+            .map(|it| (it.expiry.as_deref().unwrap(), it.quantity))
+            .collect()
+    }
+
+    #[test]
+    fn split_fills_lots_in_order() {
+        let mut available = lots(&[("2025-01-31", 3), ("2025-02-28", 5)]);
+        let out = split_over_lots(item(6), &mut available);
+        assert_eq!(pieces(&out), [("2025-01-31", 3), ("2025-02-28", 3)]);
+        assert_eq!(out[0].id, "line-1");
+        assert_ne!(out[1].id, "line-1");
+        assert_eq!(available, lots(&[("2025-01-31", 0), ("2025-02-28", 2)]));
+    }
+
+    #[test]
+    fn split_puts_the_excess_on_the_last_lot() {
+        let mut available = lots(&[("2025-01-31", 2), ("2025-02-28", 1)]);
+        let out = split_over_lots(item(5), &mut available);
+        assert_eq!(pieces(&out), [("2025-01-31", 2), ("2025-02-28", 3)]);
+        assert_eq!(available[1].1, -2);
+    }
+
+    #[test]
+    fn split_skips_used_up_lots() {
+        let mut available = lots(&[("2025-01-31", 2), ("2025-02-28", 4)]);
+        split_over_lots(item(2), &mut available);
+        let out = split_over_lots(item(3), &mut available);
+        assert_eq!(pieces(&out), [("2025-02-28", 3)]);
+        assert_eq!(out[0].id, "line-1");
+    }
+
+    #[test]
+    fn split_fits_a_single_lot() {
+        let mut available = lots(&[("2024-02-29", 10)]);
+        let out = split_over_lots(item(4), &mut available);
+        assert_eq!(pieces(&out), [("2024-02-29", 4)]);
+    }
+
+    #[test]
+    fn effect_signs_follow_the_direction() {
+        let mut items = vec![item(3), item(2)];
+        items[0].expiry = Some("2025-01-31".to_string());
+        items[1].expiry = Some("2025-01-31".to_string());
+        let key = ("A".to_string(), "2025-01-31".to_string());
+        assert_eq!(effect_of("loan_out", &items).unwrap()[&key], -5);
+        assert_eq!(effect_of("return_in", &items).unwrap()[&key], 5);
+        assert!(effect_of("sideways", &items).is_err());
+        assert!(effect_of("loan_in", &[item(1)]).is_err());
+    }
+}
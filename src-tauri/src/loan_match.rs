@@ -0,0 +1,705 @@
+use crate::analytics;
+use crate::db::{get_db_config, ignore_empty_baton_commit, sql_quote};
+use libsql_client::{Client, Transaction};
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use tokio::task;
+use uuid::Uuid;
+
+#[derive(Debug, Deserialize)]
+pub struct ReturnMatch {
+    pub loan_item_id: String,
+    pub quantity: i64, // > 0
+}
+
+/// A loan line a return line can settle.
+#[derive(Debug, Serialize)]
+pub struct MatchCandidate {
+    pub loan_item_id: String,
+    pub loan_id: String,
+    pub date: String,
+    pub expiry: Option<String>,
+    pub quantity: i64,
+    pub available: i64, // not settled by other return lines
+    pub matched: i64,   // settled by this return line
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LoanAging {
+    pub loan_id: String,
+    pub item_id: String,
+    pub date: String,
+    pub due_date: Option<String>,
+    pub counterparty: String,
+    pub direction: String, // "loan_in" | "loan_out"
+    pub product_name: String,
+    pub product_type: Option<String>,
+    pub quantity: i64,
+    pub returned: i64,
+    pub outstanding: i64,
+    pub days_open: i64,
+    pub days_left: Option<i64>, // until the due date; negative = overdue
+}
+
+/// One loan or return line and what is still open of it once returns are
+/// matched to loans.
+pub struct LineBalance {
+    pub loan_id: String,
+    pub item_id: String,
+    pub date: String,
+    pub due_date: Option<String>,
+    pub counterparty: String,
+    pub direction: String,
+    pub product_name: String,
+    pub product_type: Option<String>,
+    pub quantity: i64,
+    pub outstanding: i64, // signed like LoanLedger: > 0 lent out, < 0 borrowed in
+    pub loan_price: Option<i64>, // member price recorded with the line
+    pub current_price: Option<i64>,
+}
+
+/// Sign of a line in `LoanLedger`.
+fn ledger_sign(direction: &str) -> Result<i64, String> {
+    match direction {
+        "loan_out" | "return_out" => Ok(1),
+        "loan_in" | "return_in" => Ok(-1),
+        other => Err(format!("未知方向：{}", other)),
+    }
+}
+
+/// SQL for the quantity of LoanItem `alias` not yet in a `LoanMatch`.
+fn unmatched(alias: &str) -> String {
+    format!(
+        "{a}.quantity - COALESCE((SELECT SUM(m.quantity) FROM LoanMatch m
+                                  WHERE m.loan_item_id = {a}.id OR m.return_item_id = {a}.id), 0)",
+        a = alias
+    )
+}
+
+/// SQL joining return line `ri` (header `rh`) to the loan lines `li` (header
+/// `lh`) it may settle: same counterparty and product, opposite direction, not
+/// dated after the return.
+const SETTLED_LINES: &str = r#"
+    FROM LoanItem ri
+    JOIN LoanHeader rh ON rh.id = ri.loan_id
+    JOIN LoanHeader lh ON lh.counterparty = rh.counterparty
+                      AND lh.date <= rh.date
+                      AND lh.direction = CASE rh.direction
+                                           WHEN 'return_in'  THEN 'loan_out'
+                                           WHEN 'return_out' THEN 'loan_in'
+                                         END
+    JOIN LoanItem li ON li.loan_id = lh.id AND li.product_name = ri.product_name
+"#;
+
+/// Every loan and return line, per counterparty and product in date order,
+/// with its open quantity. Matched quantities settle the lines they link;
+/// whatever is left settles the oldest open lines first. The open quantities
+/// add up to the `LoanLedger` net quantity.
+pub async fn line_balances(client: &Client) -> Result<Vec<LineBalance>, String> {
+    let sql = format!(
+        r#"
+        SELECT h.id AS loan_id, i.id AS item_id, h.date, h.due_date, h.counterparty,
+               h.direction, i.product_name, p.type AS product_type, i.quantity,
+               i.unit_price, p.price AS current_price, {} AS unmatched
+        FROM LoanItem i
+        JOIN LoanHeader h ON h.id = i.loan_id
+        LEFT JOIN Product p ON p.name = i.product_name
+        ORDER BY h.counterparty, i.product_name, h.date,
+                 CASE WHEN h.direction IN ('loan_in', 'loan_out') THEN 0 ELSE 1 END,
+                 h.id, i.id
+        "#,
+        unmatched("i")
+    );
+    let result = client.execute(sql).await.map_err(|e| e.to_string())?;
+
+    let text = |row: &libsql_client::Row, col: &str| -> Result<String, String> {
+        Ok(row
+            .try_column::<&str>(col)
+            .map_err(|e| e.to_string())?
+            .to_string())
+    };
+    let mut lines: Vec<LineBalance> = Vec::new();
+    for row in result.rows {
+        let direction = text(&row, "direction")?;
+        lines.push(LineBalance {
+            loan_id: text(&row, "loan_id")?,
+            item_id: text(&row, "item_id")?,
+            date: text(&row, "date")?,
+            due_date: text(&row, "due_date").ok(),
+            counterparty: text(&row, "counterparty")?,
+            product_name: text(&row, "product_name")?,
+            product_type: text(&row, "product_type").ok(),
+            quantity: row
+                .try_column::<i64>("quantity")
+                .map_err(|e| e.to_string())?,
+            outstanding: ledger_sign(&direction)?
+                * row
+                    .try_column::<i64>("unmatched")
+                    .map_err(|e| e.to_string())?,
+            direction,
+            loan_price: row.try_column::<i64>("unit_price").ok(),
+            current_price: row.try_column::<i64>("current_price").ok(),
+        });
+    }
+    settle_fifo(&mut lines);
+    Ok(lines)
+}
+
+/// Settle the unmatched quantities in `lines` (sorted per counterparty and
+/// product in date order, `outstanding` holding the signed unmatched quantity)
+/// against each other, oldest open line first.
+fn settle_fifo(lines: &mut [LineBalance]) {
+    let mut open: VecDeque<usize> = VecDeque::new(); // indexes into `lines`
+    for i in 0..lines.len() {
+        if i > 0
+            && (&lines[i - 1].counterparty, &lines[i - 1].product_name)
+                != (&lines[i].counterparty, &lines[i].product_name)
+        {
+            open.clear();
+        }
+
+        let mut left = lines[i].outstanding;
+        while left != 0 {
+            let Some(&front) = open.front() else {
+                break;
+            };
+            let prior = &mut lines[front].outstanding;
+            if prior.signum() == left.signum() {
+                break;
+            }
+            let settled = prior.abs().min(left.abs());
+            *prior += settled * left.signum();
+            left -= settled * left.signum();
+            if *prior == 0 {
+                open.pop_front();
+            }
+        }
+        if left != 0 {
+            open.push_back(i);
+        }
+        lines[i].outstanding = left;
+    }
+}
+
+/// How much more of loan line `loan_item_id` return line `return_item_id` can
+/// settle, or None when it cannot settle that line at all.
+async fn room(
+    tx: &Transaction<'_>,
+    return_item_id: &str,
+    loan_item_id: &str,
+) -> Result<Option<i64>, String> {
+    let rs = tx
+        .execute(format!(
+            "SELECT MIN({}, {}) AS room {} WHERE ri.id = '{}' AND li.id = '{}'",
+            unmatched("ri"),
+            unmatched("li"),
+            SETTLED_LINES,
+            sql_quote(return_item_id),
+            sql_quote(loan_item_id)
+        ))
+        .await
+        .map_err(|e| e.to_string())?;
+    Ok(rs
+        .rows
+        .first()
+        .and_then(|row| row.try_column::<i64>("room").ok()))
+}
+
+async fn insert_match(
+    tx: &Transaction<'_>,
+    return_item_id: &str,
+    loan_item_id: &str,
+    quantity: i64,
+) -> Result<(), String> {
+    tx.execute(format!(
+        "INSERT INTO LoanMatch (id, return_item_id, loan_item_id, quantity)
+         VALUES ('{}', '{}', '{}', {});",
+        Uuid::new_v4(),
+        sql_quote(return_item_id),
+        sql_quote(loan_item_id),
+        quantity
+    ))
+    .await
+    .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+/// Match the unmatched quantity of every return line of `counterparty` and
+/// `product_name` to the oldest loan lines it can still settle.
+async fn match_fifo(
+    tx: &Transaction<'_>,
+    counterparty: &str,
+    product_name: &str,
+) -> Result<(), String> {
+    let returns = tx
+        .execute(format!(
+            "SELECT i.id, {} AS unmatched
+             FROM LoanItem i
+             JOIN LoanHeader h ON h.id = i.loan_id
+             WHERE h.counterparty = '{}' AND i.product_name = '{}'
+               AND h.direction IN ('return_in', 'return_out')
+             ORDER BY h.date, h.id, i.id",
+            unmatched("i"),
+            sql_quote(counterparty),
+            sql_quote(product_name)
+        ))
+        .await
+        .map_err(|e| e.to_string())?;
+
+    for row in returns.rows {
+        let return_item_id = row.try_column::<&str>("id").map_err(|e| e.to_string())?;
+        let mut left = row
+            .try_column::<i64>("unmatched")
+            .map_err(|e| e.to_string())?;
+        if left <= 0 {
+            continue;
+        }
+
+        let candidates = tx
+            .execute(format!(
+                "SELECT li.id, {} AS available {}
+                 WHERE ri.id = '{}'
+                 ORDER BY lh.date, lh.id, li.id",
+                unmatched("li"),
+                SETTLED_LINES,
+                sql_quote(return_item_id)
+            ))
+            .await
+            .map_err(|e| e.to_string())?;
+        for c in candidates.rows {
+            let available = c
+                .try_column::<i64>("available")
+                .map_err(|e| e.to_string())?;
+            let take = left.min(available);
+            if take <= 0 {
+                continue;
+            }
+            let loan_item_id = c.try_column::<&str>("id").map_err(|e| e.to_string())?;
+            insert_match(tx, return_item_id, loan_item_id, take).await?;
+            left -= take;
+            if left == 0 {
+                break;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Matches touching the lines of `loan_id`, as (return line, loan line, quantity).
+pub async fn loan_matches(
+    tx: &Transaction<'_>,
+    loan_id: &str,
+) -> Result<Vec<(String, String, i64)>, String> {
+    let rs = tx
+        .execute(format!(
+            "SELECT m.return_item_id, m.loan_item_id, m.quantity
+             FROM LoanMatch m
+             JOIN LoanItem i ON i.id = m.return_item_id OR i.id = m.loan_item_id
+             WHERE i.loan_id = '{}'",
+            sql_quote(loan_id)
+        ))
+        .await
+        .map_err(|e| e.to_string())?;
+
+    let mut out = Vec::new();
+    for row in rs.rows {
+        out.push((
+            row.try_column::<&str>("return_item_id")
+                .map_err(|e| e.to_string())?
+                .to_string(),
+            row.try_column::<&str>("loan_item_id")
+                .map_err(|e| e.to_string())?
+                .to_string(),
+            row.try_column::<i64>("quantity")
+                .map_err(|e| e.to_string())?,
+        ));
+    }
+    Ok(out)
+}
+
+/// Remove the matches touching the lines of `loan_id`.
+pub async fn clear_loan_matches(tx: &Transaction<'_>, loan_id: &str) -> Result<(), String> {
+    tx.execute(format!(
+        "DELETE FROM LoanMatch
+         WHERE return_item_id IN (SELECT id FROM LoanItem WHERE loan_id = '{id}')
+            OR loan_item_id   IN (SELECT id FROM LoanItem WHERE loan_id = '{id}');",
+        id = sql_quote(loan_id)
+    ))
+    .await
+    .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+/// After `loan_id` was written: put back the `kept` matches that still fit its
+/// lines, then match what is left of the counterparty's returns for its
+/// products first-in first-out.
+pub async fn rematch_loan(
+    tx: &Transaction<'_>,
+    loan_id: &str,
+    kept: &[(String, String, i64)],
+) -> Result<(), String> {
+    for (return_item_id, loan_item_id, quantity) in kept {
+        let take = room(tx, return_item_id, loan_item_id)
+            .await?
+            .unwrap_or(0)
+            .min(*quantity);
+        if take > 0 {
+            insert_match(tx, return_item_id, loan_item_id, take).await?;
+        }
+    }
+
+    let pairs = loan_pairs(tx, loan_id).await?;
+    rematch_pairs(tx, &pairs).await
+}
+
+/// The (counterparty, product) pairs the lines of `loan_id` belong to.
+pub async fn loan_pairs(
+    tx: &Transaction<'_>,
+    loan_id: &str,
+) -> Result<Vec<(String, String)>, String> {
+    pairs_where(tx, &format!("h.id = '{}'", sql_quote(loan_id))).await
+}
+
+/// The (counterparty, product) pairs of every loan line with `counterparty`.
+pub async fn counterparty_pairs(
+    tx: &Transaction<'_>,
+    counterparty: &str,
+) -> Result<Vec<(String, String)>, String> {
+    pairs_where(
+        tx,
+        &format!("h.counterparty = '{}'", sql_quote(counterparty)),
+    )
+    .await
+}
+
+async fn pairs_where(tx: &Transaction<'_>, cond: &str) -> Result<Vec<(String, String)>, String> {
+    let rs = tx
+        .execute(format!(
+            "SELECT DISTINCT h.counterparty, i.product_name
+             FROM LoanItem i JOIN LoanHeader h ON h.id = i.loan_id
+             WHERE {}",
+            cond
+        ))
+        .await
+        .map_err(|e| e.to_string())?;
+
+    let mut pairs = Vec::new();
+    for row in rs.rows {
+        pairs.push((
+            row.try_column::<&str>("counterparty")
+                .map_err(|e| e.to_string())?
+                .to_string(),
+            row.try_column::<&str>("product_name")
+                .map_err(|e| e.to_string())?
+                .to_string(),
+        ));
+    }
+    Ok(pairs)
+}
+
+/// Match what is left of the returns of each (counterparty, product) pair
+/// first-in first-out, e.g. after the loan lines they were matched to went away.
+pub async fn rematch_pairs(tx: &Transaction<'_>, pairs: &[(String, String)]) -> Result<(), String> {
+    for (counterparty, product_name) in pairs {
+        match_fifo(tx, counterparty, product_name).await?;
+    }
+    Ok(())
+}
+
+/// Loan lines return line `return_item_id` can settle, oldest first, with how
+/// much of each it settles now.
+#[tauri::command]
+pub async fn get_return_matches(return_item_id: String) -> Result<Vec<MatchCandidate>, String> {
+    task::spawn_blocking(move || {
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        rt.block_on(async move {
+            let config = get_db_config().await.map_err(|e| e.to_string())?;
+            let client = Client::from_config(config)
+                .await
+                .map_err(|e| e.to_string())?;
+
+            let sql = format!(
+                "SELECT li.id AS loan_item_id, lh.id AS loan_id, lh.date, li.expiry, li.quantity,
+                        li.quantity - COALESCE((SELECT SUM(m.quantity) FROM LoanMatch m
+                                                WHERE m.loan_item_id = li.id
+                                                  AND m.return_item_id <> ri.id), 0) AS available,
+                        COALESCE((SELECT SUM(m.quantity) FROM LoanMatch m
+                                  WHERE m.loan_item_id = li.id
+                                    AND m.return_item_id = ri.id), 0) AS matched
+                 {}
+                 WHERE ri.id = '{}'
+                 ORDER BY lh.date, lh.id, li.id",
+                SETTLED_LINES,
+                sql_quote(&return_item_id)
+            );
+            let result = client.execute(sql).await.map_err(|e| e.to_string())?;
+
+            let mut out = Vec::new();
+            for row in result.rows {
+                out.push(MatchCandidate {
+                    loan_item_id: row
+                        .try_column::<&str>("loan_item_id")
+                        .map_err(|e| e.to_string())?
+                        .to_string(),
+                    loan_id: row
+                        .try_column::<&str>("loan_id")
+                        .map_err(|e| e.to_string())?
+                        .to_string(),
+                    date: row
+                        .try_column::<&str>("date")
+                        .map_err(|e| e.to_string())?
+                        .to_string(),
+                    expiry: row.try_column::<&str>("expiry").ok().map(|s| s.to_string()),
+                    quantity: row
+                        .try_column::<i64>("quantity")
+                        .map_err(|e| e.to_string())?,
+                    available: row
+                        .try_column::<i64>("available")
+                        .map_err(|e| e.to_string())?,
+                    matched: row
+                        .try_column::<i64>("matched")
+                        .map_err(|e| e.to_string())?,
+                });
+            }
+            Ok(out)
+        })
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+/// Replace the loan lines return line `return_item_id` settles. `None` matches
+/// it to the oldest open loans; a quantity left unmatched settles the oldest
+/// open loans in reports.
+#[tauri::command]
+pub async fn set_return_matches(
+    return_item_id: String,
+    matches: Option<Vec<ReturnMatch>>,
+) -> Result<(), String> {
+    if let Some(m) = matches.iter().flatten().find(|m| m.quantity <= 0) {
+        return Err(format!("数量必须为正数：{}", m.quantity));
+    }
+    task::spawn_blocking(move || {
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        rt.block_on(async move {
+            let config = get_db_config().await.map_err(|e| e.to_string())?;
+            let client = Client::from_config(config)
+                .await
+                .map_err(|e| e.to_string())?;
+            let tx = client.transaction().await.map_err(|e| e.to_string())?;
+
+            let id_q = sql_quote(&return_item_id);
+            let rs = tx
+                .execute(format!(
+                    "SELECT h.direction, h.counterparty, i.product_name
+                     FROM LoanItem i JOIN LoanHeader h ON h.id = i.loan_id
+                     WHERE i.id = '{}'",
+                    id_q
+                ))
+                .await
+                .map_err(|e| e.to_string())?;
+            let row = rs.rows.first().ok_or("归还明细不存在")?;
+            let direction = row
+                .try_column::<&str>("direction")
+                .map_err(|e| e.to_string())?;
+            if !matches!(direction, "return_in" | "return_out") {
+                return Err("只有归还记录可以匹配借货".into());
+            }
+
+            tx.execute(format!(
+                "DELETE FROM LoanMatch WHERE return_item_id = '{}';",
+                id_q
+            ))
+            .await
+            .map_err(|e| e.to_string())?;
+
+            match matches {
+                None => {
+                    let counterparty = row
+                        .try_column::<&str>("counterparty")
+                        .map_err(|e| e.to_string())?;
+                    let product_name = row
+                        .try_column::<&str>("product_name")
+                        .map_err(|e| e.to_string())?;
+                    match_fifo(&tx, counterparty, product_name).await?;
+                }
+                Some(matches) => {
+                    for m in &matches {
+                        let room = room(&tx, &return_item_id, &m.loan_item_id)
+                            .await?
+                            .ok_or("该归还不能匹配所选借货")?;
+                        if m.quantity > room {
+                            return Err(format!(
+                                "匹配数量超出可匹配数量：{}（可匹配 {}）",
+                                m.quantity, room
+                            ));
+                        }
+                        insert_match(&tx, &return_item_id, &m.loan_item_id, m.quantity).await?;
+                    }
+                }
+            }
+
+            let res = tx.commit().await;
+            ignore_empty_baton_commit(res)?;
+            Ok(())
+        })
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+/// Loan lines with what has been returned of them, what is outstanding and
+/// how long they have been open, oldest first. Closed lines are left out
+/// unless `include_closed`.
+#[tauri::command]
+pub async fn get_loan_aging(
+    counterparty: Option<String>,
+    include_closed: Option<bool>,
+) -> Result<Vec<LoanAging>, String> {
+    let include_closed = include_closed.unwrap_or(false);
+    task::spawn_blocking(move || {
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        rt.block_on(async move {
+            let config = get_db_config().await.map_err(|e| e.to_string())?;
+            let client = Client::from_config(config)
+                .await
+                .map_err(|e| e.to_string())?;
+
+            let today = analytics::today();
+            let mut out = Vec::new();
+            for line in line_balances(&client).await? {
+                if !matches!(line.direction.as_str(), "loan_in" | "loan_out")
+                    || counterparty
+                        .as_deref()
+                        .is_some_and(|c| c != line.counterparty)
+                    || (line.outstanding == 0 && !include_closed)
+                {
+                    continue;
+                }
+                let days_left = match &line.due_date {
+                    Some(due) => Some((analytics::parse_date(due)? - today).num_days()),
+                    None => None,
+                };
+                out.push(LoanAging {
+                    days_open: (today - analytics::parse_date(&line.date)?).num_days(),
+                    days_left,
+                    returned: line.quantity - line.outstanding.abs(),
+                    outstanding: line.outstanding.abs(),
+                    loan_id: line.loan_id,
+                    item_id: line.item_id,
+                    date: line.date,
+                    due_date: line.due_date,
+                    counterparty: line.counterparty,
+                    direction: line.direction,
+                    product_name: line.product_name,
+                    product_type: line.product_type,
+                    quantity: line.quantity,
+                });
+            }
+
+            out.sort_by(|a, b| {
+                (&a.date, &a.counterparty, &a.product_name).cmp(&(
+                    &b.date,
+                    &b.counterparty,
+                    &b.product_name,
+                ))
+            });
+            Ok(out)
+        })
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn line(counterparty: &str, product: &str, direction: &str, unmatched: i64) -> LineBalance {
+        LineBalance {
+            loan_id: String::new(),
+            item_id: String::new(),
+            date: String::new(),
+            due_date: None,
+            counterparty: counterparty.to_string(),
+            direction: direction.to_string(),
+            product_name: product.to_string(),
+            product_type: None,
+            quantity: unmatched,
+            outstanding: ledger_sign(direction).unwrap() * unmatched,
+            loan_price: None,
+            current_price: None,
+        }
+    }
+
+    fn settled(mut lines: Vec<LineBalance>) -> Vec<i64> {
+        settle_fifo(&mut lines);
+        lines.iter().map(|l| l.outstanding).collect()
+    }
+
+    #[test]
+    fn returns_settle_the_oldest_loans_first() {
+        let lines = vec![
+            line("X", "A", "loan_out", 5),
+            line("X", "A", "loan_out", 3),
+            line("X", "A", "return_in", 6),
+        ];
+        assert_eq!(settled(lines), [0, 2, 0]);
+    }
+
+    #[test]
+    fn over_returns_stay_open_the_other_way() {
+        let lines = vec![
+            line("X", "A", "loan_out", 2),
+            line("X", "A", "return_in", 5),
+            line("X", "A", "loan_out", 4),
+        ];
+        // the extra 3 count as borrowed in until the next loan out covers them
+        assert_eq!(settled(lines), [0, 0, 1]);
+    }
+
+    #[test]
+    fn borrowed_lines_are_negative() {
+        let lines = vec![
+            line("X", "A", "loan_in", 4),
+            line("X", "A", "return_out", 1),
+        ];
+        assert_eq!(settled(lines), [-3, 0]);
+    }
+
+    #[test]
+    fn counterparties_and_products_are_kept_apart() {
+        let lines = vec![
+            line("X", "A", "loan_out", 2),
+            line("X", "B", "return_in", 2),
+            line("Y", "B", "loan_out", 1),
+        ];
+        assert_eq!(settled(lines), [2, -2, 1]);
+    }
+
+    #[test]
+    fn matched_lines_are_skipped() {
+        // the first loan is fully matched to a return already (unmatched 0)
+        let lines = vec![
+            line("X", "A", "loan_out", 0),
+            line("X", "A", "loan_out", 3),
+            line("X", "A", "return_in", 0),
+            line("X", "A", "return_in", 1),
+        ];
+        assert_eq!(settled(lines), [0, 2, 0, 0]);
+    }
+
+    #[test]
+    fn open_quantities_add_up_to_the_net() {
+        let lines = vec![
+            line("X", "A", "loan_out", 7),
+            line("X", "A", "return_in", 2),
+            line("X", "A", "loan_in", 4),
+            line("X", "A", "return_in", 3),
+            line("X", "A", "return_out", 1),
+        ];
+        let net: i64 = lines.iter().map(|l| l.outstanding).sum();
+        assert_eq!(settled(lines).iter().sum::<i64>(), net);
+    }
+}
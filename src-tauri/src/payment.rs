@@ -0,0 +1,317 @@
+use crate::db::{get_db_config, ignore_empty_baton_commit, sql_quote, to_sql_null_or_string};
+use libsql_client::{Client, Transaction};
+use serde::{Deserialize, Serialize};
+use tokio::task;
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct SalesPayment {
+    pub id: String,      // UUID from frontend
+    pub sale_id: String, // sale being paid
+    pub date: String,    // "YYYY-MM-DD"
+    pub amount: i64,     // > 0
+    pub method: Option<String>,
+    pub note: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Receivable {
+    pub customer: Option<String>,
+    pub sales_count: i64,
+    pub total_outstanding: i64,
+    pub days_0_30: i64,
+    pub days_31_60: i64,
+    pub days_61_90: i64,
+    pub days_over_90: i64,
+    pub oldest_date: String,
+}
+
+/// Net value of one sale (sales minus refunds).
+pub async fn sale_total(tx: &Transaction<'_>, sale_id: &str) -> Result<i64, String> {
+    let rs = tx
+        .execute(format!(
+            "SELECT COALESCE(SUM(amount), 0) AS total FROM SalesRevenueLedger WHERE sale_id = '{}';",
+            sql_quote(sale_id)
+        ))
+        .await
+        .map_err(|e| e.to_string())?;
+    Ok(rs
+        .rows
+        .first()
+        .and_then(|row| row.try_column::<i64>("total").ok())
+        .unwrap_or(0))
+}
+
+/// Recompute `amount_paid` and `payment_status` of one sale from its payments and
+/// its net value (sales minus refunds). Call after anything that changes either.
+pub async fn refresh_payment_status(tx: &Transaction<'_>, sale_id: &str) -> Result<(), String> {
+    let sale_id_q = sql_quote(sale_id);
+    tx.execute(format!(
+        "UPDATE SalesHeader
+         SET amount_paid = (SELECT COALESCE(SUM(amount), 0) FROM SalesPayment WHERE sale_id = '{0}')
+         WHERE id = '{0}';",
+        sale_id_q
+    ))
+    .await
+    .map_err(|e| e.to_string())?;
+    tx.execute(format!(
+        "UPDATE SalesHeader
+         SET payment_status = CASE
+               WHEN amount_paid >= (SELECT COALESCE(SUM(amount), 0)
+                                      FROM SalesRevenueLedger
+                                     WHERE sale_id = '{0}') THEN 'paid'
+               WHEN amount_paid > 0 THEN 'partial'
+               ELSE 'unpaid'
+             END
+         WHERE id = '{0}';",
+        sale_id_q
+    ))
+    .await
+    .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+/// Insert one payment row inside an existing transaction (status is not refreshed).
+pub async fn insert_payment(tx: &Transaction<'_>, payment: &SalesPayment) -> Result<(), String> {
+    if payment.amount <= 0 {
+        return Err(format!("付款金额必须为正数：{}", payment.amount));
+    }
+    let sql = format!(
+        "INSERT INTO SalesPayment (id, sale_id, date, amount, method, note)
+         VALUES ('{}', '{}', '{}', {}, {}, {});",
+        sql_quote(&payment.id),
+        sql_quote(&payment.sale_id),
+        sql_quote(&payment.date),
+        payment.amount,
+        to_sql_null_or_string(&payment.method),
+        to_sql_null_or_string(&payment.note)
+    );
+    tx.execute(sql).await.map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn record_payment(payment: SalesPayment) -> Result<(), String> {
+    task::spawn_blocking(move || {
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        rt.block_on(async move {
+            let config = get_db_config().await.map_err(|e| e.to_string())?;
+            let client = Client::from_config(config)
+                .await
+                .map_err(|e| e.to_string())?;
+
+            client
+                .execute("PRAGMA foreign_keys = ON;")
+                .await
+                .map_err(|e| e.to_string())?;
+
+            let tx = client.transaction().await.map_err(|e| e.to_string())?;
+
+            // 1) the sale must exist and still have something to pay
+            let rs = tx
+                .execute(format!(
+                    "SELECT h.amount_paid,
+                            (SELECT COALESCE(SUM(amount), 0)
+                               FROM SalesRevenueLedger
+                              WHERE sale_id = h.id) AS total
+                     FROM SalesHeader h
+                     WHERE h.id = '{}';",
+                    sql_quote(&payment.sale_id)
+                ))
+                .await
+                .map_err(|e| e.to_string())?;
+            let row = rs
+                .rows
+                .first()
+                .ok_or_else(|| format!("销售记录不存在：{}", payment.sale_id))?;
+            let paid = row.try_column::<i64>("amount_paid").unwrap_or(0);
+            let total = row.try_column::<i64>("total").unwrap_or(0);
+            if payment.amount > total - paid {
+                return Err(format!(
+                    "付款金额超出未付金额：应收 {}，已付 {}，本次 {}",
+                    total, paid, payment.amount
+                ));
+            }
+
+            // 2) insert and refresh status
+            insert_payment(&tx, &payment).await?;
+            refresh_payment_status(&tx, &payment.sale_id).await?;
+
+            let res = tx.commit().await;
+            ignore_empty_baton_commit(res)?;
+            Ok(())
+        })
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+#[tauri::command]
+pub async fn delete_payment(payment_id: String) -> Result<(), String> {
+    task::spawn_blocking(move || {
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        rt.block_on(async move {
+            let config = get_db_config().await.map_err(|e| e.to_string())?;
+            let client = Client::from_config(config)
+                .await
+                .map_err(|e| e.to_string())?;
+
+            let tx = client.transaction().await.map_err(|e| e.to_string())?;
+
+            let payment_id_q = sql_quote(&payment_id);
+            let rs = tx
+                .execute(format!(
+                    "SELECT sale_id FROM SalesPayment WHERE id = '{}';",
+                    payment_id_q
+                ))
+                .await
+                .map_err(|e| e.to_string())?;
+            let sale_id = rs
+                .rows
+                .first()
+                .ok_or_else(|| format!("付款记录不存在：{}", payment_id))?
+                .try_column::<&str>("sale_id")
+                .map_err(|e| e.to_string())?
+                .to_string();
+
+            tx.execute(format!(
+                "DELETE FROM SalesPayment WHERE id = '{}';",
+                payment_id_q
+            ))
+            .await
+            .map_err(|e| e.to_string())?;
+            refresh_payment_status(&tx, &sale_id).await?;
+
+            let res = tx.commit().await;
+            ignore_empty_baton_commit(res)?;
+            Ok(())
+        })
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+#[tauri::command]
+pub async fn get_sale_payments(sale_id: String) -> Result<Vec<SalesPayment>, String> {
+    task::spawn_blocking(move || {
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        rt.block_on(async move {
+            let config = get_db_config().await.map_err(|e| e.to_string())?;
+            let client = Client::from_config(config)
+                .await
+                .map_err(|e| e.to_string())?;
+
+            let sql = format!(
+                r#"
+                SELECT id, date, amount, method, note
+                FROM SalesPayment
+                WHERE sale_id = '{}'
+                ORDER BY date, id
+                "#,
+                sql_quote(&sale_id)
+            );
+
+            let result = client.execute(sql).await.map_err(|e| e.to_string())?;
+
+            let mut payments = Vec::new();
+            for row in result.rows {
+                let id = row
+                    .try_column::<&str>("id")
+                    .map_err(|_| "Failed to get id from payment".to_string())?
+                    .to_string();
+                let date = row
+                    .try_column::<&str>("date")
+                    .map_err(|_| "Failed to get date from payment".to_string())?
+                    .to_string();
+                let amount = row
+                    .try_column::<i64>("amount")
+                    .map_err(|_| "Failed to get amount from payment".to_string())?;
+                let method = row.try_column::<&str>("method").ok().map(|s| s.to_string());
+                let note = row.try_column::<&str>("note").ok().map(|s| s.to_string());
+
+                payments.push(SalesPayment {
+                    id,
+                    sale_id: sale_id.clone(),
+                    date,
+                    amount,
+                    method,
+                    note,
+                });
+            }
+
+            Ok(payments)
+        })
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+/// Outstanding amounts per customer, aged by sale date as of `as_of` (default today).
+#[tauri::command]
+pub async fn get_receivables(as_of: Option<String>) -> Result<Vec<Receivable>, String> {
+    let as_of = as_of.unwrap_or_else(|| chrono::Local::now().format("%Y-%m-%d").to_string());
+    task::spawn_blocking(move || {
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        rt.block_on(async move {
+            let config = get_db_config().await.map_err(|e| e.to_string())?;
+            let client = Client::from_config(config)
+                .await
+                .map_err(|e| e.to_string())?;
+
+            let sql = format!(
+                r#"
+                SELECT
+                    customer,
+                    COUNT(*) AS sales_count,
+                    SUM(outstanding) AS total_outstanding,
+                    SUM(CASE WHEN age <= 30 THEN outstanding ELSE 0 END) AS days_0_30,
+                    SUM(CASE WHEN age BETWEEN 31 AND 60 THEN outstanding ELSE 0 END) AS days_31_60,
+                    SUM(CASE WHEN age BETWEEN 61 AND 90 THEN outstanding ELSE 0 END) AS days_61_90,
+                    SUM(CASE WHEN age > 90 THEN outstanding ELSE 0 END) AS days_over_90,
+                    MIN(date) AS oldest_date
+                FROM (
+                    SELECT
+                        h.customer,
+                        h.date,
+                        CAST(julianday('{as_of}') - julianday(h.date) AS INTEGER) AS age,
+                        COALESCE((SELECT SUM(l.amount)
+                                    FROM SalesRevenueLedger l
+                                   WHERE l.sale_id = h.id), 0) - h.amount_paid AS outstanding
+                    FROM SalesHeader h
+                    WHERE h.payment_status != 'paid' AND h.date <= '{as_of}'
+                )
+                WHERE outstanding > 0
+                GROUP BY customer
+                ORDER BY total_outstanding DESC
+                "#,
+                as_of = sql_quote(&as_of)
+            );
+
+            let result = client.execute(sql).await.map_err(|e| e.to_string())?;
+
+            let mut out = Vec::new();
+            for row in result.rows {
+                let customer = row
+                    .try_column::<&str>("customer")
+                    .ok()
+                    .map(|s| s.to_string());
+                let oldest_date = row
+                    .try_column::<&str>("oldest_date")
+                    .map_err(|_| "Failed to get oldest_date".to_string())?
+                    .to_string();
+                out.push(Receivable {
+                    customer,
+                    sales_count: row.try_column::<i64>("sales_count").unwrap_or(0),
+                    total_outstanding: row.try_column::<i64>("total_outstanding").unwrap_or(0),
+                    days_0_30: row.try_column::<i64>("days_0_30").unwrap_or(0),
+                    days_31_60: row.try_column::<i64>("days_31_60").unwrap_or(0),
+                    days_61_90: row.try_column::<i64>("days_61_90").unwrap_or(0),
+                    days_over_90: row.try_column::<i64>("days_over_90").unwrap_or(0),
+                    oldest_date,
+                });
+            }
+            Ok(out)
+        })
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
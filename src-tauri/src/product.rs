@@ -1,12 +1,12 @@
 use anyhow::Result;
 use base64::{engine::general_purpose, Engine as _};
-use libsql_client::Client;
+use libsql_client::{Client, Transaction};
 use serde::{Deserialize, Serialize};
 use tokio::task;
 
 use crate::db::{
     get_db_config, ignore_empty_baton_commit, sql_quote, to_sql_null_or_blob_hex,
-    to_sql_null_or_int, to_sql_null_or_string,
+    to_sql_null_or_int, to_sql_null_or_real, to_sql_null_or_string,
 };
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -15,6 +15,92 @@ pub struct Product {
     pub price: Option<i64>,
     pub picture: Option<String>,
     pub r#type: Option<String>,
+    #[serde(default)]
+    pub cost: Option<i64>, // default purchase cost per unit
+    #[serde(default)]
+    pub pv: Option<f64>, // USANA point value per unit, currently in effect
+    #[serde(default)]
+    pub bv: Option<f64>, // USANA business value per unit, currently in effect
+    #[serde(default)]
+    pub consumption_days: Option<i64>, // days one unit lasts a customer
+}
+
+#[derive(Debug, Serialize)]
+pub struct ProductPoints {
+    pub effective_date: String, // "YYYY-MM-DD", applies until the next entry
+    pub pv: Option<f64>,
+    pub bv: Option<f64>,
+}
+
+fn check_points(pv: Option<f64>, bv: Option<f64>) -> Result<(), String> {
+    if pv.is_some_and(|v| v < 0.0) || bv.is_some_and(|v| v < 0.0) {
+        return Err("PV/BV 不能为负数。".into());
+    }
+    Ok(())
+}
+
+fn check_consumption_days(days: Option<i64>) -> Result<(), String> {
+    if days.is_some_and(|d| d <= 0) {
+        return Err("消耗周期必须为正整数天数。".into());
+    }
+    Ok(())
+}
+
+fn today() -> String {
+    chrono::Local::now()
+        .date_naive()
+        .format("%Y-%m-%d")
+        .to_string()
+}
+
+/// Record the product's current PV/BV in its history as of today, unless they
+/// match the entry already in effect. A product that never had points gets none.
+async fn record_current_points(tx: &Transaction<'_>, name: &str) -> Result<(), String> {
+    let sql = format!(
+        r#"
+        INSERT INTO ProductPointHistory (name, effective_date, pv, bv)
+        SELECT p.name, '{today}', p.pv, p.bv
+        FROM Product p
+        WHERE p.name = '{name}'
+          AND (p.pv IS NOT NULL OR p.bv IS NOT NULL
+               OR EXISTS (SELECT 1 FROM ProductPointHistory WHERE name = p.name))
+          AND NOT EXISTS (
+            SELECT 1 FROM ProductPointHistory h
+            WHERE h.name = p.name
+              AND h.effective_date = (
+                SELECT MAX(effective_date) FROM ProductPointHistory
+                WHERE name = p.name AND effective_date <= '{today}'
+              )
+              AND h.pv IS p.pv AND h.bv IS p.bv
+          )
+        ON CONFLICT(name, effective_date) DO UPDATE SET pv = excluded.pv, bv = excluded.bv
+        "#,
+        today = today(),
+        name = sql_quote(name)
+    );
+    tx.execute(sql).await.map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+/// Copy the history entry in effect today onto Product.pv/bv.
+async fn sync_current_points(tx: &Transaction<'_>, name: &str) -> Result<(), String> {
+    let sql = format!(
+        r#"
+        UPDATE Product
+           SET (pv, bv) = (
+             SELECT pv, bv FROM ProductPointHistory
+             WHERE name = Product.name AND effective_date <= '{today}'
+             ORDER BY effective_date DESC LIMIT 1
+           )
+         WHERE name = '{name}'
+           AND EXISTS (SELECT 1 FROM ProductPointHistory
+                       WHERE name = '{name}' AND effective_date <= '{today}')
+        "#,
+        today = today(),
+        name = sql_quote(name)
+    );
+    tx.execute(sql).await.map_err(|e| e.to_string())?;
+    Ok(())
 }
 
 #[tauri::command]
@@ -34,6 +120,10 @@ pub async fn get_all_products() -> Result<Vec<Product>, String> {
                    name,
                    price,
                    type,
+                   cost,
+                   pv,
+                   bv,
+                   consumption_days,
                    CASE
                      WHEN picture IS NULL OR length(picture) = 0 THEN 0
                      ELSE 1
@@ -63,6 +153,10 @@ pub async fn get_all_products() -> Result<Vec<Product>, String> {
                 };
 
                 let r#type = row.try_column::<&str>("type").ok().map(|s| s.to_string());
+                let cost = row.try_column::<i64>("cost").ok();
+                let pv = row.try_column::<f64>("pv").ok();
+                let bv = row.try_column::<f64>("bv").ok();
+                let consumption_days = row.try_column::<i64>("consumption_days").ok();
 
                 // 0/1 flag -> Some("Yes") / None (so your existing TS type still works)
                 let has_picture: i64 = row.try_column::<i64>("has_picture").unwrap_or(0);
@@ -77,6 +171,10 @@ pub async fn get_all_products() -> Result<Vec<Product>, String> {
                     price,
                     picture, // <- "Yes" or null
                     r#type,
+                    cost,
+                    pv,
+                    bv,
+                    consumption_days,
                 });
             }
 
@@ -101,7 +199,7 @@ pub async fn get_product(name: String, price: Option<i64>) -> Result<Product, St
             let escaped_name = name.replace('\'', "''");
 
             let query = format!(
-                "SELECT name, price, picture, type FROM Product WHERE name = '{}'",
+                "SELECT name, price, picture, type, cost, pv, bv, consumption_days FROM Product WHERE name = '{}'",
                 escaped_name
             );
 
@@ -130,6 +228,10 @@ pub async fn get_product(name: String, price: Option<i64>) -> Result<Product, St
             };
 
             let r#type = row.try_column::<&str>("type").ok().map(|s| s.to_string());
+            let cost = row.try_column::<i64>("cost").ok();
+            let pv = row.try_column::<f64>("pv").ok();
+            let bv = row.try_column::<f64>("bv").ok();
+            let consumption_days = row.try_column::<i64>("consumption_days").ok();
 
             let picture = row
                 .try_column::<&[u8]>("picture")
@@ -154,6 +256,10 @@ pub async fn get_product(name: String, price: Option<i64>) -> Result<Product, St
                 price: actual_price,
                 picture,
                 r#type,
+                cost,
+                pv,
+                bv,
+                consumption_days,
             })
         })
     })
@@ -253,14 +359,22 @@ pub async fn add_product(product: Product) -> Result<(), String> {
             let price = to_sql_null_or_int(product.price);
             let picture_sql = to_sql_null_or_blob_hex(&product.picture)?;
             let type_sql = to_sql_null_or_string(&product.r#type);
+            let cost_sql = to_sql_null_or_int(product.cost);
+            check_points(product.pv, product.bv)?;
+            check_consumption_days(product.consumption_days)?;
+            let pv_sql = to_sql_null_or_real(product.pv);
+            let bv_sql = to_sql_null_or_real(product.bv);
+            let days_sql = to_sql_null_or_int(product.consumption_days);
+
+            let tx = client.transaction().await.map_err(|e| e.to_string())?;
 
             // Fail if exists (unique name)
             let insert_sql = format!(
-                "INSERT INTO Product (name, price, picture, type) VALUES ('{}', {}, {}, {});",
-                name, price, picture_sql, type_sql
+                "INSERT INTO Product (name, price, picture, type, cost, pv, bv, consumption_days) VALUES ('{}', {}, {}, {}, {}, {}, {}, {});",
+                name, price, picture_sql, type_sql, cost_sql, pv_sql, bv_sql, days_sql
             );
 
-            let res = client
+            let res = tx
                 .execute(insert_sql)
                 .await
                 .map_err(|e| e.to_string())?;
@@ -270,6 +384,10 @@ pub async fn add_product(product: Product) -> Result<(), String> {
                 return Err("插入失败：未影响任何行。".into());
             }
 
+            record_current_points(&tx, &product.name).await?;
+
+            let commit_res = tx.commit().await;
+            ignore_empty_baton_commit(commit_res)?;
             Ok(())
         })
     })
@@ -313,6 +431,12 @@ pub async fn update_product(args: UpdateProductArgs) -> Result<(), String> {
             let price_sql = to_sql_null_or_int(product.price);
             let picture_sql = to_sql_null_or_blob_hex(&product.picture)?;
             let type_sql = to_sql_null_or_string(&product.r#type);
+            let cost_sql = to_sql_null_or_int(product.cost);
+            check_points(product.pv, product.bv)?;
+            check_consumption_days(product.consumption_days)?;
+            let pv_sql = to_sql_null_or_real(product.pv);
+            let bv_sql = to_sql_null_or_real(product.bv);
+            let days_sql = to_sql_null_or_int(product.consumption_days);
 
             // 3) checks inside the tx
             // ensure the original row exists
@@ -347,16 +471,26 @@ pub async fn update_product(args: UpdateProductArgs) -> Result<(), String> {
             let sql = if is_renaming {
                 format!(
                     "UPDATE Product
-                       SET name='{}', price={}, picture={}, type={}
+                       SET name='{}', price={}, picture={}, type={}, cost={}, pv={}, bv={},
+                           consumption_days={}
                      WHERE name='{}';",
-                    new_q, price_sql, picture_sql, type_sql, old_q
+                    new_q,
+                    price_sql,
+                    picture_sql,
+                    type_sql,
+                    cost_sql,
+                    pv_sql,
+                    bv_sql,
+                    days_sql,
+                    old_q
                 )
             } else {
                 format!(
                     "UPDATE Product
-                       SET price={}, picture={}, type={}
+                       SET price={}, picture={}, type={}, cost={}, pv={}, bv={},
+                           consumption_days={}
                      WHERE name='{}';",
-                    price_sql, picture_sql, type_sql, old_q
+                    price_sql, picture_sql, type_sql, cost_sql, pv_sql, bv_sql, days_sql, old_q
                 )
             };
 
@@ -365,6 +499,9 @@ pub async fn update_product(args: UpdateProductArgs) -> Result<(), String> {
                 return Err("更新失败：未影响任何行。".into());
             }
 
+            // a PV/BV change starts a new history entry from today
+            record_current_points(&tx, &product.name).await?;
+
             // 5) commit (use your helper if you have it)
             // If you have `ignore_empty_baton_commit`, use it like your example:
             // let commit_res = tx.commit().await;
@@ -377,3 +514,133 @@ pub async fn update_product(args: UpdateProductArgs) -> Result<(), String> {
     .await
     .map_err(|e| e.to_string())?
 }
+
+#[tauri::command]
+pub async fn get_product_point_history(name: String) -> Result<Vec<ProductPoints>, String> {
+    task::spawn_blocking(move || {
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        rt.block_on(async move {
+            let config = get_db_config().await.map_err(|e| e.to_string())?;
+            let client = Client::from_config(config)
+                .await
+                .map_err(|e| e.to_string())?;
+
+            let result = client
+                .execute(format!(
+                    "SELECT effective_date, pv, bv FROM ProductPointHistory
+                     WHERE name = '{}' ORDER BY effective_date DESC",
+                    sql_quote(&name)
+                ))
+                .await
+                .map_err(|e| e.to_string())?;
+
+            let mut history = Vec::new();
+            for row in result.rows {
+                history.push(ProductPoints {
+                    effective_date: row
+                        .try_column::<&str>("effective_date")
+                        .map_err(|e| e.to_string())?
+                        .to_string(),
+                    pv: row.try_column::<f64>("pv").ok(),
+                    bv: row.try_column::<f64>("bv").ok(),
+                });
+            }
+            Ok(history)
+        })
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+/// Set the PV/BV that apply from `effective_date` (past dates re-value the sales
+/// made since then). The product's current values follow the entry in effect today.
+#[tauri::command]
+pub async fn set_product_points(
+    name: String,
+    effective_date: String,
+    pv: Option<f64>,
+    bv: Option<f64>,
+) -> Result<(), String> {
+    chrono::NaiveDate::parse_from_str(&effective_date, "%Y-%m-%d")
+        .map_err(|_| format!("无效的日期：{}", effective_date))?;
+    check_points(pv, bv)?;
+
+    task::spawn_blocking(move || {
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        rt.block_on(async move {
+            let config = get_db_config().await.map_err(|e| e.to_string())?;
+            let client = Client::from_config(config)
+                .await
+                .map_err(|e| e.to_string())?;
+            let tx = client.transaction().await.map_err(|e| e.to_string())?;
+
+            let exists = tx
+                .execute(format!(
+                    "SELECT 1 FROM Product WHERE name = '{}' LIMIT 1",
+                    sql_quote(&name)
+                ))
+                .await
+                .map_err(|e| e.to_string())?;
+            if exists.rows.is_empty() {
+                return Err(format!("产品不存在：{}", name));
+            }
+
+            tx.execute(format!(
+                "INSERT INTO ProductPointHistory (name, effective_date, pv, bv)
+                 VALUES ('{}', '{}', {}, {})
+                 ON CONFLICT(name, effective_date) DO UPDATE SET pv = excluded.pv, bv = excluded.bv",
+                sql_quote(&name),
+                effective_date,
+                to_sql_null_or_real(pv),
+                to_sql_null_or_real(bv)
+            ))
+            .await
+            .map_err(|e| e.to_string())?;
+
+            sync_current_points(&tx, &name).await?;
+
+            let commit_res = tx.commit().await;
+            ignore_empty_baton_commit(commit_res)?;
+            Ok(())
+        })
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+#[tauri::command]
+pub async fn delete_product_points(name: String, effective_date: String) -> Result<(), String> {
+    task::spawn_blocking(move || {
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        rt.block_on(async move {
+            let config = get_db_config().await.map_err(|e| e.to_string())?;
+            let client = Client::from_config(config)
+                .await
+                .map_err(|e| e.to_string())?;
+            let tx = client.transaction().await.map_err(|e| e.to_string())?;
+
+            let res = tx
+                .execute(format!(
+                    "DELETE FROM ProductPointHistory WHERE name = '{}' AND effective_date = '{}'",
+                    sql_quote(&name),
+                    sql_quote(&effective_date)
+                ))
+                .await
+                .map_err(|e| e.to_string())?;
+            if res.rows_affected == 0 {
+                return Err(format!(
+                    "未找到 {} 在 {} 的积分记录。",
+                    name, effective_date
+                ));
+            }
+
+            sync_current_points(&tx, &name).await?;
+
+            let commit_res = tx.commit().await;
+            ignore_empty_baton_commit(commit_res)?;
+            Ok(())
+        })
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
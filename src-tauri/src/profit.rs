@@ -0,0 +1,165 @@
+use crate::db::{get_db_config, sql_quote};
+use libsql_client::Client;
+use serde::Serialize;
+use tokio::task;
+
+#[derive(Debug, Default, Serialize)]
+pub struct ProfitLine {
+    pub key: Option<String>, // sale id, product, "YYYY-MM" or customer; None = total / no customer
+    pub date: Option<String>, // sale date, only when grouped by sale
+    pub customer: Option<String>, // only when grouped by sale
+    pub quantity: i64,       // sold minus returned
+    pub revenue: i64,        // sales minus refunds
+    pub cost: i64,           // purchase cost of the lots sold, minus restocked returns
+    pub gross_profit: i64,   // revenue - cost
+    pub margin: Option<f64>, // gross_profit / revenue in %, None when revenue is 0
+    pub uncosted_quantity: i64, // units whose lot has no known cost (not in `cost`)
+}
+
+#[derive(Debug, Serialize)]
+pub struct ProfitReport {
+    pub lines: Vec<ProfitLine>,
+    pub total: ProfitLine,
+}
+
+fn margin(profit: i64, revenue: i64) -> Option<f64> {
+    if revenue == 0 {
+        return None;
+    }
+    Some((profit as f64 * 1000.0 / revenue as f64).round() / 10.0)
+}
+
+/// Gross profit per "sale", "product", "month" or "customer" between two dates.
+/// Each sold unit costs what its lot was bought for (`LotCost`, matched on
+/// product + expiry), else the product's default cost. Returns put the cost back
+/// when restocked; written-off returns keep it.
+#[tauri::command]
+pub async fn get_profit_report(
+    group_by: String,
+    date_from: Option<String>,
+    date_to: Option<String>,
+) -> Result<ProfitReport, String> {
+    let (key_expr, order) = match group_by.as_str() {
+        "sale" => ("sale_id", "MIN(sale_date) DESC, group_key DESC"),
+        "product" => ("product_name", "gross_profit DESC, group_key"),
+        "month" => ("substr(date, 1, 7)", "group_key"),
+        "customer" => ("customer", "gross_profit DESC, group_key"),
+        other => return Err(format!("不支持的分组方式：{}", other)),
+    };
+
+    let mut conds: Vec<String> = Vec::new();
+    if let Some(from) = date_from.as_deref().filter(|s| !s.is_empty()) {
+        conds.push(format!("date >= '{}'", sql_quote(from)));
+    }
+    if let Some(to) = date_to.as_deref().filter(|s| !s.is_empty()) {
+        conds.push(format!("date <= '{}'", sql_quote(to)));
+    }
+    let where_clause = if conds.is_empty() {
+        String::new()
+    } else {
+        format!("WHERE {}", conds.join(" AND "))
+    };
+
+    task::spawn_blocking(move || {
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        rt.block_on(async move {
+            let config = get_db_config().await.map_err(|e| e.to_string())?;
+            let client = Client::from_config(config)
+                .await
+                .map_err(|e| e.to_string())?;
+
+            // Sale lines and return lines side by side; `cost_quantity` is the
+            // number of units whose cost counts (negative = cost given back).
+            let sql = format!(
+                r#"
+                WITH lines AS (
+                    SELECT h.id AS sale_id, h.date AS sale_date, h.date AS date,
+                           h.customer AS customer, i.product_name AS product_name,
+                           i.quantity AS quantity,
+                           i.quantity * COALESCE(i.price, p.price, 0) AS revenue,
+                           COALESCE(lc.unit_cost, p.cost) AS unit_cost,
+                           i.quantity AS cost_quantity
+                    FROM SalesItem i
+                    JOIN SalesHeader h ON h.id = i.sale_id
+                    LEFT JOIN Product p ON p.name = i.product_name
+                    LEFT JOIN LotCost lc ON lc.name = i.product_name AND lc.expiry = i.expiry
+
+                    UNION ALL
+
+                    SELECT h.id, h.date, r.date,
+                           h.customer, ri.product_name,
+                           -ri.quantity,
+                           -ri.refund,
+                           COALESCE(lc.unit_cost, p.cost),
+                           CASE WHEN ri.damaged THEN 0 ELSE -ri.quantity END
+                    FROM SalesReturnItem ri
+                    JOIN SalesReturnHeader r ON r.id = ri.return_id
+                    JOIN SalesHeader h ON h.id = r.sale_id
+                    LEFT JOIN Product p ON p.name = ri.product_name
+                    LEFT JOIN LotCost lc ON lc.name = ri.product_name AND lc.expiry = ri.expiry
+                )
+                SELECT {key} AS group_key,
+                       MIN(sale_date) AS sale_date,
+                       MAX(customer) AS customer,
+                       CAST(SUM(quantity) AS INTEGER) AS quantity,
+                       CAST(SUM(revenue) AS INTEGER) AS revenue,
+                       CAST(SUM(CASE WHEN unit_cost IS NOT NULL
+                                     THEN cost_quantity * unit_cost ELSE 0 END) AS INTEGER) AS cost,
+                       CAST(SUM(CASE WHEN unit_cost IS NULL
+                                     THEN cost_quantity ELSE 0 END) AS INTEGER) AS uncosted_quantity,
+                       CAST(SUM(revenue) - SUM(CASE WHEN unit_cost IS NOT NULL
+                                     THEN cost_quantity * unit_cost ELSE 0 END) AS INTEGER) AS gross_profit
+                FROM lines
+                {where_clause}
+                GROUP BY group_key
+                ORDER BY {order}
+                "#,
+                key = key_expr,
+                where_clause = where_clause,
+                order = order
+            );
+
+            let result = client.execute(sql).await.map_err(|e| e.to_string())?;
+
+            let by_sale = group_by == "sale";
+            let mut total = ProfitLine::default();
+            let mut lines = Vec::new();
+            for row in result.rows {
+                let revenue = row.try_column::<i64>("revenue").unwrap_or(0);
+                let cost = row.try_column::<i64>("cost").unwrap_or(0);
+                let line = ProfitLine {
+                    key: row
+                        .try_column::<&str>("group_key")
+                        .ok()
+                        .map(|s| s.to_string()),
+                    date: by_sale
+                        .then(|| row.try_column::<&str>("sale_date").ok())
+                        .flatten()
+                        .map(|s| s.to_string()),
+                    customer: by_sale
+                        .then(|| row.try_column::<&str>("customer").ok())
+                        .flatten()
+                        .map(|s| s.to_string()),
+                    quantity: row.try_column::<i64>("quantity").unwrap_or(0),
+                    revenue,
+                    cost,
+                    gross_profit: revenue - cost,
+                    margin: margin(revenue - cost, revenue),
+                    uncosted_quantity: row.try_column::<i64>("uncosted_quantity").unwrap_or(0),
+                };
+
+                total.quantity += line.quantity;
+                total.revenue += line.revenue;
+                total.cost += line.cost;
+                total.uncosted_quantity += line.uncosted_quantity;
+                lines.push(line);
+            }
+            total.gross_profit = total.revenue - total.cost;
+            total.margin = margin(total.gross_profit, total.revenue);
+
+            Ok(ProfitReport { lines, total })
+        })
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
@@ -0,0 +1,595 @@
+use crate::config::config;
+use crate::db::{get_db_config, sql_quote};
+use libsql_client::Client;
+use std::fmt::Write as _;
+use std::path::Path;
+use tokio::task;
+
+struct ReceiptLine {
+    product_name: String,
+    expiry: String,
+    quantity: i64,
+    unit_price: i64,
+    amount: i64,
+}
+
+struct Receipt {
+    business_name: String,
+    footer: String,
+    sale_id: String,
+    date: String,
+    customer: Option<String>,
+    note: Option<String>,
+    payment_method: Option<String>,
+    amount_paid: i64,
+    refunded: i64,
+    lines: Vec<ReceiptLine>,
+}
+
+impl Receipt {
+    fn subtotal(&self) -> i64 {
+        self.lines.iter().map(|l| l.amount).sum()
+    }
+
+    fn total(&self) -> i64 {
+        self.subtotal() - self.refunded
+    }
+
+    /// Short receipt number shown to customers (first block of the sale UUID).
+    fn number(&self) -> &str {
+        self.sale_id.split('-').next().unwrap_or(&self.sale_id)
+    }
+
+    /// (label, value) rows printed under the item table.
+    fn totals(&self) -> Vec<(&'static str, String)> {
+        let mut rows = vec![("合计", self.subtotal().to_string())];
+        if self.refunded != 0 {
+            rows.push(("退款", format!("-{}", self.refunded)));
+            rows.push(("实收合计", self.total().to_string()));
+        }
+        rows.push(("已付", self.amount_paid.to_string()));
+        let outstanding = self.total() - self.amount_paid;
+        if outstanding > 0 {
+            rows.push(("未付", outstanding.to_string()));
+        }
+        rows
+    }
+}
+
+async fn load_receipt(sale_id: &str) -> Result<Receipt, String> {
+    let (business_name, footer) = {
+        let cfg = config().map_err(|e| e.to_string())?;
+        (cfg.business_name.clone(), cfg.receipt_footer.clone())
+    };
+
+    let config = get_db_config().await.map_err(|e| e.to_string())?;
+    let client = Client::from_config(config)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    let sale_id_q = sql_quote(sale_id);
+
+    let header = client
+        .execute(format!(
+            "SELECT h.date, h.note, h.customer, h.payment_method, h.amount_paid,
+                    (SELECT COALESCE(SUM(ri.refund), 0)
+                       FROM SalesReturnItem ri
+                       JOIN SalesReturnHeader r ON r.id = ri.return_id
+                      WHERE r.sale_id = h.id) AS refunded
+             FROM SalesHeader h
+             WHERE h.id = '{}';",
+            sale_id_q
+        ))
+        .await
+        .map_err(|e| e.to_string())?;
+    let row = header
+        .rows
+        .first()
+        .ok_or_else(|| format!("销售记录不存在：{}", sale_id))?;
+
+    let date = row
+        .try_column::<&str>("date")
+        .map_err(|_| "Failed to get date from sales header".to_string())?
+        .to_string();
+    let note = row.try_column::<&str>("note").ok().map(|s| s.to_string());
+    let customer = row
+        .try_column::<&str>("customer")
+        .ok()
+        .map(|s| s.to_string());
+    let payment_method = row
+        .try_column::<&str>("payment_method")
+        .ok()
+        .map(|s| s.to_string());
+    let amount_paid = row.try_column::<i64>("amount_paid").unwrap_or(0);
+    let refunded = row.try_column::<i64>("refunded").unwrap_or(0);
+
+    let items = client
+        .execute(format!(
+            "SELECT i.product_name, i.expiry, i.quantity,
+                    COALESCE(i.price, p.price, 0) AS unit_price
+             FROM SalesItem i
+             LEFT JOIN Product p ON p.name = i.product_name
+             WHERE i.sale_id = '{}'
+             ORDER BY i.product_name, i.expiry;",
+            sale_id_q
+        ))
+        .await
+        .map_err(|e| e.to_string())?;
+
+    let mut lines = Vec::new();
+    for row in items.rows {
+        let product_name = row
+            .try_column::<&str>("product_name")
+            .map_err(|_| "Failed to get product_name from sales item".to_string())?
+            .to_string();
+        let expiry = row
+            .try_column::<&str>("expiry")
+            .map_err(|_| "Failed to get expiry from sales item".to_string())?
+            .to_string();
+        let quantity = row
+            .try_column::<i64>("quantity")
+            .map_err(|_| "Failed to get quantity from sales item".to_string())?;
+        let unit_price = row.try_column::<i64>("unit_price").unwrap_or(0);
+        lines.push(ReceiptLine {
+            product_name,
+            expiry,
+            quantity,
+            unit_price,
+            amount: quantity * unit_price,
+        });
+    }
+
+    Ok(Receipt {
+        business_name,
+        footer,
+        sale_id: sale_id.to_string(),
+        date,
+        customer,
+        note,
+        payment_method,
+        amount_paid,
+        refunded,
+        lines,
+    })
+}
+
+/// Render the sale to `path`; the format follows the extension (.html / .pdf).
+#[tauri::command]
+pub async fn export_sale_receipt(sale_id: String, path: String) -> Result<(), String> {
+    let ext = Path::new(&path)
+        .extension()
+        .and_then(|e| e.to_str())
+        .map(|e| e.to_ascii_lowercase())
+        .unwrap_or_default();
+    if !matches!(ext.as_str(), "html" | "htm" | "pdf") {
+        return Err("仅支持导出 .html 或 .pdf 文件".into());
+    }
+
+    let receipt = task::spawn_blocking(move || {
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        rt.block_on(load_receipt(&sale_id))
+    })
+    .await
+    .map_err(|e| e.to_string())??;
+
+    let bytes = if ext == "pdf" {
+        render_pdf(&receipt)
+    } else {
+        render_html(&receipt).into_bytes()
+    };
+    std::fs::write(&path, bytes).map_err(|e| format!("写入文件失败 {}：{}", path, e))
+}
+
+// ---- HTML ----------------------------------------------------------------
+
+fn html_escape(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+fn render_html(r: &Receipt) -> String {
+    let mut rows = String::new();
+    for l in &r.lines {
+        let _ = write!(
+            rows,
+            "<tr><td>{}</td><td>{}</td><td class=\"num\">{}</td><td class=\"num\">{}</td><td class=\"num\">{}</td></tr>",
+            html_escape(&l.product_name),
+            html_escape(&l.expiry),
+            l.quantity,
+            l.unit_price,
+            l.amount
+        );
+    }
+
+    let mut totals = String::new();
+    for (label, value) in r.totals() {
+        let _ = write!(
+            totals,
+            "<tr><td colspan=\"4\" class=\"num\">{}</td><td class=\"num\">{}</td></tr>",
+            label, value
+        );
+    }
+
+    let mut meta = format!(
+        "<div>单号：{}</div><div>日期：{}</div>",
+        html_escape(r.number()),
+        html_escape(&r.date)
+    );
+    if let Some(c) = &r.customer {
+        let _ = write!(meta, "<div>客户：{}</div>", html_escape(c));
+    }
+    if let Some(m) = &r.payment_method {
+        let _ = write!(meta, "<div>付款方式：{}</div>", html_escape(m));
+    }
+    let note = r
+        .note
+        .as_deref()
+        .map(|n| format!("<p class=\"note\">备注：{}</p>", html_escape(n)))
+        .unwrap_or_default();
+
+    format!(
+        r#"<!DOCTYPE html>
+<html lang="zh-CN">
+<head>
+<meta charset="utf-8">
+<title>收据 {number}</title>
+<style>
+  body {{ font-family: "PingFang SC", "Microsoft YaHei", sans-serif; max-width: 720px; margin: 24px auto; color: #222; }}
+  h1 {{ text-align: center; margin: 0 0 4px; }}
+  h2 {{ text-align: center; margin: 0 0 16px; font-weight: normal; }}
+  .meta {{ display: flex; flex-wrap: wrap; gap: 4px 24px; margin-bottom: 12px; }}
+  table {{ width: 100%; border-collapse: collapse; }}
+  th, td {{ padding: 6px 4px; border-bottom: 1px solid #ddd; text-align: left; }}
+  .num {{ text-align: right; }}
+  .note {{ margin-top: 12px; }}
+  .footer {{ margin-top: 24px; text-align: center; color: #666; }}
+  @media print {{ body {{ margin: 0; }} }}
+</style>
+</head>
+<body>
+<h1>{business}</h1>
+<h2>收据</h2>
+<div class="meta">{meta}</div>
+<table>
+<thead><tr><th>产品</th><th>有效期</th><th class="num">数量</th><th class="num">单价</th><th class="num">金额</th></tr></thead>
+<tbody>{rows}</tbody>
+<tfoot>{totals}</tfoot>
+</table>
+{note}
+<div class="footer">{footer}</div>
+</body>
+</html>
+"#,
+        number = html_escape(r.number()),
+        business = html_escape(&r.business_name),
+        meta = meta,
+        rows = rows,
+        totals = totals,
+        note = note,
+        footer = html_escape(&r.footer),
+    )
+}
+
+// ---- PDF -----------------------------------------------------------------
+//
+// A deliberately small PDF writer: one font, text and rules only. It uses the
+// Adobe-GB1 CID font "STSong-Light", which PDF readers supply themselves, so
+// Chinese text renders without shipping or embedding a font file.
+
+pub(crate) const PAGE_W: f64 = 595.0; // A4
+pub(crate) const PAGE_H: f64 = 842.0;
+pub(crate) const MARGIN: f64 = 50.0;
+pub(crate) const FONT_SIZE: f64 = 10.5;
+pub(crate) const LINE_H: f64 = 18.0;
+
+/// Advance width in points: ASCII is half width (CIDs 1-95), everything else full width.
+pub(crate) fn text_width(s: &str, size: f64) -> f64 {
+    s.chars()
+        .map(|c| if c.is_ascii() { 0.5 } else { 1.0 })
+        .sum::<f64>()
+        * size
+}
+
+/// Shorten `s` with an ellipsis so it fits into `max` points.
+pub(crate) fn fit_text(s: &str, size: f64, max: f64) -> String {
+    if text_width(s, size) <= max {
+        return s.to_string();
+    }
+    let mut out = String::new();
+    for c in s.chars() {
+        if text_width(&out, size) + text_width(&c.to_string(), size) + size > max {
+            break;
+        }
+        out.push(c);
+    }
+    out.push('…');
+    out
+}
+
+/// UTF-16BE hex string for the UniGB-UCS2-H encoding.
+fn pdf_hex(s: &str) -> String {
+    let mut out = String::from("<");
+    for u in s.encode_utf16() {
+        let _ = write!(out, "{:04X}", u);
+    }
+    out.push('>');
+    out
+}
+
+#[derive(Default)]
+pub(crate) struct PdfPages {
+    pub(crate) pages: Vec<String>,
+    pub(crate) y: f64,
+}
+
+impl PdfPages {
+    pub(crate) fn new_page(&mut self) {
+        self.pages.push(String::new());
+        self.y = PAGE_H - MARGIN;
+    }
+
+    fn ops(&mut self) -> &mut String {
+        self.pages.last_mut().expect("page started")
+    }
+
+    pub(crate) fn text(&mut self, x: f64, size: f64, s: &str) {
+        let y = self.y;
+        let _ = writeln!(
+            self.ops(),
+            "BT /F1 {:.1} Tf {:.2} {:.2} Td {} Tj ET",
+            size,
+            x,
+            y,
+            pdf_hex(s)
+        );
+    }
+
+    pub(crate) fn text_right(&mut self, right: f64, size: f64, s: &str) {
+        self.text(right - text_width(s, size), size, s);
+    }
+
+    pub(crate) fn text_center(&mut self, size: f64, s: &str) {
+        self.text((PAGE_W - text_width(s, size)) / 2.0, size, s);
+    }
+
+    pub(crate) fn rule(&mut self) {
+        let y = self.y + LINE_H * 0.6;
+        let _ = writeln!(
+            self.ops(),
+            "0.5 w {:.2} {:.2} m {:.2} {:.2} l S",
+            MARGIN,
+            y,
+            PAGE_W - MARGIN,
+            y
+        );
+    }
+
+    pub(crate) fn next_line(&mut self, lines: f64) {
+        self.y -= LINE_H * lines;
+    }
+}
+
+// column positions of the item table
+const COL_PRODUCT: f64 = MARGIN;
+const COL_EXPIRY: f64 = 270.0;
+const COL_QTY_RIGHT: f64 = 380.0;
+const COL_PRICE_RIGHT: f64 = 460.0;
+const COL_AMOUNT_RIGHT: f64 = PAGE_W - MARGIN;
+
+fn table_header(p: &mut PdfPages) {
+    p.text(COL_PRODUCT, FONT_SIZE, "产品");
+    p.text(COL_EXPIRY, FONT_SIZE, "有效期");
+    p.text_right(COL_QTY_RIGHT, FONT_SIZE, "数量");
+    p.text_right(COL_PRICE_RIGHT, FONT_SIZE, "单价");
+    p.text_right(COL_AMOUNT_RIGHT, FONT_SIZE, "金额");
+    p.next_line(1.0);
+    p.rule();
+}
+
+fn render_pdf(r: &Receipt) -> Vec<u8> {
+    let mut p = PdfPages::default();
+    p.new_page();
+
+    if !r.business_name.is_empty() {
+        p.text_center(18.0, &r.business_name);
+        p.next_line(1.6);
+    }
+    p.text_center(14.0, "收据");
+    p.next_line(1.8);
+
+    let mut meta = vec![format!("单号：{}", r.number()), format!("日期：{}", r.date)];
+    if let Some(c) = &r.customer {
+        meta.push(format!("客户：{}", c));
+    }
+    if let Some(m) = &r.payment_method {
+        meta.push(format!("付款方式：{}", m));
+    }
+    for m in meta {
+        p.text(MARGIN, FONT_SIZE, &m);
+        p.next_line(1.0);
+    }
+    p.next_line(0.5);
+
+    table_header(&mut p);
+    for l in &r.lines {
+        if p.y < MARGIN + LINE_H * 2.0 {
+            p.new_page();
+            table_header(&mut p);
+        }
+        let name = fit_text(&l.product_name, FONT_SIZE, COL_EXPIRY - COL_PRODUCT - 10.0);
+        p.text(COL_PRODUCT, FONT_SIZE, &name);
+        p.text(COL_EXPIRY, FONT_SIZE, &l.expiry);
+        p.text_right(COL_QTY_RIGHT, FONT_SIZE, &l.quantity.to_string());
+        p.text_right(COL_PRICE_RIGHT, FONT_SIZE, &l.unit_price.to_string());
+        p.text_right(COL_AMOUNT_RIGHT, FONT_SIZE, &l.amount.to_string());
+        p.next_line(1.0);
+    }
+    p.rule();
+
+    let totals = r.totals();
+    let mut tail = totals.len() as f64 + 3.0;
+    if r.note.is_some() {
+        tail += 1.5;
+    }
+    if p.y < MARGIN + LINE_H * tail {
+        p.new_page();
+    }
+    for (label, value) in totals {
+        p.text_right(COL_PRICE_RIGHT, FONT_SIZE, label);
+        p.text_right(COL_AMOUNT_RIGHT, FONT_SIZE, &value);
+        p.next_line(1.0);
+    }
+    if let Some(n) = &r.note {
+        p.next_line(0.5);
+        let note = fit_text(&format!("备注：{}", n), FONT_SIZE, PAGE_W - 2.0 * MARGIN);
+        p.text(MARGIN, FONT_SIZE, &note);
+        p.next_line(1.0);
+    }
+    if !r.footer.is_empty() {
+        p.next_line(1.0);
+        p.text_center(9.0, &r.footer);
+    }
+
+    write_pdf(&p.pages)
+}
+
+/// Serialize page content streams into a complete PDF file.
+pub(crate) fn write_pdf(pages: &[String]) -> Vec<u8> {
+    // fixed objects: 1 catalog, 2 page tree, 3 font, 4 CID font, 5 font descriptor;
+    // then a (page, content) pair per page
+    let page_ids: Vec<usize> = (0..pages.len()).map(|i| 6 + i * 2).collect();
+    let kids: Vec<String> = page_ids.iter().map(|id| format!("{} 0 R", id)).collect();
+
+    let mut objects: Vec<Vec<u8>> = vec![
+        b"<< /Type /Catalog /Pages 2 0 R >>".to_vec(),
+        format!(
+            "<< /Type /Pages /Kids [{}] /Count {} >>",
+            kids.join(" "),
+            pages.len()
+        )
+        .into_bytes(),
+        b"<< /Type /Font /Subtype /Type0 /BaseFont /STSong-Light /Encoding /UniGB-UCS2-H /DescendantFonts [4 0 R] >>".to_vec(),
+        b"<< /Type /Font /Subtype /CIDFontType0 /BaseFont /STSong-Light /CIDSystemInfo << /Registry (Adobe) /Ordering (GB1) /Supplement 2 >> /FontDescriptor 5 0 R /DW 1000 /W [1 95 500] >>".to_vec(),
+        b"<< /Type /FontDescriptor /FontName /STSong-Light /Flags 6 /FontBBox [-25 -254 1000 880] /ItalicAngle 0 /Ascent 880 /Descent -120 /CapHeight 880 /StemV 93 >>".to_vec(),
+    ];
+    for (i, content) in pages.iter().enumerate() {
+        objects.push(
+            format!(
+                "<< /Type /Page /Parent 2 0 R /MediaBox [0 0 {} {}] /Resources << /Font << /F1 3 0 R >> >> /Contents {} 0 R >>",
+                PAGE_W,
+                PAGE_H,
+                page_ids[i] + 1
+            )
+            .into_bytes(),
+        );
+        let mut stream = format!("<< /Length {} >>\nstream\n", content.len()).into_bytes();
+        stream.extend_from_slice(content.as_bytes());
+        stream.extend_from_slice(b"\nendstream");
+        objects.push(stream);
+    }
+
+    let mut out: Vec<u8> = b"%PDF-1.4\n".to_vec();
+    let mut offsets = Vec::with_capacity(objects.len());
+    for (i, obj) in objects.iter().enumerate() {
+        offsets.push(out.len());
+        out.extend_from_slice(format!("{} 0 obj\n", i + 1).as_bytes());
+        out.extend_from_slice(obj);
+        out.extend_from_slice(b"\nendobj\n");
+    }
+
+    let xref_at = out.len();
+    let mut xref = format!("xref\n0 {}\n0000000000 65535 f \n", objects.len() + 1);
+    for off in offsets {
+        let _ = writeln!(xref, "{:010} 00000 n ", off);
+    }
+    let _ = write!(
+        xref,
+        "trailer\n<< /Size {} /Root 1 0 R >>\nstartxref\n{}\n%%EOF\n",
+        objects.len() + 1,
+        xref_at
+    );
+    out.extend_from_slice(xref.as_bytes());
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn receipt(amounts: &[i64], refunded: i64, amount_paid: i64) -> Receipt {
+        Receipt {
+            business_name: String::new(),
+            footer: String::new(),
+            sale_id: "1a2b3c4d-0000-0000-0000-000000000000".to_string(),
+            date: "2025-03-01".to_string(),
+            customer: None,
+            note: None,
+            payment_method: None,
+            amount_paid,
+            refunded,
+            lines: amounts
+                .iter()
+                .map(|&amount| ReceiptLine {
+                    product_name: "A".to_string(),
+                    expiry: "2026-01-31".to_string(),
+                    quantity: 1,
+                    unit_price: amount,
+                    amount,
+                })
+                .collect(),
+        }
+    }
+
+    fn labels(rows: &[(&'static str, String)]) -> Vec<String> {
+        rows.iter().map(|(l, v)| format!("{}={}", l, v)).collect()
+    }
+
+    #[test]
+    fn totals_of_a_paid_sale() {
+        let r = receipt(&[120, 80], 0, 200);
+        assert_eq!(labels(&r.totals()), ["合计=200", "已付=200"]);
+        assert_eq!(r.number(), "1a2b3c4d");
+    }
+
+    #[test]
+    fn totals_with_refund_and_outstanding() {
+        let r = receipt(&[120, 80], 50, 100);
+        assert_eq!(r.total(), 150);
+        assert_eq!(
+            labels(&r.totals()),
+            [
+                "合计=200",
+                "退款=-50",
+                "实收合计=150",
+                "已付=100",
+                "未付=50"
+            ]
+        );
+    }
+
+    #[test]
+    fn fit_text_keeps_short_text() {
+        assert_eq!(fit_text("abc", 10.0, 15.0), "abc");
+        assert_eq!(text_width("ab中", 10.0), 20.0);
+    }
+
+    #[test]
+    fn fit_text_truncates_with_an_ellipsis() {
+        let fitted = fit_text("营养素维生素套装", 10.0, 45.0);
+        assert_eq!(fitted, "营养素…");
+        assert!(text_width(&fitted, 10.0) <= 45.0);
+        assert_eq!(fit_text("abcdefgh", 10.0, 30.0), "abcd…");
+    }
+
+    #[test]
+    fn pdf_has_one_page_object_per_page() {
+        let pdf = String::from_utf8(write_pdf(&["a".to_string(), "b".to_string()])).unwrap();
+        assert!(pdf.starts_with("%PDF-1.4\n"));
+        assert!(pdf.contains("/Kids [6 0 R 8 0 R] /Count 2"));
+        assert!(pdf.contains("xref\n0 10\n"));
+        assert!(pdf.ends_with("%%EOF\n"));
+        assert_eq!(pdf_hex("A中"), "<00414E2D>");
+    }
+}
@@ -0,0 +1,680 @@
+use crate::analytics;
+use crate::db::{
+    get_db_config, ignore_empty_baton_commit, sql_quote, to_sql_null_or_int, to_sql_null_or_string,
+};
+use crate::sales::SalesHeader;
+use crate::stock::{take_stock, StockChange};
+use chrono::{Datelike, Duration, NaiveDate};
+use libsql_client::{Client, Transaction};
+use serde::{Deserialize, Serialize};
+use tokio::task;
+use uuid::Uuid;
+
+/// Anchor day of a `RecurringSale` row; templates from before it was stored
+/// fall back to the day of their next date.
+const ANCHOR_DAY: &str =
+    "COALESCE(anchor_day, CAST(substr(next_date, 9, 2) AS INTEGER)) AS anchor_day";
+
+fn default_active() -> bool {
+    true
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct RecurringSaleHeader {
+    pub id: String, // UUID from frontend
+    pub customer: String,
+    pub interval_unit: String, // "day" | "week" | "month"
+    pub interval_count: i64,   // > 0
+    pub next_date: String,     // "YYYY-MM-DD", next order due
+    #[serde(default)]
+    pub payment_method: Option<String>,
+    #[serde(default)]
+    pub payment_status: Option<String>, // of generated sales: "paid" | "unpaid" (default)
+    pub note: Option<String>,
+    #[serde(default = "default_active")]
+    pub active: bool, // false = paused
+    #[serde(default)]
+    pub last_sale_id: Option<String>, // maintained by the app
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct RecurringSaleItem {
+    pub id: String, // UUID from frontend
+    pub product_name: String,
+    pub quantity: i64,
+    #[serde(default)]
+    pub price: Option<i64>, // agreed unit price; None = Product.price when generated
+    #[serde(default)]
+    pub available: i64, // unexpired units in stock, filled on read
+}
+
+#[derive(Debug, Serialize)]
+pub struct RecurringSale {
+    pub header: RecurringSaleHeader,
+    pub items: Vec<RecurringSaleItem>,
+    pub days_until_due: i64, // negative = overdue
+}
+
+/// `date` moved forward by one interval; monthly steps land on `anchor_day`
+/// (the day of month the template was set up for) so they never drift.
+fn advance(date: NaiveDate, unit: &str, count: i64, anchor_day: u32) -> Result<NaiveDate, String> {
+    match unit {
+        "day" => Ok(date + Duration::days(count)),
+        "week" => Ok(date + Duration::weeks(count)),
+        "month" => Ok(analytics::add_months_on_day(date, count as i32, anchor_day)),
+        other => Err(format!("不支持的周期单位：{}", other)),
+    }
+}
+
+/// Next due date after a sale dated `date` for the order due on `next`: at
+/// least one interval on, and past `date` when intervals were missed.
+fn next_after(
+    next: NaiveDate,
+    date: NaiveDate,
+    unit: &str,
+    count: i64,
+    anchor_day: u32,
+) -> Result<NaiveDate, String> {
+    let mut next = advance(next, unit, count, anchor_day)?;
+    while next <= date {
+        next = advance(next, unit, count, anchor_day)?;
+    }
+    Ok(next)
+}
+
+fn validate(header: &RecurringSaleHeader, items: &[RecurringSaleItem]) -> Result<(), String> {
+    if header.customer.trim().is_empty() {
+        return Err("请填写客户。".into());
+    }
+    if header.interval_count <= 0 {
+        return Err("周期必须为正数。".into());
+    }
+    let next = analytics::parse_date(&header.next_date)?;
+    advance(
+        next,
+        &header.interval_unit,
+        header.interval_count,
+        next.day(),
+    )?;
+    if !matches!(
+        header.payment_status.as_deref(),
+        None | Some("paid" | "unpaid")
+    ) {
+        return Err("未知付款状态".into());
+    }
+    if items.is_empty() {
+        return Err("至少需要一条明细项".into());
+    }
+    for it in items {
+        if it.quantity <= 0 {
+            return Err(format!(
+                "数量必须为正数：{} ({})",
+                it.product_name, it.quantity
+            ));
+        }
+        if it.price.is_some_and(|p| p < 0) {
+            return Err(format!("单价不能为负数：{}", it.product_name));
+        }
+    }
+    Ok(())
+}
+
+async fn insert_items(
+    tx: &Transaction<'_>,
+    recurring_id: &str,
+    items: &[RecurringSaleItem],
+) -> Result<(), String> {
+    for it in items {
+        tx.execute(format!(
+            "INSERT INTO RecurringSaleItem (id, recurring_id, product_name, quantity, price)
+             VALUES ('{}', '{}', '{}', {}, {});",
+            sql_quote(&it.id),
+            sql_quote(recurring_id),
+            sql_quote(&it.product_name),
+            it.quantity,
+            to_sql_null_or_int(it.price)
+        ))
+        .await
+        .map_err(|e| e.to_string())?;
+    }
+    Ok(())
+}
+
+/// Split `qty` of a product over its lots, earliest expiry first, skipping lots
+/// already expired on `date`.
+async fn allocate_lots(
+    tx: &Transaction<'_>,
+    name: &str,
+    qty: i64,
+    price: Option<i64>,
+    date: &str,
+) -> Result<Vec<StockChange>, String> {
+    let rows = tx
+        .execute(format!(
+            "SELECT expiry, quantity FROM Stock
+             WHERE name = '{}' AND quantity > 0 AND expiry >= '{}'
+             ORDER BY expiry",
+            sql_quote(name),
+            sql_quote(date)
+        ))
+        .await
+        .map_err(|e| e.to_string())?
+        .rows;
+
+    let mut left = qty;
+    let mut changes = Vec::new();
+    for row in rows {
+        if left == 0 {
+            break;
+        }
+        let avail = row
+            .try_column::<i64>("quantity")
+            .map_err(|e| e.to_string())?;
+        let take = avail.min(left);
+        changes.push(StockChange {
+            name: name.to_string(),
+            expiry_date: row
+                .try_column::<&str>("expiry")
+                .map_err(|e| e.to_string())?
+                .to_string(),
+            qty: take,
+            price,
+            cost: None,
+        });
+        left -= take;
+    }
+    if left > 0 {
+        return Err(format!(
+            "库存不足：{}（需要 {}，可用 {}）",
+            name,
+            qty,
+            qty - left
+        ));
+    }
+    Ok(changes)
+}
+
+#[tauri::command]
+pub async fn create_recurring_sale(
+    header: RecurringSaleHeader,
+    items: Vec<RecurringSaleItem>,
+) -> Result<(), String> {
+    validate(&header, &items)?;
+    task::spawn_blocking(move || {
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        rt.block_on(async move {
+            let config = get_db_config().await.map_err(|e| e.to_string())?;
+            let client = Client::from_config(config)
+                .await
+                .map_err(|e| e.to_string())?;
+            client
+                .execute("PRAGMA foreign_keys = ON;")
+                .await
+                .map_err(|e| e.to_string())?;
+
+            let tx = client.transaction().await.map_err(|e| e.to_string())?;
+            tx.execute(format!(
+                "INSERT INTO RecurringSale
+                   (id, customer, interval_unit, interval_count, next_date, anchor_day,
+                    payment_method, payment_status, note, active)
+                 VALUES ('{}', '{}', '{}', {}, '{}', {}, {}, {}, {}, {});",
+                sql_quote(&header.id),
+                sql_quote(header.customer.trim()),
+                sql_quote(&header.interval_unit),
+                header.interval_count,
+                sql_quote(&header.next_date),
+                analytics::parse_date(&header.next_date)?.day(),
+                to_sql_null_or_string(&header.payment_method),
+                to_sql_null_or_string(&header.payment_status),
+                to_sql_null_or_string(&header.note),
+                header.active as i64
+            ))
+            .await
+            .map_err(|e| e.to_string())?;
+            insert_items(&tx, &header.id, &items).await?;
+
+            let commit_res = tx.commit().await;
+            ignore_empty_baton_commit(commit_res)?;
+            Ok(())
+        })
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+#[tauri::command]
+pub async fn update_recurring_sale(
+    header: RecurringSaleHeader,
+    items: Vec<RecurringSaleItem>,
+) -> Result<(), String> {
+    validate(&header, &items)?;
+    task::spawn_blocking(move || {
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        rt.block_on(async move {
+            let config = get_db_config().await.map_err(|e| e.to_string())?;
+            let client = Client::from_config(config)
+                .await
+                .map_err(|e| e.to_string())?;
+            client
+                .execute("PRAGMA foreign_keys = ON;")
+                .await
+                .map_err(|e| e.to_string())?;
+
+            let tx = client.transaction().await.map_err(|e| e.to_string())?;
+            // the anchor only moves when the user picks a new next date; an
+            // unchanged one may be clamped to a short month
+            let next_q = sql_quote(&header.next_date);
+            let res = tx
+                .execute(format!(
+                    "UPDATE RecurringSale
+                       SET customer = '{}', interval_unit = '{}', interval_count = {},
+                           anchor_day = CASE WHEN next_date = '{next}' THEN anchor_day
+                                             ELSE {} END,
+                           next_date = '{next}', payment_method = {}, payment_status = {},
+                           note = {}, active = {}
+                     WHERE id = '{}';",
+                    sql_quote(header.customer.trim()),
+                    sql_quote(&header.interval_unit),
+                    header.interval_count,
+                    analytics::parse_date(&header.next_date)?.day(),
+                    to_sql_null_or_string(&header.payment_method),
+                    to_sql_null_or_string(&header.payment_status),
+                    to_sql_null_or_string(&header.note),
+                    header.active as i64,
+                    sql_quote(&header.id),
+                    next = next_q
+                ))
+                .await
+                .map_err(|e| e.to_string())?;
+            if res.rows_affected == 0 {
+                return Err(format!("定期订单不存在：{}", header.id));
+            }
+
+            tx.execute(format!(
+                "DELETE FROM RecurringSaleItem WHERE recurring_id = '{}';",
+                sql_quote(&header.id)
+            ))
+            .await
+            .map_err(|e| e.to_string())?;
+            insert_items(&tx, &header.id, &items).await?;
+
+            let commit_res = tx.commit().await;
+            ignore_empty_baton_commit(commit_res)?;
+            Ok(())
+        })
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+#[tauri::command]
+pub async fn delete_recurring_sale(recurring_id: String) -> Result<(), String> {
+    task::spawn_blocking(move || {
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        rt.block_on(async move {
+            let config = get_db_config().await.map_err(|e| e.to_string())?;
+            let client = Client::from_config(config)
+                .await
+                .map_err(|e| e.to_string())?;
+
+            // Generated sales stay; only the template goes
+            let tx = client.transaction().await.map_err(|e| e.to_string())?;
+            let id_q = sql_quote(&recurring_id);
+            tx.execute(format!(
+                "DELETE FROM RecurringSaleItem WHERE recurring_id = '{}';",
+                id_q
+            ))
+            .await
+            .map_err(|e| e.to_string())?;
+            let res = tx
+                .execute(format!("DELETE FROM RecurringSale WHERE id = '{}';", id_q))
+                .await
+                .map_err(|e| e.to_string())?;
+            if res.rows_affected == 0 {
+                return Err(format!("定期订单不存在：{}", recurring_id));
+            }
+
+            let commit_res = tx.commit().await;
+            ignore_empty_baton_commit(commit_res)?;
+            Ok(())
+        })
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+/// All recurring orders, or with `due_within_days` only the active ones due
+/// within that many days (overdue included), earliest first. Each item carries
+/// the stock still sellable on the due date (or today, if overdue).
+#[tauri::command]
+pub async fn get_recurring_sales(
+    due_within_days: Option<u32>,
+) -> Result<Vec<RecurringSale>, String> {
+    let today = analytics::today();
+    let due_filter = match due_within_days {
+        Some(n) => format!(
+            "WHERE r.active = 1 AND r.next_date <= '{}'",
+            today + Duration::days(n.min(3650) as i64)
+        ),
+        None => String::new(),
+    };
+    task::spawn_blocking(move || {
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        rt.block_on(async move {
+            let config = get_db_config().await.map_err(|e| e.to_string())?;
+            let client = Client::from_config(config)
+                .await
+                .map_err(|e| e.to_string())?;
+
+            let sql = format!(
+                r#"
+                SELECT r.id AS recurring_id, r.customer, r.interval_unit, r.interval_count,
+                       r.next_date, r.payment_method, r.payment_status, r.note, r.active,
+                       r.last_sale_id,
+                       i.id, i.product_name, i.quantity, i.price,
+                       (SELECT COALESCE(SUM(s.quantity), 0) FROM Stock s
+                         WHERE s.name = i.product_name
+                           AND s.expiry >= MAX(r.next_date, '{today}')) AS available
+                FROM RecurringSale r
+                JOIN RecurringSaleItem i ON i.recurring_id = r.id
+                {due_filter}
+                ORDER BY r.next_date, r.customer, r.id, i.product_name
+                "#,
+                today = today,
+                due_filter = due_filter
+            );
+            let result = client.execute(sql).await.map_err(|e| e.to_string())?;
+
+            let mut out: Vec<RecurringSale> = Vec::new();
+            for row in result.rows {
+                let recurring_id = row
+                    .try_column::<&str>("recurring_id")
+                    .map_err(|e| e.to_string())?
+                    .to_string();
+
+                // rows are ordered by template, so a new id starts a new template
+                if out
+                    .last()
+                    .map(|r| r.header.id != recurring_id)
+                    .unwrap_or(true)
+                {
+                    let next_date = row
+                        .try_column::<&str>("next_date")
+                        .map_err(|e| e.to_string())?
+                        .to_string();
+                    let days_until_due = (analytics::parse_date(&next_date)? - today).num_days();
+                    out.push(RecurringSale {
+                        header: RecurringSaleHeader {
+                            id: recurring_id,
+                            customer: row
+                                .try_column::<&str>("customer")
+                                .map_err(|e| e.to_string())?
+                                .to_string(),
+                            interval_unit: row
+                                .try_column::<&str>("interval_unit")
+                                .map_err(|e| e.to_string())?
+                                .to_string(),
+                            interval_count: row
+                                .try_column::<i64>("interval_count")
+                                .map_err(|e| e.to_string())?,
+                            next_date,
+                            payment_method: row
+                                .try_column::<&str>("payment_method")
+                                .ok()
+                                .map(|s| s.to_string()),
+                            payment_status: row
+                                .try_column::<&str>("payment_status")
+                                .ok()
+                                .map(|s| s.to_string()),
+                            note: row.try_column::<&str>("note").ok().map(|s| s.to_string()),
+                            active: row.try_column::<i64>("active").unwrap_or(1) != 0,
+                            last_sale_id: row
+                                .try_column::<&str>("last_sale_id")
+                                .ok()
+                                .map(|s| s.to_string()),
+                        },
+                        items: Vec::new(),
+                        days_until_due,
+                    });
+                }
+
+                let item = RecurringSaleItem {
+                    id: row
+                        .try_column::<&str>("id")
+                        .map_err(|e| e.to_string())?
+                        .to_string(),
+                    product_name: row
+                        .try_column::<&str>("product_name")
+                        .map_err(|e| e.to_string())?
+                        .to_string(),
+                    quantity: row
+                        .try_column::<i64>("quantity")
+                        .map_err(|e| e.to_string())?,
+                    price: row.try_column::<i64>("price").ok(),
+                    available: row.try_column::<i64>("available").unwrap_or(0),
+                };
+                if let Some(r) = out.last_mut() {
+                    r.items.push(item);
+                }
+            }
+            Ok(out)
+        })
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+/// Turn a recurring order into a real sale dated `date` (default today). Stock
+/// is taken from the earliest-expiring unexpired lots, the same way
+/// `remove_stock` records a sale, so the result can be edited with
+/// `update_sale` like any other, with the template's payment status (unpaid
+/// unless set). The next date moves on by at least one interval, and by whole
+/// intervals until it is after the sale date. Returns the new sale id.
+#[tauri::command]
+pub async fn generate_recurring_sale(
+    recurring_id: String,
+    date: Option<String>,
+) -> Result<String, String> {
+    let date = match date.filter(|d| !d.is_empty()) {
+        Some(d) => analytics::parse_date(&d)?,
+        None => analytics::today(),
+    };
+    task::spawn_blocking(move || {
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        rt.block_on(async move {
+            let config = get_db_config().await.map_err(|e| e.to_string())?;
+            let client = Client::from_config(config)
+                .await
+                .map_err(|e| e.to_string())?;
+            client
+                .execute("PRAGMA foreign_keys = ON;")
+                .await
+                .map_err(|e| e.to_string())?;
+
+            let tx = client.transaction().await.map_err(|e| e.to_string())?;
+            let id_q = sql_quote(&recurring_id);
+
+            let header = tx
+                .execute(format!(
+                    "SELECT customer, interval_unit, interval_count, next_date, {},
+                            payment_method, payment_status, note, active
+                     FROM RecurringSale WHERE id = '{}'",
+                    ANCHOR_DAY, id_q
+                ))
+                .await
+                .map_err(|e| e.to_string())?;
+            let row = header
+                .rows
+                .first()
+                .ok_or_else(|| format!("定期订单不存在：{}", recurring_id))?;
+            if row.try_column::<i64>("active").unwrap_or(1) == 0 {
+                return Err("该定期订单已暂停。".into());
+            }
+            let customer = row
+                .try_column::<&str>("customer")
+                .map_err(|e| e.to_string())?
+                .to_string();
+            let unit = row
+                .try_column::<&str>("interval_unit")
+                .map_err(|e| e.to_string())?
+                .to_string();
+            let count = row
+                .try_column::<i64>("interval_count")
+                .map_err(|e| e.to_string())?;
+            let next = analytics::parse_date(
+                row.try_column::<&str>("next_date")
+                    .map_err(|e| e.to_string())?,
+            )?;
+            let anchor_day = row
+                .try_column::<i64>("anchor_day")
+                .map_err(|e| e.to_string())? as u32;
+            let payment_method = row
+                .try_column::<&str>("payment_method")
+                .ok()
+                .map(|s| s.to_string());
+            let payment_status = row
+                .try_column::<&str>("payment_status")
+                .unwrap_or("unpaid")
+                .to_string();
+            let note = row.try_column::<&str>("note").ok().map(|s| s.to_string());
+
+            // price agreed on the template, else today's list price
+            let items = tx
+                .execute(format!(
+                    "SELECT i.product_name, i.quantity, COALESCE(i.price, p.price) AS price
+                     FROM RecurringSaleItem i
+                     LEFT JOIN Product p ON p.name = i.product_name
+                     WHERE i.recurring_id = '{}'
+                     ORDER BY i.product_name",
+                    id_q
+                ))
+                .await
+                .map_err(|e| e.to_string())?;
+            if items.rows.is_empty() {
+                return Err("该定期订单没有明细项。".into());
+            }
+
+            let sale_date = date.format("%Y-%m-%d").to_string();
+            let mut changes = Vec::new();
+            for it in &items.rows {
+                let name = it
+                    .try_column::<&str>("product_name")
+                    .map_err(|e| e.to_string())?;
+                let qty = it
+                    .try_column::<i64>("quantity")
+                    .map_err(|e| e.to_string())?;
+                let price = it.try_column::<i64>("price").ok();
+                changes.extend(allocate_lots(&tx, name, qty, price, &sale_date).await?);
+            }
+
+            let sale = SalesHeader {
+                id: Uuid::new_v4().to_string(),
+                date: sale_date,
+                note,
+                customer: Some(customer),
+                payment_method,
+                payment_status: Some(payment_status),
+                ..Default::default()
+            };
+            take_stock(&tx, &changes, Some(&sale)).await?;
+
+            // one sale covers the order due and any missed intervals
+            let next = next_after(next, date, &unit, count, anchor_day)?;
+            tx.execute(format!(
+                "UPDATE RecurringSale SET next_date = '{}', last_sale_id = '{}' WHERE id = '{}';",
+                next.format("%Y-%m-%d"),
+                sql_quote(&sale.id),
+                id_q
+            ))
+            .await
+            .map_err(|e| e.to_string())?;
+
+            let commit_res = tx.commit().await;
+            ignore_empty_baton_commit(commit_res)?;
+            Ok(sale.id)
+        })
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+/// Move a recurring order on by one interval without making a sale.
+#[tauri::command]
+pub async fn skip_recurring_sale(recurring_id: String) -> Result<(), String> {
+    task::spawn_blocking(move || {
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        rt.block_on(async move {
+            let config = get_db_config().await.map_err(|e| e.to_string())?;
+            let client = Client::from_config(config)
+                .await
+                .map_err(|e| e.to_string())?;
+
+            let result = client
+                .execute(format!(
+                    "SELECT interval_unit, interval_count, next_date, {}
+                     FROM RecurringSale WHERE id = '{}'",
+                    ANCHOR_DAY,
+                    sql_quote(&recurring_id)
+                ))
+                .await
+                .map_err(|e| e.to_string())?;
+            let row = result
+                .rows
+                .first()
+                .ok_or_else(|| format!("定期订单不存在：{}", recurring_id))?;
+            let next = advance(
+                analytics::parse_date(
+                    row.try_column::<&str>("next_date")
+                        .map_err(|e| e.to_string())?,
+                )?,
+                row.try_column::<&str>("interval_unit")
+                    .map_err(|e| e.to_string())?,
+                row.try_column::<i64>("interval_count")
+                    .map_err(|e| e.to_string())?,
+                row.try_column::<i64>("anchor_day")
+                    .map_err(|e| e.to_string())? as u32,
+            )?;
+
+            client
+                .execute(format!(
+                    "UPDATE RecurringSale SET next_date = '{}' WHERE id = '{}';",
+                    next.format("%Y-%m-%d"),
+                    sql_quote(&recurring_id)
+                ))
+                .await
+                .map_err(|e| e.to_string())?;
+            Ok(())
+        })
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn d(s: &str) -> NaiveDate {
+        analytics::parse_date(s).unwrap()
+    }
+
+    #[test]
+    fn early_generation_still_moves_on() {
+        let next = next_after(d("2025-03-10"), d("2025-03-05"), "week", 1, 10).unwrap();
+        assert_eq!(next, d("2025-03-17"));
+    }
+
+    #[test]
+    fn missed_intervals_are_skipped() {
+        let next = next_after(d("2025-01-31"), d("2025-04-02"), "month", 1, 31).unwrap();
+        assert_eq!(next, d("2025-04-30"));
+        let next = next_after(d("2025-03-01"), d("2025-03-01"), "day", 2, 1).unwrap();
+        assert_eq!(next, d("2025-03-03"));
+    }
+
+    #[test]
+    fn monthly_steps_keep_the_anchor() {
+        let next = next_after(d("2025-02-28"), d("2025-02-28"), "month", 1, 31).unwrap();
+        assert_eq!(next, d("2025-03-31"));
+    }
+}
@@ -0,0 +1,118 @@
+use crate::analytics;
+use crate::db::get_db_config;
+use chrono::Duration;
+use libsql_client::Client;
+use serde::Serialize;
+use tokio::task;
+
+#[derive(Debug, Serialize)]
+pub struct RefillReminder {
+    pub customer: String,
+    pub product_name: String,
+    pub r#type: Option<String>,
+    pub last_purchase: String, // YYYY-MM-DD
+    pub quantity: i64,         // units bought that day, net of returns
+    pub consumption_days: i64, // days one unit lasts
+    pub due_date: String,      // last_purchase + quantity * consumption_days
+    pub days_left: i64,        // negative = overdue
+    pub purchase_count: i64,   // days the customer bought this product
+}
+
+/// Customers who run out of a product within the next `within_days` days
+/// (default 7), or already have. The supply runs from the customer's last
+/// purchase of the product: units bought that day times the product's
+/// consumption period. Only named customers and products with a consumption
+/// period are considered; `max_overdue_days` drops customers long lapsed.
+#[tauri::command]
+pub async fn get_refill_reminders(
+    within_days: Option<u32>,
+    max_overdue_days: Option<u32>,
+) -> Result<Vec<RefillReminder>, String> {
+    let within = within_days.unwrap_or(7).min(3650) as i64;
+    let max_overdue = max_overdue_days.map(|d| d as i64);
+    task::spawn_blocking(move || {
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        rt.block_on(async move {
+            let config = get_db_config().await.map_err(|e| e.to_string())?;
+            let client = Client::from_config(config)
+                .await
+                .map_err(|e| e.to_string())?;
+
+            // Same-day sales to a customer count as one purchase; a purchase
+            // returned in full is no purchase.
+            let sql = r#"
+                WITH purchases AS (
+                    SELECT h.customer AS customer, h.date AS date,
+                           l.product_name AS product_name,
+                           SUM(l.quantity) AS quantity
+                    FROM SalesRevenueLedger l
+                    JOIN SalesHeader h ON h.id = l.sale_id
+                    WHERE h.customer IS NOT NULL AND TRIM(h.customer) <> ''
+                    GROUP BY h.customer, h.date, l.product_name
+                    HAVING SUM(l.quantity) > 0
+                ),
+                latest AS (
+                    SELECT customer, date, product_name, quantity,
+                           ROW_NUMBER() OVER (
+                               PARTITION BY customer, product_name ORDER BY date DESC
+                           ) AS rn,
+                           COUNT(*) OVER (PARTITION BY customer, product_name) AS purchase_count
+                    FROM purchases
+                )
+                SELECT l.customer, l.product_name, p.type AS ptype, l.date,
+                       CAST(l.quantity AS INTEGER) AS quantity,
+                       p.consumption_days, l.purchase_count
+                FROM latest l
+                JOIN Product p ON p.name = l.product_name
+                WHERE l.rn = 1 AND p.consumption_days > 0
+                "#;
+
+            let res = client.execute(sql).await.map_err(|e| e.to_string())?;
+
+            let today = analytics::today();
+            let mut out = Vec::new();
+            for row in res.rows {
+                let last = row.try_column::<&str>("date").map_err(|e| e.to_string())?;
+                let quantity = row.try_column::<i64>("quantity").unwrap_or(0);
+                let consumption_days = row.try_column::<i64>("consumption_days").unwrap_or(0);
+
+                let due =
+                    analytics::parse_date(last)? + Duration::days(quantity * consumption_days);
+                let days_left = (due - today).num_days();
+                if days_left > within || max_overdue.is_some_and(|m| -days_left > m) {
+                    continue;
+                }
+
+                out.push(RefillReminder {
+                    customer: row
+                        .try_column::<&str>("customer")
+                        .map_err(|e| e.to_string())?
+                        .to_string(),
+                    product_name: row
+                        .try_column::<&str>("product_name")
+                        .map_err(|e| e.to_string())?
+                        .to_string(),
+                    r#type: row.try_column::<&str>("ptype").ok().map(|s| s.to_string()),
+                    last_purchase: last.to_string(),
+                    quantity,
+                    consumption_days,
+                    due_date: due.format("%Y-%m-%d").to_string(),
+                    days_left,
+                    purchase_count: row.try_column::<i64>("purchase_count").unwrap_or(0),
+                });
+            }
+
+            // most overdue first
+            out.sort_by(|a, b| {
+                (a.days_left, &a.customer, &a.product_name).cmp(&(
+                    b.days_left,
+                    &b.customer,
+                    &b.product_name,
+                ))
+            });
+            Ok(out)
+        })
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
@@ -0,0 +1,355 @@
+use crate::db::{get_db_config, ignore_empty_baton_commit, sql_quote, to_sql_null_or_string};
+use crate::payment::refresh_payment_status;
+use crate::stock::{add_to_lot, take_from_lot};
+use libsql_client::Client;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use tokio::task;
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct SalesReturnHeader {
+    pub id: String,      // UUID from frontend
+    pub sale_id: String, // original sale
+    pub date: String,    // "YYYY-MM-DD"
+    pub note: Option<String>,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct SalesReturnItem {
+    pub id: String,           // UUID from frontend
+    pub sale_item_id: String, // original SalesItem line
+    #[serde(default)]
+    pub product_name: String, // filled from the sale line
+    #[serde(default)]
+    pub expiry: String, // filled from the sale line
+    pub quantity: i64,
+    #[serde(default)]
+    pub damaged: bool, // true = write off instead of restocking
+    #[serde(default)]
+    pub refund: Option<i64>, // None = quantity * sale price
+}
+
+#[derive(Debug, Serialize)]
+pub struct SalesReturn {
+    pub header: SalesReturnHeader,
+    pub items: Vec<SalesReturnItem>,
+    pub total_refund: i64,
+}
+
+#[tauri::command]
+pub async fn create_sales_return(
+    header: SalesReturnHeader,
+    mut items: Vec<SalesReturnItem>,
+) -> Result<(), String> {
+    if items.is_empty() {
+        return Err("至少需要一条明细项".into());
+    }
+    for it in &items {
+        if it.quantity <= 0 {
+            return Err(format!("退货数量必须为正数：{}", it.quantity));
+        }
+        if it.refund.is_some_and(|r| r < 0) {
+            return Err("退款金额不能为负数。".into());
+        }
+    }
+
+    task::spawn_blocking(move || {
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        rt.block_on(async move {
+            let config = get_db_config().await.map_err(|e| e.to_string())?;
+            let client = Client::from_config(config)
+                .await
+                .map_err(|e| e.to_string())?;
+
+            // IMPORTANT: enable FKs
+            client
+                .execute("PRAGMA foreign_keys = ON;")
+                .await
+                .map_err(|e| e.to_string())?;
+
+            let tx = client.transaction().await.map_err(|e| e.to_string())?;
+
+            let sale_id_q = sql_quote(&header.sale_id);
+
+            // 1) the original sale must exist
+            let exists = tx
+                .execute(format!(
+                    "SELECT 1 FROM SalesHeader WHERE id='{}' LIMIT 1;",
+                    sale_id_q
+                ))
+                .await
+                .map_err(|e| e.to_string())?;
+            if exists.rows.is_empty() {
+                return Err(format!("销售记录不存在：{}", header.sale_id));
+            }
+
+            // 2) resolve each line against the sale and check it is not over-returned
+            let mut pending: HashMap<String, i64> = HashMap::new();
+            for it in items.iter_mut() {
+                let sql = format!(
+                    "SELECT i.product_name, i.expiry, i.quantity,
+                            COALESCE(i.price, p.price, 0) AS unit_price,
+                            (SELECT COALESCE(SUM(ri.quantity), 0)
+                               FROM SalesReturnItem ri
+                              WHERE ri.sale_item_id = i.id) AS returned
+                     FROM SalesItem i
+                     LEFT JOIN Product p ON p.name = i.product_name
+                     WHERE i.id = '{}' AND i.sale_id = '{}';",
+                    sql_quote(&it.sale_item_id),
+                    sale_id_q
+                );
+                let rs = tx.execute(sql).await.map_err(|e| e.to_string())?;
+                let row = rs
+                    .rows
+                    .first()
+                    .ok_or_else(|| format!("该销售中不存在此明细：{}", it.sale_item_id))?;
+
+                it.product_name = row
+                    .try_column::<&str>("product_name")
+                    .map_err(|e| e.to_string())?
+                    .to_string();
+                it.expiry = row
+                    .try_column::<&str>("expiry")
+                    .map_err(|e| e.to_string())?
+                    .to_string();
+                let sold = row
+                    .try_column::<i64>("quantity")
+                    .map_err(|e| e.to_string())?;
+                let returned = row.try_column::<i64>("returned").unwrap_or(0);
+                let unit_price = row.try_column::<i64>("unit_price").unwrap_or(0);
+
+                let already = pending.entry(it.sale_item_id.clone()).or_insert(0);
+                if returned + *already + it.quantity > sold {
+                    return Err(format!(
+                        "退货数量超出销售数量：{}（已售 {}，已退 {}）",
+                        it.product_name,
+                        sold,
+                        returned + *already
+                    ));
+                }
+                *already += it.quantity;
+
+                if it.refund.is_none() {
+                    it.refund = Some(it.quantity * unit_price);
+                }
+            }
+
+            // 3) header
+            let header_sql = format!(
+                "INSERT INTO SalesReturnHeader (id, sale_id, date, note)
+                 VALUES ('{}', '{}', '{}', {});",
+                sql_quote(&header.id),
+                sale_id_q,
+                sql_quote(&header.date),
+                to_sql_null_or_string(&header.note)
+            );
+            tx.execute(header_sql).await.map_err(|e| e.to_string())?;
+
+            // 4) items, restocking the original lot unless written off
+            for it in &items {
+                let item_sql = format!(
+                    "INSERT INTO SalesReturnItem
+                       (id, return_id, sale_item_id, product_name, expiry, quantity, damaged, refund)
+                     VALUES ('{}', '{}', '{}', '{}', '{}', {}, {}, {});",
+                    sql_quote(&it.id),
+                    sql_quote(&header.id),
+                    sql_quote(&it.sale_item_id),
+                    sql_quote(&it.product_name),
+                    sql_quote(&it.expiry),
+                    it.quantity,
+                    it.damaged as i64,
+                    it.refund.unwrap_or(0)
+                );
+                tx.execute(item_sql).await.map_err(|e| e.to_string())?;
+
+                if !it.damaged {
+                    add_to_lot(&tx, &it.product_name, &it.expiry, it.quantity).await?;
+                }
+            }
+
+            // 5) refunds lower what the customer owes
+            refresh_payment_status(&tx, &header.sale_id).await?;
+
+            let res = tx.commit().await;
+            ignore_empty_baton_commit(res)?;
+            Ok(())
+        })
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+#[tauri::command]
+pub async fn delete_sales_return(return_id: String) -> Result<(), String> {
+    task::spawn_blocking(move || {
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        rt.block_on(async move {
+            let config = get_db_config().await.map_err(|e| e.to_string())?;
+            let client = Client::from_config(config)
+                .await
+                .map_err(|e| e.to_string())?;
+
+            client
+                .execute("PRAGMA foreign_keys = ON;")
+                .await
+                .map_err(|e| e.to_string())?;
+
+            let tx = client.transaction().await.map_err(|e| e.to_string())?;
+
+            let return_id_q = sql_quote(&return_id);
+
+            // 0. Find the sale this return belongs to
+            let rs = tx
+                .execute(format!(
+                    "SELECT sale_id FROM SalesReturnHeader WHERE id = '{}';",
+                    return_id_q
+                ))
+                .await
+                .map_err(|e| e.to_string())?;
+            let sale_id = rs
+                .rows
+                .first()
+                .ok_or_else(|| format!("退货记录不存在：{}", return_id))?
+                .try_column::<&str>("sale_id")
+                .map_err(|e| e.to_string())?
+                .to_string();
+
+            // 1. Take restocked quantities back out of their lots
+            let rs = tx
+                .execute(format!(
+                    "SELECT product_name, expiry, quantity
+                     FROM SalesReturnItem
+                     WHERE return_id = '{}' AND damaged = 0;",
+                    return_id_q
+                ))
+                .await
+                .map_err(|e| e.to_string())?;
+            for row in rs.rows {
+                let name = row
+                    .try_column::<&str>("product_name")
+                    .map_err(|e| e.to_string())?;
+                let expiry = row
+                    .try_column::<&str>("expiry")
+                    .map_err(|e| e.to_string())?;
+                let qty = row
+                    .try_column::<i64>("quantity")
+                    .map_err(|e| e.to_string())?;
+                take_from_lot(&tx, name, expiry, qty).await?;
+            }
+
+            // 2. Delete items, then header
+            tx.execute(format!(
+                "DELETE FROM SalesReturnItem WHERE return_id = '{}';",
+                return_id_q
+            ))
+            .await
+            .map_err(|e| e.to_string())?;
+            tx.execute(format!(
+                "DELETE FROM SalesReturnHeader WHERE id = '{}';",
+                return_id_q
+            ))
+            .await
+            .map_err(|e| e.to_string())?;
+
+            // 3. Without the refund the customer owes more again
+            refresh_payment_status(&tx, &sale_id).await?;
+
+            let res = tx.commit().await;
+            ignore_empty_baton_commit(res)?;
+            Ok(())
+        })
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+#[tauri::command]
+pub async fn get_sales_returns(sale_id: String) -> Result<Vec<SalesReturn>, String> {
+    task::spawn_blocking(move || {
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        rt.block_on(async move {
+            let config = get_db_config().await.map_err(|e| e.to_string())?;
+            let client = Client::from_config(config)
+                .await
+                .map_err(|e| e.to_string())?;
+
+            let sql = format!(
+                r#"
+                SELECT r.id AS return_id, r.date, r.note,
+                       ri.id, ri.sale_item_id, ri.product_name, ri.expiry,
+                       ri.quantity, ri.damaged, ri.refund
+                FROM SalesReturnHeader r
+                JOIN SalesReturnItem ri ON ri.return_id = r.id
+                WHERE r.sale_id = '{}'
+                ORDER BY r.date, r.id, ri.product_name
+                "#,
+                sql_quote(&sale_id)
+            );
+
+            let result = client.execute(sql).await.map_err(|e| e.to_string())?;
+
+            let mut returns: Vec<SalesReturn> = Vec::new();
+            for row in result.rows {
+                let return_id = row
+                    .try_column::<&str>("return_id")
+                    .map_err(|_| "Failed to get id from sales return".to_string())?
+                    .to_string();
+
+                // rows are ordered by return, so a new id starts a new return
+                if returns
+                    .last()
+                    .map(|r| r.header.id != return_id)
+                    .unwrap_or(true)
+                {
+                    let date = row
+                        .try_column::<&str>("date")
+                        .map_err(|_| "Failed to get date from sales return".to_string())?
+                        .to_string();
+                    let note = row.try_column::<&str>("note").ok().map(|s| s.to_string());
+                    returns.push(SalesReturn {
+                        header: SalesReturnHeader {
+                            id: return_id,
+                            sale_id: sale_id.clone(),
+                            date,
+                            note,
+                        },
+                        items: Vec::new(),
+                        total_refund: 0,
+                    });
+                }
+
+                let item = SalesReturnItem {
+                    id: row
+                        .try_column::<&str>("id")
+                        .map_err(|e| e.to_string())?
+                        .to_string(),
+                    sale_item_id: row
+                        .try_column::<&str>("sale_item_id")
+                        .map_err(|e| e.to_string())?
+                        .to_string(),
+                    product_name: row
+                        .try_column::<&str>("product_name")
+                        .map_err(|e| e.to_string())?
+                        .to_string(),
+                    expiry: row
+                        .try_column::<&str>("expiry")
+                        .map_err(|e| e.to_string())?
+                        .to_string(),
+                    quantity: row
+                        .try_column::<i64>("quantity")
+                        .map_err(|e| e.to_string())?,
+                    damaged: row.try_column::<i64>("damaged").unwrap_or(0) != 0,
+                    refund: row.try_column::<i64>("refund").ok(),
+                };
+
+                let current = returns.last_mut().unwrap();
+                current.total_refund += item.refund.unwrap_or(0);
+                current.items.push(item);
+            }
+
+            Ok(returns)
+        })
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
@@ -1,15 +1,29 @@
-use crate::db::{get_db_config, ignore_empty_baton_commit, sql_quote, to_sql_null_or_string};
-use crate::stock::StockChange;
-use libsql_client::Client;
+use crate::analytics::{self, Granularity};
+use crate::db::{
+    get_db_config, ignore_empty_baton_commit, sql_like_quote, sql_quote, to_sql_null_or_int,
+    to_sql_null_or_string,
+};
+use crate::payment::{insert_payment, refresh_payment_status, sale_total, SalesPayment};
+use crate::stock::apply_lot_delta;
+use libsql_client::{Client, Transaction};
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use tokio::task;
 use uuid::Uuid;
 
-#[derive(Debug, Deserialize, Serialize)]
+#[derive(Debug, Default, Deserialize, Serialize)]
 pub struct SalesHeader {
     pub id: String,   // UUID from frontend
     pub date: String, // "YYYY-MM-DD"
     pub note: Option<String>,
+    #[serde(default)]
+    pub customer: Option<String>,
+    #[serde(default)]
+    pub payment_method: Option<String>,
+    #[serde(default)]
+    pub amount_paid: i64, // on create: paid at the time of a "partial" sale; afterwards SUM(SalesPayment)
+    #[serde(default)]
+    pub payment_status: Option<String>, // "unpaid" | "partial" | "paid"; on create None = "paid"
 }
 
 #[derive(Debug, Deserialize, Serialize)]
@@ -18,6 +32,8 @@ pub struct SalesItem {
     pub product_name: String,
     pub quantity: i64,
     pub expiry: String,
+    #[serde(default)]
+    pub price: Option<i64>, // unit price at time of sale; None = current Product.price
 }
 
 #[derive(Debug, Deserialize, Serialize)]
@@ -25,70 +41,178 @@ pub struct SalesSummary {
     pub header: SalesHeader,
     pub top_products: Vec<String>,
     pub total_value: i64,
+    #[serde(default)]
+    pub total_pv: f64, // USANA points of the items sold, at the sale date's values
+    #[serde(default)]
+    pub total_bv: f64,
 }
 
 #[derive(Debug, Deserialize, Serialize)]
 pub struct MonthlySales {
     pub month: String, // "YYYY-MM"
     pub total: i64,
+    pub pv: f64, // net of returns, like `total`
+    pub bv: f64,
 }
 
 #[derive(Debug, Deserialize, Serialize)]
 pub struct MonthlySalesStats {
     pub this_month_total: i64,
     pub last_month_same_period_total: i64,
+    pub this_month_pv: f64,
+    pub this_month_bv: f64,
+    pub last_month_same_period_pv: f64,
+    pub last_month_same_period_bv: f64,
 }
 
-pub async fn add_sale(changes: Vec<StockChange>, note: Option<String>) -> Result<(), String> {
-    task::spawn_blocking(move || {
-        let rt = tokio::runtime::Runtime::new().unwrap();
-        rt.block_on(async move {
-            let config = get_db_config()
-                .await
-                .map_err(|e| e.to_string())?;
-            let client = Client::from_config(config)
-                .await
-                .map_err(|e| e.to_string())?;
-
-            // begin transaction
-            let tx = client.transaction().await.map_err(|e| e.to_string())?;
-
-            let sale_id = Uuid::new_v4().to_string();
-            let note = note.unwrap_or_default();
-            let date = chrono::Local::now().format("%Y-%m-%d").to_string();
+/// Net PV/BV per month ("YYYY-MM") between two dates, from `SalesPointsLedger`.
+async fn monthly_points(
+    client: &Client,
+    from: &str,
+    to: &str,
+) -> Result<BTreeMap<String, (f64, f64)>, String> {
+    let sql = format!(
+        r#"
+        SELECT substr(date, 1, 7) AS month, TOTAL(pv) AS pv, TOTAL(bv) AS bv
+        FROM SalesPointsLedger
+        WHERE date >= '{}' AND date <= '{}'
+        GROUP BY month
+        "#,
+        sql_quote(from),
+        sql_quote(to)
+    );
+    let result = client.execute(sql).await.map_err(|e| e.to_string())?;
+
+    let mut points = BTreeMap::new();
+    for row in result.rows {
+        let month = row.try_column::<&str>("month").map_err(|e| e.to_string())?;
+        points.insert(
+            month.to_string(),
+            (
+                row.try_column::<f64>("pv").unwrap_or(0.0),
+                row.try_column::<f64>("bv").unwrap_or(0.0),
+            ),
+        );
+    }
+    Ok(points)
+}
 
-            let header_sql = format!(
-                "INSERT INTO SalesHeader (id, date, note) VALUES ('{}', '{}', '{}')",
-                sql_quote(&sale_id),
-                sql_quote(&date),
-                sql_quote(&note)
-            );
-            tx.execute(header_sql).await.map_err(|e| e.to_string())?;
-
-            for change in changes {
-                let item_sql = format!(
-                    "INSERT INTO SalesItem (id, sale_id, product_name, quantity, expiry) VALUES ('{}', '{}', '{}', {}, '{}')",
-                    sql_quote(&Uuid::new_v4().to_string()),
-                    sql_quote(&sale_id),
-                    sql_quote(&change.name),
-                    change.qty,
-                    sql_quote(&change.expiry_date)
-                );
-                tx.execute(item_sql).await.map_err(|e| e.to_string())?;
+/// Insert a sale header and its items inside an existing transaction.
+pub async fn insert_sale(
+    tx: &Transaction<'_>,
+    header: &SalesHeader,
+    items: &[SalesItem],
+) -> Result<(), String> {
+    if header.date.trim().is_empty() {
+        return Err("请选择销售日期。".into());
+    }
+
+    let header_sql = format!(
+        "INSERT INTO SalesHeader (id, date, note, customer, payment_method)
+         VALUES ('{}', '{}', {}, {}, {});",
+        sql_quote(&header.id),
+        sql_quote(&header.date),
+        to_sql_null_or_string(&header.note),
+        to_sql_null_or_string(&header.customer),
+        to_sql_null_or_string(&header.payment_method)
+    );
+    tx.execute(header_sql).await.map_err(|e| e.to_string())?;
+
+    insert_sale_items(tx, &header.id, items).await?;
+
+    // Amount paid on the spot becomes the first payment; a sale is paid in full
+    // unless it says otherwise, so only an explicit status makes a receivable
+    let paid_now = match header.payment_status.as_deref() {
+        None | Some("paid") => sale_total(tx, &header.id).await?,
+        Some("unpaid") => 0,
+        Some("partial") => {
+            let total = sale_total(tx, &header.id).await?;
+            if header.amount_paid <= 0 || header.amount_paid >= total {
+                return Err(format!(
+                    "部分付款金额须大于 0 且小于应收金额 {}：{}",
+                    total, header.amount_paid
+                ));
             }
+            header.amount_paid
+        }
+        Some(other) => return Err(format!("未知付款状态：{}", other)),
+    };
+    if paid_now > 0 {
+        let payment = SalesPayment {
+            id: Uuid::new_v4().to_string(),
+            sale_id: header.id.clone(),
+            date: header.date.clone(),
+            amount: paid_now,
+            method: header.payment_method.clone(),
+            note: None,
+        };
+        insert_payment(tx, &payment).await?;
+    }
+    refresh_payment_status(tx, &header.id).await
+}
 
-            // commit using the helper
-            let res = tx.commit().await;
-            ignore_empty_baton_commit(res)?;
-            Ok(())
-        })
-    })
-    .await
-    .map_err(|e| e.to_string())?
+/// Insert sale items for `sale_id` inside an existing transaction.
+async fn insert_sale_items(
+    tx: &Transaction<'_>,
+    sale_id: &str,
+    items: &[SalesItem],
+) -> Result<(), String> {
+    for it in items {
+        if it.price.is_some_and(|p| p < 0) {
+            return Err(format!("单价不能为负数：{}", it.product_name));
+        }
+        let sql_item = format!(
+            "INSERT INTO SalesItem (id, sale_id, product_name, quantity, expiry, price)
+             VALUES ('{}', '{}', '{}', {}, '{}', {});",
+            sql_quote(&it.id),
+            sql_quote(sale_id),
+            sql_quote(&it.product_name),
+            it.quantity,
+            sql_quote(&it.expiry),
+            to_sql_null_or_int(it.price)
+        );
+        tx.execute(sql_item).await.map_err(|e| e.to_string())?;
+    }
+    Ok(())
+}
+
+/// Quantity still with the customer (sold minus returned) per (product, expiry) lot for one sale.
+async fn sold_per_lot(
+    tx: &Transaction<'_>,
+    sale_id: &str,
+) -> Result<BTreeMap<(String, String), i64>, String> {
+    let sql = format!(
+        "SELECT i.product_name, i.expiry,
+                SUM(i.quantity - (SELECT COALESCE(SUM(ri.quantity), 0)
+                                    FROM SalesReturnItem ri
+                                   WHERE ri.sale_item_id = i.id)) AS quantity
+         FROM SalesItem i
+         WHERE i.sale_id = '{}'
+         GROUP BY i.product_name, i.expiry;",
+        sql_quote(sale_id)
+    );
+    let rows = tx.execute(sql).await.map_err(|e| e.to_string())?.rows;
+
+    let mut out = BTreeMap::new();
+    for row in rows {
+        let name = row
+            .try_column::<&str>("product_name")
+            .map_err(|e| e.to_string())?
+            .to_string();
+        let expiry = row
+            .try_column::<&str>("expiry")
+            .map_err(|e| e.to_string())?
+            .to_string();
+        let qty = row
+            .try_column::<i64>("quantity")
+            .map_err(|e| e.to_string())?;
+        out.insert((name, expiry), qty);
+    }
+    Ok(out)
 }
 
 #[tauri::command]
-pub async fn delete_sale(sale_id: String) -> Result<(), String> {
+pub async fn delete_sale(sale_id: String, restore_stock: Option<bool>) -> Result<(), String> {
     task::spawn_blocking(move || {
         let rt = tokio::runtime::Runtime::new().unwrap();
         rt.block_on(async move {
@@ -108,6 +232,34 @@ pub async fn delete_sale(sale_id: String) -> Result<(), String> {
 
             let sale_id_q = sql_quote(&sale_id);
 
+            // 0. Put the sold quantities back into their lots if requested
+            if restore_stock.unwrap_or(true) {
+                for ((name, expiry), qty) in sold_per_lot(&tx, &sale_id).await? {
+                    apply_lot_delta(&tx, &name, &expiry, qty).await?;
+                }
+            }
+
+            // 0b. Delete payments and returns recorded against this sale
+            tx.execute(format!(
+                "DELETE FROM SalesPayment WHERE sale_id = '{}';",
+                sale_id_q
+            ))
+            .await
+            .map_err(|e| e.to_string())?;
+            tx.execute(format!(
+                "DELETE FROM SalesReturnItem WHERE return_id IN
+                   (SELECT id FROM SalesReturnHeader WHERE sale_id = '{}');",
+                sale_id_q
+            ))
+            .await
+            .map_err(|e| e.to_string())?;
+            tx.execute(format!(
+                "DELETE FROM SalesReturnHeader WHERE sale_id = '{}';",
+                sale_id_q
+            ))
+            .await
+            .map_err(|e| e.to_string())?;
+
             // 1. First delete the sale items (child records)
             let delete_items_sql =
                 format!("DELETE FROM SalesItem WHERE sale_id = '{}';", sale_id_q);
@@ -133,7 +285,20 @@ pub async fn delete_sale(sale_id: String) -> Result<(), String> {
 }
 
 #[tauri::command]
-pub async fn update_sale(header: SalesHeader, items: Vec<SalesItem>) -> Result<(), String> {
+pub async fn update_sale(
+    header: SalesHeader,
+    items: Vec<SalesItem>,
+    adjust_stock: Option<bool>,
+) -> Result<(), String> {
+    for it in &items {
+        if it.quantity <= 0 {
+            return Err(format!(
+                "数量必须为正数：{} ({})",
+                it.product_name, it.quantity
+            ));
+        }
+    }
+
     task::spawn_blocking(move || {
         let rt = tokio::runtime::Runtime::new().unwrap();
         rt.block_on(async move {
@@ -154,6 +319,8 @@ pub async fn update_sale(header: SalesHeader, items: Vec<SalesItem>) -> Result<(
             let sale_id_q = sql_quote(&header.id);
             let date_q = sql_quote(&header.date);
             let note_sql = to_sql_null_or_string(&header.note);
+            let customer_sql = to_sql_null_or_string(&header.customer);
+            let method_sql = to_sql_null_or_string(&header.payment_method);
 
             // 0. Verify all products exist
             for it in &items {
@@ -170,10 +337,42 @@ pub async fn update_sale(header: SalesHeader, items: Vec<SalesItem>) -> Result<(
                 }
             }
 
+            // 0b. Lines of a sale with returns are referenced by those returns
+            let has_returns = tx
+                .execute(format!(
+                    "SELECT 1 FROM SalesReturnHeader WHERE sale_id='{}' LIMIT 1;",
+                    sale_id_q
+                ))
+                .await
+                .map_err(|e| e.to_string())?;
+            if !has_returns.rows.is_empty() {
+                return Err("该销售已有退货记录，请先删除退货记录再修改。".into());
+            }
+
+            // 0c. Apply per-lot differences to Stock: old quantities go back,
+            // new quantities are taken out again
+            if adjust_stock.unwrap_or(true) {
+                let mut deltas = sold_per_lot(&tx, &header.id).await?;
+                for it in &items {
+                    *deltas
+                        .entry((it.product_name.clone(), it.expiry.clone()))
+                        .or_insert(0) -= it.quantity;
+                }
+                // returns first, so a lot is never checked before it gets its stock back
+                for ((name, expiry), delta) in deltas.iter().filter(|(_, d)| **d > 0) {
+                    apply_lot_delta(&tx, name, expiry, *delta).await?;
+                }
+                for ((name, expiry), delta) in deltas.iter().filter(|(_, d)| **d < 0) {
+                    apply_lot_delta(&tx, name, expiry, *delta).await?;
+                }
+            }
+
             // 1. Update sale header
             let update_header_sql = format!(
-                "UPDATE SalesHeader SET date = '{}', note = {} WHERE id = '{}';",
-                date_q, note_sql, sale_id_q
+                "UPDATE SalesHeader
+                 SET date = '{}', note = {}, customer = {}, payment_method = {}
+                 WHERE id = '{}';",
+                date_q, note_sql, customer_sql, method_sql, sale_id_q
             );
             tx.execute(update_header_sql)
                 .await
@@ -187,17 +386,10 @@ pub async fn update_sale(header: SalesHeader, items: Vec<SalesItem>) -> Result<(
                 .map_err(|e| e.to_string())?;
 
             // 3. Insert new sale items
-            for it in &items {
-                let it_id_q = sql_quote(&it.id);
-                let name_q = sql_quote(&it.product_name);
-                let expiry_q = sql_quote(&it.expiry);
-                let sql_item = format!(
-                    "INSERT INTO SalesItem (id, sale_id, product_name, quantity, expiry)
-                     VALUES ('{}', '{}', '{}', {}, '{}');",
-                    it_id_q, sale_id_q, name_q, it.quantity, expiry_q
-                );
-                tx.execute(sql_item).await.map_err(|e| e.to_string())?;
-            }
+            insert_sale_items(&tx, &header.id, &items).await?;
+
+            // 4. The sale value may have changed
+            refresh_payment_status(&tx, &header.id).await?;
 
             // Commit the transaction
             let res = tx.commit().await;
@@ -210,8 +402,104 @@ pub async fn update_sale(header: SalesHeader, items: Vec<SalesItem>) -> Result<(
     .map_err(|e| e.to_string())?
 }
 
+/// Filters, sort order and page position for `get_sales_history`.
+/// Every field is optional; the default is the newest 50 sales.
+#[derive(Debug, Default, Deserialize)]
+pub struct SalesHistoryFilter {
+    pub date_from: Option<String>,          // "YYYY-MM-DD", inclusive
+    pub date_to: Option<String>,            // "YYYY-MM-DD", inclusive
+    pub product_name: Option<String>,       // sales containing this product
+    pub customer: Option<String>,           // substring match
+    pub note: Option<String>,               // substring match
+    pub sort_by: Option<String>,            // "date" (default) | "total_value" | "customer"
+    pub sort_dir: Option<String>,           // "desc" (default) | "asc"
+    pub limit: Option<u32>,                 // page size, default 50
+    pub cursor: Option<SalesHistoryCursor>, // `next_cursor` of the previous page
+}
+
+/// Position after the last row of a page: its sort key and id.
+#[derive(Debug, Deserialize, Serialize)]
+pub struct SalesHistoryCursor {
+    pub key: String,
+    pub id: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SalesHistoryPage {
+    pub sales: Vec<SalesSummary>,
+    pub next_cursor: Option<SalesHistoryCursor>, // None = last page
+}
+
 #[tauri::command]
-pub async fn get_sales_history() -> Result<Vec<SalesSummary>, String> {
+pub async fn get_sales_history(
+    filter: Option<SalesHistoryFilter>,
+) -> Result<SalesHistoryPage, String> {
+    let filter = filter.unwrap_or_default();
+    let limit = filter.limit.unwrap_or(50).clamp(1, 500);
+
+    // sort key as it is named in the `matched` subquery below
+    let (sort_key, sort_by) = match filter.sort_by.as_deref().unwrap_or("date") {
+        "date" => ("date", "date"),
+        "total_value" => ("total_value", "total_value"),
+        "customer" => ("COALESCE(customer, '')", "customer"),
+        other => return Err(format!("不支持的排序字段：{}", other)),
+    };
+    let (dir, cmp) = match filter.sort_dir.as_deref().unwrap_or("desc") {
+        "desc" => ("DESC", "<"),
+        "asc" => ("ASC", ">"),
+        other => return Err(format!("不支持的排序方向：{}", other)),
+    };
+
+    let mut conds: Vec<String> = Vec::new();
+    if let Some(from) = filter.date_from.as_deref().filter(|s| !s.is_empty()) {
+        conds.push(format!("h.date >= '{}'", sql_quote(from)));
+    }
+    if let Some(to) = filter.date_to.as_deref().filter(|s| !s.is_empty()) {
+        conds.push(format!("h.date <= '{}'", sql_quote(to)));
+    }
+    if let Some(name) = filter.product_name.as_deref().filter(|s| !s.is_empty()) {
+        conds.push(format!(
+            "EXISTS (SELECT 1 FROM SalesItem f WHERE f.sale_id = h.id AND f.product_name = '{}')",
+            sql_quote(name)
+        ));
+    }
+    if let Some(c) = filter.customer.as_deref().filter(|s| !s.is_empty()) {
+        conds.push(format!(
+            "h.customer LIKE '%{}%' ESCAPE '\\'",
+            sql_like_quote(c)
+        ));
+    }
+    if let Some(n) = filter.note.as_deref().filter(|s| !s.is_empty()) {
+        conds.push(format!("h.note LIKE '%{}%' ESCAPE '\\'", sql_like_quote(n)));
+    }
+    let where_clause = if conds.is_empty() {
+        String::new()
+    } else {
+        format!("WHERE {}", conds.join(" AND "))
+    };
+
+    // keyset pagination: strictly after the cursor row in sort order
+    let cursor_clause = match &filter.cursor {
+        None => String::new(),
+        Some(c) => {
+            let key = if sort_by == "total_value" {
+                c.key
+                    .parse::<i64>()
+                    .map_err(|_| format!("无效的分页游标：{}", c.key))?
+                    .to_string()
+            } else {
+                format!("'{}'", sql_quote(&c.key))
+            };
+            format!(
+                "WHERE {k} {cmp} {v} OR ({k} = {v} AND id {cmp} '{id}')",
+                k = sort_key,
+                cmp = cmp,
+                v = key,
+                id = sql_quote(&c.id)
+            )
+        }
+    };
+
     task::spawn_blocking(move || {
         let rt = tokio::runtime::Runtime::new().unwrap();
         rt.block_on(async move {
@@ -220,12 +508,55 @@ pub async fn get_sales_history() -> Result<Vec<SalesSummary>, String> {
                 .await
                 .map_err(|e| e.to_string())?;
 
-            // Query to get all sales headers ordered by date (newest first)
-            let sql = r#"
-                SELECT id, date, note
-                FROM SalesHeader
-                ORDER BY date DESC, id DESC
-            "#;
+            // One round trip: pick the page of headers first, then aggregate only
+            // their items, ranking products per sale by quantity for the top 3.
+            // One extra row is fetched to know whether another page follows.
+            let sql = format!(
+                r#"
+                WITH page AS (
+                    SELECT * FROM (
+                        SELECT h.id, h.date, h.note, h.customer, h.payment_method,
+                               h.amount_paid, h.payment_status,
+                               (SELECT COALESCE(SUM(i.quantity * COALESCE(i.price, p.price, 0)), 0)
+                                  FROM SalesItem i
+                                  LEFT JOIN Product p ON p.name = i.product_name
+                                 WHERE i.sale_id = h.id) AS total_value
+                        FROM SalesHeader h
+                        {where_clause}
+                    ) AS matched
+                    {cursor_clause}
+                    ORDER BY {sort_key} {dir}, id {dir}
+                    LIMIT {fetch}
+                ),
+                ranked AS (
+                    SELECT i.sale_id, i.product_name,
+                           ROW_NUMBER() OVER (
+                               PARTITION BY i.sale_id
+                               ORDER BY SUM(i.quantity) DESC, i.product_name
+                           ) AS rn
+                    FROM SalesItem i
+                    WHERE i.sale_id IN (SELECT id FROM page)
+                    GROUP BY i.sale_id, i.product_name
+                )
+                SELECT page.*,
+                       (SELECT TOTAL(pv) FROM SalesPointsLedger l
+                         WHERE l.sale_id = page.id AND l.quantity > 0) AS total_pv,
+                       (SELECT TOTAL(bv) FROM SalesPointsLedger l
+                         WHERE l.sale_id = page.id AND l.quantity > 0) AS total_bv,
+                       MAX(CASE WHEN r.rn = 1 THEN r.product_name END) AS top1,
+                       MAX(CASE WHEN r.rn = 2 THEN r.product_name END) AS top2,
+                       MAX(CASE WHEN r.rn = 3 THEN r.product_name END) AS top3
+                FROM page
+                LEFT JOIN ranked r ON r.sale_id = page.id AND r.rn <= 3
+                GROUP BY page.id
+                ORDER BY {sort_key} {dir}, page.id {dir}
+                "#,
+                where_clause = where_clause,
+                cursor_clause = cursor_clause,
+                sort_key = sort_key,
+                dir = dir,
+                fetch = limit + 1
+            );
 
             let result = client.execute(sql).await.map_err(|e| e.to_string())?;
 
@@ -243,56 +574,64 @@ pub async fn get_sales_history() -> Result<Vec<SalesSummary>, String> {
                     .to_string();
 
                 let note = row.try_column::<&str>("note").ok().map(|s| s.to_string());
-
-                // Fetch items for this sale, join with Product to get price
-                let items_sql = format!(
-                    r#"
-                    SELECT i.product_name, SUM(i.quantity) as quantity, p.price
-                    FROM SalesItem i
-                    JOIN Product p ON i.product_name = p.name
-                    WHERE i.sale_id = '{}'
-                    GROUP BY i.product_name, p.price
-                    ORDER BY quantity DESC
-                    "#,
-                    id
-                );
-                let items_result = client.execute(items_sql).await.map_err(|e| e.to_string())?;
-
-                // Calculate total value and top 3 products
-                let mut total_value = 0i64;
-                let mut products: Vec<(String, i64)> = Vec::new();
-
-                for item_row in items_result.rows {
-                    let product_name = item_row
-                        .try_column::<&str>("product_name")
-                        .map_err(|_| "Failed to get product_name".to_string())?
-                        .to_string();
-                    let quantity = item_row
-                        .try_column::<i64>("quantity")
-                        .map_err(|_| "Failed to get quantity".to_string())?;
-                    let price = item_row
-                        .try_column::<i64>("price")
-                        .map_err(|_| "Failed to get price".to_string())?;
-
-                    total_value += quantity * price;
-                    products.push((product_name, quantity));
-                }
-
-                // take top 3;
-                let top_products: Vec<String> = products
+                let customer = row
+                    .try_column::<&str>("customer")
+                    .ok()
+                    .map(|s| s.to_string());
+                let payment_method = row
+                    .try_column::<&str>("payment_method")
+                    .ok()
+                    .map(|s| s.to_string());
+                let amount_paid = row.try_column::<i64>("amount_paid").unwrap_or(0);
+                let payment_status = row
+                    .try_column::<&str>("payment_status")
+                    .ok()
+                    .map(|s| s.to_string());
+                let total_value = row.try_column::<i64>("total_value").unwrap_or(0);
+                let total_pv = row.try_column::<f64>("total_pv").unwrap_or(0.0);
+                let total_bv = row.try_column::<f64>("total_bv").unwrap_or(0.0);
+
+                let top_products: Vec<String> = ["top1", "top2", "top3"]
                     .iter()
-                    .take(3)
-                    .map(|(name, _)| name.clone())
+                    .filter_map(|col| row.try_column::<&str>(col).ok())
+                    .map(|s| s.to_string())
                     .collect();
 
                 sales_summary.push(SalesSummary {
-                    header: SalesHeader { id, date, note },
+                    header: SalesHeader {
+                        id,
+                        date,
+                        note,
+                        customer,
+                        payment_method,
+                        amount_paid,
+                        payment_status,
+                    },
                     top_products,
                     total_value,
+                    total_pv,
+                    total_bv,
                 });
             }
 
-            Ok(sales_summary)
+            let next_cursor = if sales_summary.len() > limit as usize {
+                sales_summary.truncate(limit as usize);
+                sales_summary.last().map(|last| SalesHistoryCursor {
+                    key: match sort_by {
+                        "total_value" => last.total_value.to_string(),
+                        "customer" => last.header.customer.clone().unwrap_or_default(),
+                        _ => last.header.date.clone(),
+                    },
+                    id: last.header.id.clone(),
+                })
+            } else {
+                None
+            };
+
+            Ok(SalesHistoryPage {
+                sales: sales_summary,
+                next_cursor,
+            })
         })
     })
     .await
@@ -312,7 +651,7 @@ pub async fn get_sales_items(sale_id: String) -> Result<Vec<SalesItem>, String>
             // Query to get all items for a specific sale
             let sql = format!(
                 r#"
-                SELECT id, product_name, quantity, expiry
+                SELECT id, product_name, quantity, expiry, price
                 FROM SalesItem
                 WHERE sale_id = '{}'
                 ORDER BY product_name
@@ -344,11 +683,14 @@ pub async fn get_sales_items(sale_id: String) -> Result<Vec<SalesItem>, String>
                     .map_err(|_| "Failed to get expiry from sales item".to_string())?
                     .to_string();
 
+                let price = row.try_column::<i64>("price").ok();
+
                 sales_items.push(SalesItem {
                     id,
                     product_name,
                     quantity,
                     expiry,
+                    price,
                 });
             }
 
@@ -361,8 +703,7 @@ pub async fn get_sales_items(sale_id: String) -> Result<Vec<SalesItem>, String>
 
 #[tauri::command]
 pub async fn get_monthly_sales(months: Option<u32>) -> Result<Vec<MonthlySales>, String> {
-    use chrono::{Datelike, Duration, Local};
-    let months = months.unwrap_or(12);
+    let months = months.unwrap_or(12).max(1);
     task::spawn_blocking(move || {
         let rt = tokio::runtime::Runtime::new().unwrap();
         rt.block_on(async move {
@@ -371,41 +712,25 @@ pub async fn get_monthly_sales(months: Option<u32>) -> Result<Vec<MonthlySales>,
                 .await
                 .map_err(|e| e.to_string())?;
 
-            // Calculate the first day of the earliest month to include
-            let today = Local::now();
-            let first_month = today
-                .with_day(1)
-                .unwrap()
-                .checked_sub_signed(Duration::days(31 * (months as i64 - 1)))
-                .unwrap();
-            let first_month_str = first_month.format("%Y-%m-01").to_string();
-
-            // Query: sum of quantity * price per month
-            let sql = format!(
-                r#"
-                SELECT strftime('%Y-%m', h.date) as month,
-                       SUM(i.quantity * p.price) as total
-                FROM SalesHeader h
-                JOIN SalesItem i ON h.id = i.sale_id
-                JOIN Product p ON i.product_name = p.name
-                WHERE h.date >= '{}'
-                GROUP BY month
-                ORDER BY month ASC
-                "#,
-                first_month_str
-            );
-
-            let result = client.execute(sql).await.map_err(|e| e.to_string())?;
-            let mut out = Vec::new();
-            for row in result.rows {
-                let month = row
-                    .try_column::<&str>("month")
-                    .map_err(|_| "Failed to get month".to_string())?
-                    .to_string();
-                let total = row.try_column::<i64>("total").unwrap_or(0);
-                out.push(MonthlySales { month, total });
-            }
-            Ok(out)
+            // First day of the earliest month to include; months without sales are kept as 0
+            let to = analytics::today();
+            let from = analytics::add_months(Granularity::Month.start_of(to), -(months as i32 - 1));
+
+            let rows = analytics::daily_sales(&client, from, to, None).await?;
+            let buckets = analytics::bucketize(&rows, from, to, Granularity::Month, false)?;
+            let points = monthly_points(&client, &from.to_string(), &to.to_string()).await?;
+            Ok(buckets
+                .into_iter()
+                .map(|b| {
+                    let (pv, bv) = points.get(&b.period).copied().unwrap_or_default();
+                    MonthlySales {
+                        month: b.period,
+                        total: b.value,
+                        pv,
+                        bv,
+                    }
+                })
+                .collect())
         })
     })
     .await
@@ -454,11 +779,9 @@ pub async fn get_monthly_sales_stats() -> Result<MonthlySalesStats, String> {
             // Query for this month
             let sql_this = format!(
                 r#"
-                SELECT SUM(i.quantity * p.price) as total
-                FROM SalesHeader h
-                JOIN SalesItem i ON h.id = i.sale_id
-                JOIN Product p ON i.product_name = p.name
-                WHERE h.date >= '{}' AND h.date <= '{}'
+                SELECT SUM(amount) as total
+                FROM SalesRevenueLedger
+                WHERE date >= '{}' AND date <= '{}'
                 "#,
                 this_month_start, this_month_end
             );
@@ -472,11 +795,9 @@ pub async fn get_monthly_sales_stats() -> Result<MonthlySalesStats, String> {
             // Query for last month same period
             let sql_last = format!(
                 r#"
-                SELECT SUM(i.quantity * p.price) as total
-                FROM SalesHeader h
-                JOIN SalesItem i ON h.id = i.sale_id
-                JOIN Product p ON i.product_name = p.name
-                WHERE h.date >= '{}' AND h.date <= '{}'
+                SELECT SUM(amount) as total
+                FROM SalesRevenueLedger
+                WHERE date >= '{}' AND date <= '{}'
                 "#,
                 last_month_start, last_month_end
             );
@@ -487,9 +808,33 @@ pub async fn get_monthly_sales_stats() -> Result<MonthlySalesStats, String> {
                 .and_then(|row| row.try_column::<i64>("total").ok())
                 .unwrap_or(0);
 
+            // Points for the same two periods (each lies within a single month)
+            let (this_month_pv, this_month_bv) = monthly_points(
+                &client,
+                &this_month_start.to_string(),
+                &this_month_end.to_string(),
+            )
+            .await?
+            .into_values()
+            .next()
+            .unwrap_or_default();
+            let (last_month_same_period_pv, last_month_same_period_bv) = monthly_points(
+                &client,
+                &last_month_start.to_string(),
+                &last_month_end.to_string(),
+            )
+            .await?
+            .into_values()
+            .next()
+            .unwrap_or_default();
+
             Ok(MonthlySalesStats {
                 this_month_total,
                 last_month_same_period_total,
+                this_month_pv,
+                this_month_bv,
+                last_month_same_period_pv,
+                last_month_same_period_bv,
             })
         })
     })
@@ -0,0 +1,671 @@
+use crate::config::config;
+use crate::db::{get_db_config, sql_quote};
+use crate::exchange::exchange_value;
+use crate::receipt::{fit_text, write_pdf, PdfPages, FONT_SIZE, LINE_H, MARGIN, PAGE_W};
+use libsql_client::Client;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+use std::path::Path;
+use tokio::task;
+
+/// One loan line on a statement. Quantities and values are signed like
+/// `LoanLedger`: positive = the counterparty holds our stock.
+#[derive(Debug, Serialize)]
+pub struct StatementLine {
+    pub loan_id: String,
+    pub date: String,
+    pub direction: String,
+    pub product_name: String,
+    pub quantity: i64,
+    pub unit_price: i64, // current Product.price
+    pub value: i64,
+    pub balance_quantity: i64, // running balance of this product after the line
+    pub balance_value: i64,
+    pub note: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct StatementProduct {
+    pub product_name: String,
+    pub opening_quantity: i64,
+    pub opening_value: i64,
+    pub closing_quantity: i64,
+    pub closing_value: i64,
+}
+
+/// One exchange on a statement. `value` is what it adds to the exchange
+/// balance: positive = the counterparty owes us.
+#[derive(Debug, Serialize)]
+pub struct StatementExchange {
+    pub exchange_id: String,
+    pub date: String,
+    pub description: String,
+    pub value: i64,
+    pub balance: i64, // running exchange balance after the exchange
+    pub note: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct LoanStatement {
+    pub counterparty: String,
+    pub from: Option<String>, // "YYYY-MM-DD"; None = from the first loan
+    pub to: Option<String>,   // "YYYY-MM-DD"; None = up to today
+    pub products: Vec<StatementProduct>,
+    pub lines: Vec<StatementLine>,
+    pub opening_value: i64,
+    pub closing_value: i64,
+    pub exchanges: Vec<StatementExchange>,
+    pub exchange_opening_value: i64,
+    pub exchange_closing_value: i64,
+}
+
+/// Quantity and value of a product balance.
+type Balance = (i64, i64);
+
+fn direction_label(direction: &str) -> &str {
+    match direction {
+        "loan_out" => "借出",
+        "loan_in" => "借入",
+        "return_in" => "还入",
+        "return_out" => "还出",
+        other => other,
+    }
+}
+
+/// Run the balance of each product over `rows` (every ledger line up to the
+/// end of the period, in order): lines before `from` only build the opening
+/// balance, the rest are kept with the running balance after them. Products
+/// settled before the period are left out.
+fn fold_balances(
+    rows: Vec<StatementLine>,
+    from: Option<&str>,
+) -> (Vec<StatementLine>, Vec<StatementProduct>) {
+    // (opening, running) balance per product
+    let mut balances: BTreeMap<String, (Balance, Balance)> = BTreeMap::new();
+    let mut in_range: BTreeMap<String, bool> = BTreeMap::new();
+    let mut lines = Vec::new();
+    for line in rows {
+        let (opening, running) = balances.entry(line.product_name.clone()).or_default();
+        running.0 += line.quantity;
+        running.1 += line.value;
+        if from.is_some_and(|f| line.date.as_str() < f) {
+            *opening = *running;
+            in_range.entry(line.product_name).or_insert(false);
+            continue;
+        }
+        in_range.insert(line.product_name.clone(), true);
+        lines.push(StatementLine {
+            balance_quantity: running.0,
+            balance_value: running.1,
+            ..line
+        });
+    }
+
+    let products = balances
+        .into_iter()
+        .filter(|(name, (opening, _))| in_range[name] || opening.0 != 0)
+        .map(|(product_name, (opening, closing))| StatementProduct {
+            product_name,
+            opening_quantity: opening.0,
+            opening_value: opening.1,
+            closing_quantity: closing.0,
+            closing_value: closing.1,
+        })
+        .collect();
+    (lines, products)
+}
+
+async fn load_statement(
+    counterparty: &str,
+    from: Option<String>,
+    to: Option<String>,
+) -> Result<LoanStatement, String> {
+    let config = get_db_config().await.map_err(|e| e.to_string())?;
+    let client = Client::from_config(config)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    let to_filter = to
+        .as_deref()
+        .map(|t| format!("AND l.date <= '{}'", sql_quote(t)))
+        .unwrap_or_default();
+    let sql = format!(
+        r#"
+        SELECT l.loan_id, l.date, l.direction, l.product_name, h.note,
+               l.quantity * l.sign AS quantity,
+               COALESCE(p.price, 0) AS unit_price
+        FROM LoanLedger l
+        JOIN LoanHeader h ON h.id = l.loan_id
+        LEFT JOIN Product p ON p.name = l.product_name
+        WHERE l.counterparty = '{}' {}
+        ORDER BY l.date,
+                 CASE WHEN l.direction IN ('loan_in', 'loan_out') THEN 0 ELSE 1 END,
+                 l.loan_id, l.product_name
+        "#,
+        sql_quote(counterparty),
+        to_filter
+    );
+    let result = client.execute(sql).await.map_err(|e| e.to_string())?;
+
+    let mut rows = Vec::new();
+    for row in result.rows {
+        let quantity = row
+            .try_column::<i64>("quantity")
+            .map_err(|e| e.to_string())?;
+        let unit_price = row.try_column::<i64>("unit_price").unwrap_or(0);
+        rows.push(StatementLine {
+            loan_id: row
+                .try_column::<&str>("loan_id")
+                .map_err(|e| e.to_string())?
+                .to_string(),
+            date: row
+                .try_column::<&str>("date")
+                .map_err(|e| e.to_string())?
+                .to_string(),
+            direction: row
+                .try_column::<&str>("direction")
+                .map_err(|e| e.to_string())?
+                .to_string(),
+            product_name: row
+                .try_column::<&str>("product_name")
+                .map_err(|e| e.to_string())?
+                .to_string(),
+            quantity,
+            unit_price,
+            value: quantity * unit_price,
+            balance_quantity: 0,
+            balance_value: 0,
+            note: row.try_column::<&str>("note").ok().map(|s| s.to_string()),
+        });
+    }
+    let (lines, products) = fold_balances(rows, from.as_deref());
+
+    let to_filter = to
+        .as_deref()
+        .map(|t| format!("AND e.date <= '{}'", sql_quote(t)))
+        .unwrap_or_default();
+    let sql = format!(
+        r#"
+        SELECT e.id, e.date, e.note, e.cash, {} AS value,
+               (SELECT GROUP_CONCAT(CASE i.side WHEN 'give' THEN '换出' ELSE '换入' END
+                                    || i.product_name || '×' || i.quantity, '，')
+                FROM LoanExchangeItem i WHERE i.exchange_id = e.id) AS items
+        FROM LoanExchange e
+        WHERE e.counterparty = '{}' {}
+        ORDER BY e.date, e.id
+        "#,
+        exchange_value("e"),
+        sql_quote(counterparty),
+        to_filter
+    );
+    let result = client.execute(sql).await.map_err(|e| e.to_string())?;
+
+    let mut exchange_opening_value = 0;
+    let mut exchange_balance = 0;
+    let mut exchanges = Vec::new();
+    for row in result.rows {
+        let date = row.try_column::<&str>("date").map_err(|e| e.to_string())?;
+        let value = row.try_column::<i64>("value").unwrap_or(0);
+        exchange_balance += value;
+        if from.as_deref().is_some_and(|f| date < f) {
+            exchange_opening_value = exchange_balance;
+            continue;
+        }
+
+        let cash = row.try_column::<i64>("cash").unwrap_or(0);
+        let mut parts: Vec<String> = row
+            .try_column::<&str>("items")
+            .ok()
+            .map(|s| vec![s.to_string()])
+            .unwrap_or_default();
+        if cash > 0 {
+            parts.push(format!("对方付款 {}", cash));
+        } else if cash < 0 {
+            parts.push(format!("我方付款 {}", -cash));
+        }
+        exchanges.push(StatementExchange {
+            exchange_id: row
+                .try_column::<&str>("id")
+                .map_err(|e| e.to_string())?
+                .to_string(),
+            date: date.to_string(),
+            description: parts.join("，"),
+            value,
+            balance: exchange_balance,
+            note: row.try_column::<&str>("note").ok().map(|s| s.to_string()),
+        });
+    }
+
+    Ok(LoanStatement {
+        counterparty: counterparty.to_string(),
+        from,
+        to,
+        opening_value: products.iter().map(|p| p.opening_value).sum(),
+        closing_value: products.iter().map(|p| p.closing_value).sum(),
+        products,
+        lines,
+        exchanges,
+        exchange_opening_value,
+        exchange_closing_value: exchange_balance,
+    })
+}
+
+/// Statement of all loans with `counterparty` between `from` and `to`
+/// (inclusive, both optional): opening balance per product, every loan and
+/// return line with the product's running quantity and value, and closing
+/// balance. Values use current product prices. Exchanges with the
+/// counterparty follow with their running value balance.
+#[tauri::command]
+pub async fn get_loan_statement(
+    counterparty: String,
+    from: Option<String>,
+    to: Option<String>,
+) -> Result<LoanStatement, String> {
+    task::spawn_blocking(move || {
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        rt.block_on(load_statement(&counterparty, from, to))
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+/// Write the statement to `path`; the format follows the extension (.csv / .pdf).
+#[tauri::command]
+pub async fn export_loan_statement(
+    counterparty: String,
+    from: Option<String>,
+    to: Option<String>,
+    path: String,
+) -> Result<(), String> {
+    let ext = Path::new(&path)
+        .extension()
+        .and_then(|e| e.to_str())
+        .map(|e| e.to_ascii_lowercase())
+        .unwrap_or_default();
+    if !matches!(ext.as_str(), "csv" | "pdf") {
+        return Err("仅支持导出 .csv 或 .pdf 文件".into());
+    }
+
+    let statement = task::spawn_blocking(move || {
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        rt.block_on(load_statement(&counterparty, from, to))
+    })
+    .await
+    .map_err(|e| e.to_string())??;
+
+    let bytes = if ext == "pdf" {
+        let business_name = config().map_err(|e| e.to_string())?.business_name.clone();
+        render_pdf(&statement, &business_name)
+    } else {
+        render_csv(&statement).into_bytes()
+    };
+    std::fs::write(&path, bytes).map_err(|e| format!("写入文件失败 {}：{}", path, e))
+}
+
+fn period(s: &LoanStatement) -> String {
+    format!(
+        "{} 至 {}",
+        s.from.as_deref().unwrap_or("最早"),
+        s.to.as_deref().unwrap_or("今天")
+    )
+}
+
+// ---- CSV -----------------------------------------------------------------
+
+fn csv_field(s: &str) -> String {
+    if s.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", s.replace('"', "\"\""))
+    } else {
+        s.to_string()
+    }
+}
+
+fn render_csv(s: &LoanStatement) -> String {
+    // BOM so spreadsheet programs read the Chinese text as UTF-8
+    let mut out = String::from("\u{feff}");
+    let _ = writeln!(out, "对方,{}", csv_field(&s.counterparty));
+    let _ = writeln!(out, "期间,{}", period(s));
+    let _ = writeln!(out, "说明,数量与金额为正表示对方持有我方货品");
+    out.push('\n');
+
+    out.push_str("日期,方向,产品,数量,单价,金额,结余数量,结余金额,备注\n");
+    for l in &s.lines {
+        let _ = writeln!(
+            out,
+            "{},{},{},{},{},{},{},{},{}",
+            l.date,
+            direction_label(&l.direction),
+            csv_field(&l.product_name),
+            l.quantity,
+            l.unit_price,
+            l.value,
+            l.balance_quantity,
+            l.balance_value,
+            csv_field(l.note.as_deref().unwrap_or(""))
+        );
+    }
+    out.push('\n');
+
+    out.push_str("产品,期初数量,期初金额,期末数量,期末金额\n");
+    for p in &s.products {
+        let _ = writeln!(
+            out,
+            "{},{},{},{},{}",
+            csv_field(&p.product_name),
+            p.opening_quantity,
+            p.opening_value,
+            p.closing_quantity,
+            p.closing_value
+        );
+    }
+    let _ = writeln!(out, "合计,,{},,{}", s.opening_value, s.closing_value);
+
+    if !s.exchanges.is_empty() || s.exchange_opening_value != 0 {
+        out.push('\n');
+        out.push_str("换货日期,内容,差额,结余差额,备注\n");
+        let _ = writeln!(out, ",期初,,{},", s.exchange_opening_value);
+        for e in &s.exchanges {
+            let _ = writeln!(
+                out,
+                "{},{},{},{},{}",
+                e.date,
+                csv_field(&e.description),
+                e.value,
+                e.balance,
+                csv_field(e.note.as_deref().unwrap_or(""))
+            );
+        }
+        let _ = writeln!(out, ",期末,,{},", s.exchange_closing_value);
+    }
+    out
+}
+
+// ---- PDF -----------------------------------------------------------------
+
+// column positions of the line table
+const COL_DATE: f64 = MARGIN;
+const COL_DIRECTION: f64 = 118.0;
+const COL_PRODUCT: f64 = 158.0;
+const COL_QTY_RIGHT: f64 = 320.0;
+const COL_PRICE_RIGHT: f64 = 365.0;
+const COL_VALUE_RIGHT: f64 = 420.0;
+const COL_BALANCE_QTY_RIGHT: f64 = 480.0;
+const COL_BALANCE_VALUE_RIGHT: f64 = PAGE_W - MARGIN;
+
+fn lines_header(p: &mut PdfPages) {
+    p.text(COL_DATE, FONT_SIZE, "日期");
+    p.text(COL_DIRECTION, FONT_SIZE, "方向");
+    p.text(COL_PRODUCT, FONT_SIZE, "产品");
+    p.text_right(COL_QTY_RIGHT, FONT_SIZE, "数量");
+    p.text_right(COL_PRICE_RIGHT, FONT_SIZE, "单价");
+    p.text_right(COL_VALUE_RIGHT, FONT_SIZE, "金额");
+    p.text_right(COL_BALANCE_QTY_RIGHT, FONT_SIZE, "结余数量");
+    p.text_right(COL_BALANCE_VALUE_RIGHT, FONT_SIZE, "结余金额");
+    p.next_line(1.0);
+    p.rule();
+}
+
+fn products_header(p: &mut PdfPages) {
+    p.text(COL_DATE, FONT_SIZE, "产品");
+    p.text_right(COL_QTY_RIGHT, FONT_SIZE, "期初数量");
+    p.text_right(COL_BALANCE_QTY_RIGHT - 55.0, FONT_SIZE, "期初金额");
+    p.text_right(COL_BALANCE_QTY_RIGHT, FONT_SIZE, "期末数量");
+    p.text_right(COL_BALANCE_VALUE_RIGHT, FONT_SIZE, "期末金额");
+    p.next_line(1.0);
+    p.rule();
+}
+
+fn exchanges_header(p: &mut PdfPages) {
+    p.text(COL_DATE, FONT_SIZE, "换货日期");
+    p.text(COL_DIRECTION, FONT_SIZE, "内容");
+    p.text_right(COL_BALANCE_QTY_RIGHT, FONT_SIZE, "差额");
+    p.text_right(COL_BALANCE_VALUE_RIGHT, FONT_SIZE, "结余差额");
+    p.next_line(1.0);
+    p.rule();
+}
+
+fn render_pdf(s: &LoanStatement, business_name: &str) -> Vec<u8> {
+    let mut p = PdfPages::default();
+    p.new_page();
+
+    if !business_name.is_empty() {
+        p.text_center(18.0, business_name);
+        p.next_line(1.6);
+    }
+    p.text_center(14.0, "借货对账单");
+    p.next_line(1.8);
+    for m in [
+        format!("对方：{}", s.counterparty),
+        format!("期间：{}", period(s)),
+        "数量与金额为正表示对方持有我方货品，为负表示我方持有对方货品。".to_string(),
+    ] {
+        p.text(MARGIN, FONT_SIZE, &m);
+        p.next_line(1.0);
+    }
+    p.next_line(0.5);
+
+    lines_header(&mut p);
+    for l in &s.lines {
+        if p.y < MARGIN + LINE_H * 2.0 {
+            p.new_page();
+            lines_header(&mut p);
+        }
+        let name = fit_text(
+            &l.product_name,
+            FONT_SIZE,
+            COL_QTY_RIGHT - COL_PRODUCT - 40.0,
+        );
+        p.text(COL_DATE, FONT_SIZE, &l.date);
+        p.text(COL_DIRECTION, FONT_SIZE, direction_label(&l.direction));
+        p.text(COL_PRODUCT, FONT_SIZE, &name);
+        p.text_right(COL_QTY_RIGHT, FONT_SIZE, &l.quantity.to_string());
+        p.text_right(COL_PRICE_RIGHT, FONT_SIZE, &l.unit_price.to_string());
+        p.text_right(COL_VALUE_RIGHT, FONT_SIZE, &l.value.to_string());
+        p.text_right(
+            COL_BALANCE_QTY_RIGHT,
+            FONT_SIZE,
+            &l.balance_quantity.to_string(),
+        );
+        p.text_right(
+            COL_BALANCE_VALUE_RIGHT,
+            FONT_SIZE,
+            &l.balance_value.to_string(),
+        );
+        p.next_line(1.0);
+    }
+    p.rule();
+    p.next_line(1.0);
+
+    if p.y < MARGIN + LINE_H * 4.0 {
+        p.new_page();
+    }
+    products_header(&mut p);
+    for prod in &s.products {
+        if p.y < MARGIN + LINE_H * 2.0 {
+            p.new_page();
+            products_header(&mut p);
+        }
+        let name = fit_text(
+            &prod.product_name,
+            FONT_SIZE,
+            COL_QTY_RIGHT - COL_DATE - 60.0,
+        );
+        p.text(COL_DATE, FONT_SIZE, &name);
+        p.text_right(COL_QTY_RIGHT, FONT_SIZE, &prod.opening_quantity.to_string());
+        p.text_right(
+            COL_BALANCE_QTY_RIGHT - 55.0,
+            FONT_SIZE,
+            &prod.opening_value.to_string(),
+        );
+        p.text_right(
+            COL_BALANCE_QTY_RIGHT,
+            FONT_SIZE,
+            &prod.closing_quantity.to_string(),
+        );
+        p.text_right(
+            COL_BALANCE_VALUE_RIGHT,
+            FONT_SIZE,
+            &prod.closing_value.to_string(),
+        );
+        p.next_line(1.0);
+    }
+    p.rule();
+    if p.y < MARGIN + LINE_H {
+        p.new_page();
+    }
+    p.text(COL_DATE, FONT_SIZE, "合计");
+    p.text_right(
+        COL_BALANCE_QTY_RIGHT - 55.0,
+        FONT_SIZE,
+        &s.opening_value.to_string(),
+    );
+    p.text_right(
+        COL_BALANCE_VALUE_RIGHT,
+        FONT_SIZE,
+        &s.closing_value.to_string(),
+    );
+    p.next_line(1.0);
+
+    if !s.exchanges.is_empty() || s.exchange_opening_value != 0 {
+        p.next_line(1.0);
+        if p.y < MARGIN + LINE_H * 4.0 {
+            p.new_page();
+        }
+        exchanges_header(&mut p);
+        p.text(COL_DIRECTION, FONT_SIZE, "期初");
+        p.text_right(
+            COL_BALANCE_VALUE_RIGHT,
+            FONT_SIZE,
+            &s.exchange_opening_value.to_string(),
+        );
+        p.next_line(1.0);
+        for e in &s.exchanges {
+            if p.y < MARGIN + LINE_H * 2.0 {
+                p.new_page();
+                exchanges_header(&mut p);
+            }
+            let description = fit_text(
+                &e.description,
+                FONT_SIZE,
+                COL_BALANCE_QTY_RIGHT - COL_DIRECTION - 60.0,
+            );
+            p.text(COL_DATE, FONT_SIZE, &e.date);
+            p.text(COL_DIRECTION, FONT_SIZE, &description);
+            p.text_right(COL_BALANCE_QTY_RIGHT, FONT_SIZE, &e.value.to_string());
+            p.text_right(COL_BALANCE_VALUE_RIGHT, FONT_SIZE, &e.balance.to_string());
+            p.next_line(1.0);
+        }
+        p.rule();
+        if p.y < MARGIN + LINE_H {
+            p.new_page();
+        }
+        p.text(COL_DIRECTION, FONT_SIZE, "期末");
+        p.text_right(
+            COL_BALANCE_VALUE_RIGHT,
+            FONT_SIZE,
+            &s.exchange_closing_value.to_string(),
+        );
+    }
+
+    write_pdf(&p.pages)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn line(date: &str, direction: &str, product: &str, quantity: i64) -> StatementLine {
+        StatementLine {
+            loan_id: format!("{}-{}", date, product),
+            date: date.to_string(),
+            direction: direction.to_string(),
+            product_name: product.to_string(),
+            quantity,
+            unit_price: 10,
+            value: quantity * 10,
+            balance_quantity: 0,
+            balance_value: 0,
+            note: None,
+        }
+    }
+
+    #[test]
+    fn csv_field_quotes_only_when_needed() {
+        assert_eq!(csv_field("plain 文本"), "plain 文本");
+        assert_eq!(csv_field("a,b"), "\"a,b\"");
+        assert_eq!(csv_field("say \"hi\""), "\"say \"\"hi\"\"\"");
+        assert_eq!(csv_field("two\nlines"), "\"two\nlines\"");
+        assert_eq!(csv_field("cr\r"), "\"cr\r\"");
+    }
+
+    #[test]
+    fn opening_balance_from_lines_before_the_period() {
+        let rows = vec![
+            line("2025-01-05", "loan_out", "A", 5),
+            line("2025-01-20", "return_in", "A", -2),
+            line("2025-02-03", "loan_out", "A", 4),
+            line("2025-02-10", "return_in", "A", -1),
+        ];
+        let (lines, products) = fold_balances(rows, Some("2025-02-01"));
+        assert_eq!(lines.len(), 2);
+        assert_eq!((lines[0].balance_quantity, lines[0].balance_value), (7, 70));
+        assert_eq!((lines[1].balance_quantity, lines[1].balance_value), (6, 60));
+        assert_eq!(products.len(), 1);
+        let p = &products[0];
+        assert_eq!((p.opening_quantity, p.opening_value), (3, 30));
+        assert_eq!((p.closing_quantity, p.closing_value), (6, 60));
+    }
+
+    #[test]
+    fn products_settled_before_the_period_are_left_out() {
+        let rows = vec![
+            line("2025-01-05", "loan_out", "A", 2),
+            line("2025-01-06", "return_in", "A", -2),
+            line("2025-01-07", "loan_in", "B", -3),
+            line("2025-02-02", "loan_out", "C", 1),
+        ];
+        let (lines, products) = fold_balances(rows, Some("2025-02-01"));
+        assert_eq!(lines.len(), 1);
+        let names: Vec<_> = products.iter().map(|p| p.product_name.as_str()).collect();
+        // B is still open from before the period, with no line in it
+        assert_eq!(names, ["B", "C"]);
+        assert_eq!(products[0].opening_quantity, -3);
+        assert_eq!(products[0].closing_quantity, -3);
+    }
+
+    #[test]
+    fn without_from_everything_is_in_range() {
+        let rows = vec![
+            line("2025-01-05", "loan_out", "A", 2),
+            line("2025-01-06", "return_in", "A", -2),
+        ];
+        let (lines, products) = fold_balances(rows, None);
+        assert_eq!(lines.len(), 2);
+        assert_eq!(products[0].opening_quantity, 0);
+        assert_eq!(products[0].closing_quantity, 0);
+    }
+
+    #[test]
+    fn csv_escapes_names_and_notes() {
+        let mut l = line("2025-02-03", "loan_out", "A, large", 1);
+        l.note = Some("line\nbreak".to_string());
+        let statement = LoanStatement {
+            counterparty: "Li \"Lei\"".to_string(),
+            from: None,
+            to: None,
+            products: Vec::new(),
+            lines: vec![l],
+            opening_value: 0,
+            closing_value: 0,
+            exchanges: Vec::new(),
+            exchange_opening_value: 0,
+            exchange_closing_value: 0,
+        };
+        let csv = render_csv(&statement);
+        assert!(csv.starts_with('\u{feff}'));
+        assert!(csv.contains("对方,\"Li \"\"Lei\"\"\"\n"));
+        assert!(csv.contains("2025-02-03,借出,\"A, large\",1,10,10,0,0,\"line\nbreak\"\n"));
+        assert!(!csv.contains("换货日期"));
+    }
+}
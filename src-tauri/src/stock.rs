@@ -1,16 +1,20 @@
-use libsql_client::Client;
+use libsql_client::{Client, Transaction};
 use serde::{Deserialize, Serialize};
 use tokio::task;
 use uuid::Uuid;
 
 use crate::db::{ignore_empty_baton_commit, sql_quote};
-use crate::sales::add_sale;
+use crate::sales::{insert_sale, SalesHeader, SalesItem};
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct StockChange {
     pub name: String,
     pub expiry_date: String,
     pub qty: i64,
+    #[serde(default)]
+    pub price: Option<i64>, // sale price per unit, only used when removing as a sale
+    #[serde(default)]
+    pub cost: Option<i64>, // purchase cost per unit, only used when adding; None = Product.cost
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -108,10 +112,16 @@ pub async fn add_stock(changes: Vec<StockChange>) -> Result<(), String> {
             let client = Client::from_config(config)
                 .await
                 .map_err(|e| e.to_string())?;
+            let tx = client.transaction().await.map_err(|e| e.to_string())?;
+            let today = chrono::Local::now().format("%Y-%m-%d").to_string();
+
             for c in changes {
                 if c.qty <= 0 {
                     return Err(format!("数量必须为正数：{} - {}", c.name, c.qty));
                 }
+                if c.cost.is_some_and(|v| v < 0) {
+                    return Err(format!("进货单价不能为负数：{}", c.name));
+                }
 
                 let id = Uuid::new_v4().to_string();
                 let name = sql_quote(&c.name);
@@ -128,11 +138,16 @@ pub async fn add_stock(changes: Vec<StockChange>) -> Result<(), String> {
                     c.qty
                 );
 
-                let res = client.execute(sql).await.map_err(|e| e.to_string())?;
+                let res = tx.execute(sql).await.map_err(|e| e.to_string())?;
                 if res.rows_affected == 0 {
                     return Err("插入/更新失败：未影响任何行。".into());
                 }
+
+                insert_receipt(&tx, &today, &c).await?;
             }
+
+            let res = tx.commit().await;
+            ignore_empty_baton_commit(res)?;
             Ok(())
         })
     })
@@ -140,9 +155,168 @@ pub async fn add_stock(changes: Vec<StockChange>) -> Result<(), String> {
     .map_err(|e| e.to_string())?
 }
 
+/// Record a `StockReceipt` for a change added to stock on `date`, with its unit
+/// cost, falling back to the product's default cost.
+pub async fn insert_receipt(
+    tx: &Transaction<'_>,
+    date: &str,
+    change: &StockChange,
+) -> Result<(), String> {
+    let name = sql_quote(&change.name);
+    let cost_sql = match change.cost {
+        Some(v) => v.to_string(),
+        None => format!("(SELECT cost FROM Product WHERE name = '{}')", name),
+    };
+    tx.execute(format!(
+        "INSERT INTO StockReceipt (id, date, name, expiry, quantity, unit_cost)
+         VALUES ('{}', '{}', '{}', '{}', {}, {});",
+        sql_quote(&Uuid::new_v4().to_string()),
+        sql_quote(date),
+        name,
+        sql_quote(&change.expiry_date),
+        change.qty,
+        cost_sql
+    ))
+    .await
+    .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+/// Decrement one (name, expiry) lot inside `tx`, failing if the lot cannot cover `qty`.
+pub async fn take_from_lot(
+    tx: &Transaction<'_>,
+    name: &str,
+    expiry: &str,
+    qty: i64,
+) -> Result<(), String> {
+    let name_q = sql_quote(name);
+    let expiry_q = sql_quote(expiry);
+
+    // Check available
+    let check_sql = format!(
+        "SELECT quantity AS quantity
+         FROM Stock
+         WHERE name = '{}' AND expiry = '{}'",
+        name_q, expiry_q
+    );
+    let rows = tx.execute(check_sql).await.map_err(|e| e.to_string())?.rows;
+
+    let mut avail: i64 = 0;
+    if let Some(row) = rows.into_iter().next() {
+        avail = row
+            .try_column::<i64>("quantity")
+            .map_err(|e| e.to_string())?;
+    }
+
+    if avail <= 0 {
+        return Err(format!("无库存：{} - {}", name, expiry));
+    }
+    if qty > avail {
+        return Err(format!(
+            "数量超出库存：{} {}（可用 {}）",
+            name, expiry, avail
+        ));
+    }
+
+    // Apply decrement
+    let upd_sql = format!(
+        "UPDATE Stock
+         SET quantity = quantity - {}
+         WHERE name = '{}' AND expiry = '{}'",
+        qty, name_q, expiry_q
+    );
+    let upd_res = tx.execute(upd_sql).await.map_err(|e| e.to_string())?;
+    if upd_res.rows_affected == 0 {
+        return Err("更新失败：未影响任何行。".into());
+    }
+
+    // Optional cleanup of zero/negative rows
+    let del_sql = format!(
+        "DELETE FROM Stock
+         WHERE name = '{}' AND expiry = '{}' AND quantity <= 0",
+        name_q, expiry_q
+    );
+    tx.execute(del_sql).await.map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+/// Add `qty` to one (name, expiry) lot inside `tx`, creating the lot if it does not exist.
+pub async fn add_to_lot(
+    tx: &Transaction<'_>,
+    name: &str,
+    expiry: &str,
+    qty: i64,
+) -> Result<(), String> {
+    let sql = format!(
+        "INSERT INTO Stock (id, name, expiry, quantity)
+         VALUES ('{}','{}','{}',{})
+         ON CONFLICT(name, expiry)
+         DO UPDATE SET quantity = Stock.quantity + excluded.quantity;",
+        sql_quote(&Uuid::new_v4().to_string()),
+        sql_quote(name),
+        sql_quote(expiry),
+        qty
+    );
+    let res = tx.execute(sql).await.map_err(|e| e.to_string())?;
+    if res.rows_affected == 0 {
+        return Err("插入/更新失败：未影响任何行。".into());
+    }
+    Ok(())
+}
+
+/// Apply a signed quantity change to one lot: positive adds stock, negative takes
+/// stock (with the same availability checks as `remove_stock`).
+pub async fn apply_lot_delta(
+    tx: &Transaction<'_>,
+    name: &str,
+    expiry: &str,
+    delta: i64,
+) -> Result<(), String> {
+    match delta.signum() {
+        1 => add_to_lot(tx, name, expiry, delta).await,
+        -1 => take_from_lot(tx, name, expiry, -delta).await,
+        _ => Ok(()),
+    }
+}
+
+/// Take each change from its lot inside `tx` and, when `sale` is given, record
+/// the changes as the items of that sale in the same transaction.
+pub async fn take_stock(
+    tx: &Transaction<'_>,
+    changes: &[StockChange],
+    sale: Option<&SalesHeader>,
+) -> Result<(), String> {
+    for c in changes {
+        if c.qty <= 0 {
+            // No need to explicitly rollback; not committing will abort the tx.
+            return Err(format!("数量必须为正数：{} - {}", c.name, c.qty));
+        }
+        take_from_lot(tx, &c.name, &c.expiry_date, c.qty).await?;
+    }
+
+    if let Some(header) = sale {
+        let items: Vec<SalesItem> = changes
+            .iter()
+            .map(|c| SalesItem {
+                id: Uuid::new_v4().to_string(),
+                product_name: c.name.clone(),
+                quantity: c.qty,
+                expiry: c.expiry_date.clone(),
+                price: c.price,
+            })
+            .collect();
+        insert_sale(tx, header, &items).await?;
+    }
+    Ok(())
+}
+
 #[tauri::command]
-pub async fn remove_stock(changes: Vec<StockChange>, mark_as_sale: bool) -> Result<(), String> {
-    let changes = task::spawn_blocking(move || {
+pub async fn remove_stock(
+    changes: Vec<StockChange>,
+    mark_as_sale: bool,
+    sale: Option<SalesHeader>,
+) -> Result<(), String> {
+    task::spawn_blocking(move || {
         let rt = tokio::runtime::Runtime::new().unwrap();
         rt.block_on(async move {
             // 1) Connect
@@ -156,77 +330,25 @@ pub async fn remove_stock(changes: Vec<StockChange>, mark_as_sale: bool) -> Resu
             // 2) Begin transaction (recommended pattern)
             let tx = client.transaction().await.map_err(|e| e.to_string())?;
 
-            // 3) Validate & apply each change
-            for c in &changes {
-                if c.qty <= 0 {
-                    // No need to explicitly rollback; not committing will abort the tx.
-                    return Err(format!("数量必须为正数：{} - {}", c.name, c.qty));
-                }
-
-                let name = sql_quote(&c.name);
-                let expiry = sql_quote(&c.expiry_date);
-
-                // Check available
-                let check_sql = format!(
-                    "SELECT quantity AS quantity
-                     FROM Stock
-                     WHERE name = '{}' AND expiry = '{}'",
-                    name, expiry
-                );
-                let rows = tx.execute(check_sql).await.map_err(|e| e.to_string())?.rows;
-
-                let mut avail: i64 = 0;
-                if let Some(row) = rows.into_iter().next() {
-                    avail = row
-                        .try_column::<i64>("quantity")
-                        .map_err(|e| e.to_string())?;
-                }
-
-                if avail <= 0 {
-                    return Err(format!("无库存：{} - {}", c.name, c.expiry_date));
-                }
-                if c.qty > avail {
-                    return Err(format!(
-                        "数量超出库存：{} {}（可用 {}）",
-                        c.name, c.expiry_date, avail
-                    ));
-                }
-
-                // Apply decrement
-                let upd_sql = format!(
-                    "UPDATE Stock
-                     SET quantity = quantity - {}
-                     WHERE name = '{}' AND expiry = '{}'",
-                    c.qty, name, expiry
-                );
-                let upd_res = tx.execute(upd_sql).await.map_err(|e| e.to_string())?;
-                if upd_res.rows_affected == 0 {
-                    return Err("更新失败：未影响任何行。".into());
-                }
-
-                // Optional cleanup of zero/negative rows
-                let del_sql = format!(
-                    "DELETE FROM Stock
-                     WHERE name = '{}' AND expiry = '{}' AND quantity <= 0",
-                    name, expiry
-                );
-                tx.execute(del_sql).await.map_err(|e| e.to_string())?;
-            }
+            // 3) Take the stock, recording the sale if asked (defaults to today, no note)
+            let header = mark_as_sale.then(|| {
+                sale.unwrap_or_else(|| SalesHeader {
+                    id: Uuid::new_v4().to_string(),
+                    date: chrono::Local::now().format("%Y-%m-%d").to_string(),
+                    payment_status: Some("paid".to_string()),
+                    ..Default::default()
+                })
+            });
+            take_stock(&tx, &changes, header.as_ref()).await?;
 
             // 4) Commit, note we need to handle potential empty baton error message gracefully
             let commit_res = tx.commit().await;
             ignore_empty_baton_commit(commit_res)?;
-            Ok(changes)
+            Ok(())
         })
     })
     .await
-    .map_err(|e| e.to_string())??;
-
-    if mark_as_sale {
-        return add_sale(changes, None).await;
-    } else {
-        Ok(())
-    }
+    .map_err(|e| e.to_string())?
 }
 
 #[tauri::command]
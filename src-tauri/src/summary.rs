@@ -1,5 +1,7 @@
+use crate::analytics;
 use crate::config::get_alert_period;
 use crate::db::{get_db_config, sql_quote};
+use chrono::Duration;
 use libsql_client::Client;
 use serde::{Deserialize, Serialize};
 use tokio::task;
@@ -19,6 +21,20 @@ pub struct ExpiryBucket {
     pub quantity: i64,
 }
 
+#[derive(Debug, Serialize)]
+pub struct LotExpiryRisk {
+    pub name: String,
+    pub r#type: Option<String>,
+    pub expiry: String,       // YYYY-MM-DD
+    pub quantity: i64,        // units in the lot now
+    pub days_left: i64,       // negative = already expired
+    pub daily_rate: f64,      // product's net units sold per day over the lookback
+    pub expected_sold: f64,   // units of this lot likely sold before it expires
+    pub expected_unsold: f64, // units likely to expire unsold
+    pub unit_price: i64,      // current Product.price
+    pub unsold_value: i64,    // expected_unsold * unit_price, rounded
+}
+
 #[tauri::command]
 pub async fn get_stock_overview() -> Result<Vec<StockSummary>, String> {
     task::spawn_blocking(move || {
@@ -126,3 +142,97 @@ pub async fn get_stock_histogram(name: String) -> Result<Vec<ExpiryBucket>, Stri
     .await
     .map_err(|e| e.to_string())?
 }
+
+/// Lot-by-lot estimate of stock that will expire before it sells.
+/// Each product sells at its average daily rate over the last `lookback_days`
+/// (default 90), and sales take the earliest-expiring lot first, so demand
+/// before a lot expires goes to that lot only after the lots ahead of it.
+#[tauri::command]
+pub async fn get_expiry_risk(lookback_days: Option<u32>) -> Result<Vec<LotExpiryRisk>, String> {
+    let lookback = lookback_days.unwrap_or(90).clamp(7, 3650);
+    // local dates, like the rest of the reports; SQLite's 'now' is UTC
+    let today = analytics::today();
+    let since = today - Duration::days(lookback as i64);
+    task::spawn_blocking(move || {
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        rt.block_on(async move {
+            let config = get_db_config().await.map_err(|e| e.to_string())?;
+            let client = Client::from_config(config)
+                .await
+                .map_err(|e| e.to_string())?;
+
+            let sql = format!(
+                r#"
+                SELECT s.name, p.type AS ptype, s.expiry, s.quantity,
+                       COALESCE(p.price, 0) AS unit_price,
+                       CAST(julianday(s.expiry) - julianday('{today}') AS INTEGER) AS days_left,
+                       COALESCE((SELECT SUM(l.quantity)
+                                   FROM SalesRevenueLedger l
+                                  WHERE l.product_name = s.name
+                                    AND l.date > '{since}'
+                                    AND l.date <= '{today}'), 0) AS sold
+                FROM Stock s
+                JOIN Product p ON p.name = s.name
+                WHERE s.quantity > 0
+                ORDER BY s.name, s.expiry;
+                "#,
+                today = today.format("%Y-%m-%d"),
+                since = since.format("%Y-%m-%d")
+            );
+
+            let res = client.execute(sql).await.map_err(|e| e.to_string())?;
+
+            let mut out: Vec<LotExpiryRisk> = Vec::new();
+            // units of the current product already taken by earlier lots
+            let mut taken = 0.0;
+            for row in res.rows {
+                let name = row
+                    .try_column::<&str>("name")
+                    .map_err(|e| e.to_string())?
+                    .to_string();
+                let expiry = row
+                    .try_column::<&str>("expiry")
+                    .map_err(|e| e.to_string())?
+                    .to_string();
+                let quantity = row.try_column::<i64>("quantity").unwrap_or(0);
+                let unit_price = row.try_column::<i64>("unit_price").unwrap_or(0);
+                let days_left = row.try_column::<i64>("days_left").unwrap_or(0);
+                let sold = row.try_column::<i64>("sold").unwrap_or(0);
+                let r#type = row.try_column::<&str>("ptype").ok().map(|s| s.to_string());
+
+                if out.last().map(|l| l.name != name).unwrap_or(true) {
+                    taken = 0.0;
+                }
+
+                let daily_rate = sold.max(0) as f64 / lookback as f64;
+                let demand = daily_rate * days_left.max(0) as f64;
+                let expected_sold = (demand - taken).clamp(0.0, quantity as f64);
+                taken += expected_sold;
+                let expected_unsold = quantity as f64 - expected_sold;
+
+                let round2 = |x: f64| (x * 100.0).round() / 100.0;
+                out.push(LotExpiryRisk {
+                    name,
+                    r#type,
+                    expiry,
+                    quantity,
+                    days_left,
+                    daily_rate: round2(daily_rate),
+                    expected_sold: round2(expected_sold),
+                    expected_unsold: round2(expected_unsold),
+                    unit_price,
+                    unsold_value: (expected_unsold * unit_price as f64).round() as i64,
+                });
+            }
+
+            out.sort_by(|a, b| {
+                b.unsold_value
+                    .cmp(&a.unsold_value)
+                    .then_with(|| a.expiry.cmp(&b.expiry))
+            });
+            Ok(out)
+        })
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
@@ -34,13 +34,13 @@ function App() {
   const openSettings = async (lock = false, errorMsg?: string) => {
     // only fetch config if not locked (user open)
     if (lock) {
-      setInitialConfig({ url: "", token: "", alert_period: 180 });
+      setInitialConfig({ url: "", token: "", alert_period: 180, business_name: "", receipt_footer: "" });
     } else {
       try {
         const cfg = await invoke<Config>("get_config");
         setInitialConfig(cfg);
       } catch {
-        setInitialConfig({ url: "", token: "", alert_period: 180 });
+        setInitialConfig({ url: "", token: "", alert_period: 180, business_name: "", receipt_footer: "" });
       }
     }
     setSettingsError(errorMsg ?? null);
@@ -63,14 +63,26 @@ function App() {
         // Valid config -> Refresh all data & switch to default tab
         triggerRefresh(
               "viewStock",
+              "expiryRisk",
               "dashboard",
               "addStock",
               "removeStock",
               "salesHistory",
+              "receivables",
               "salesTrend",
+              "salesAnalysis",
+              "demandForecast",
+              "profitReport",
+              "refillReminder",
+              "recurringSales",
               "loanSummary",
+              "loanDue",
+              "loanAging",
+              "loanStatement",
               "loanHistory",
               "addLoan",
+              "exchanges",
+              "counterparties",
               "productManagement",
           )
         setActiveTab(DEFAULT_TAB);
@@ -104,21 +116,33 @@ function App() {
         open={showSettings}
         locked={lockSettings}
         errorText={settingsError ?? undefined}
-        initial={initialConfig ?? { url: "", token: "", alert_period: 180 }}
+        initial={initialConfig ?? { url: "", token: "", alert_period: 180, business_name: "", receipt_footer: "" }}
         onClose={() => {
           if (!lockSettings) setShowSettings(false);
         }}
         onVerified={() => {
           triggerRefresh(
               "viewStock",
+              "expiryRisk",
               "dashboard",
               "addStock",
               "removeStock",
               "salesHistory",
+              "receivables",
               "salesTrend",
+              "salesAnalysis",
+              "demandForecast",
+              "profitReport",
+              "refillReminder",
+              "recurringSales",
               "loanSummary",
+              "loanDue",
+              "loanAging",
+              "loanStatement",
               "loanHistory",
               "addLoan",
+              "exchanges",
+              "counterparties",
               "productManagement",
           )
           setLockSettings(false);
@@ -20,10 +20,12 @@ export type LineItemsTableProps = {
     product?: string;
     expiry?: string;
     qty?: string;
+    cost?: string;
     actions?: string;
   };
   disableDeleteOnSingle?: boolean;
   showExpiry?: boolean;
+  showCost?: boolean;
 };
 
 export default function LineItemsTable({
@@ -33,9 +35,10 @@ export default function LineItemsTable({
   removeRow,
   inputRefs,
   handleEnter,
-  headers = { product: "产品", expiry: "有效期", qty: "数量", actions: "操作" },
+  headers = { product: "产品", expiry: "有效期", qty: "数量", cost: "进货单价", actions: "操作" },
   disableDeleteOnSingle = true,
   showExpiry = true,
+  showCost = false,
 }: LineItemsTableProps) {
   return (
     <div className="product-table-container">
@@ -45,6 +48,7 @@ export default function LineItemsTable({
             <th style={{ width: 380 }}>{headers.product}</th>
             {showExpiry && <th style={{ width: 140 }}>{headers.expiry}</th>}
             <th style={{ width: 110 }}>{headers.qty}</th>
+            {showCost && <th style={{ width: 110 }}>{headers.cost}</th>}
             <th style={{ width: 80 }}>{headers.actions}</th>
           </tr>
         </thead>
@@ -102,6 +106,28 @@ export default function LineItemsTable({
                 />
               </td>
 
+              {showCost && (
+                <td>
+                  <input
+                    type="number"
+                    min={0}
+                    placeholder="默认成本"
+                    value={r.cost ?? ""}
+                    onChange={(e) =>
+                      setRow(
+                        r.id,
+                        (row) => ((row.cost = parseNum(e.target.value)), row)
+                      )
+                    }
+                    ref={(el) => {
+                      inputRefs.current[rowIdx] ||= [];
+                      inputRefs.current[rowIdx][3] = el;
+                    }}
+                    onKeyDown={(e) => handleEnter(e, rowIdx, 3)}
+                  />
+                </td>
+              )}
+
               <td>
                 <button
                   className="action-btn delete"
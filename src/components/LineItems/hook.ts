@@ -6,13 +6,15 @@ export type LineItem = {
     product: string;        // product name
     qty: number | null;
     expiry: string | null;  // YYYY-MM-DD or null
+    cost?: number | null;   // purchase cost per unit; only collected when adding stock
 };
 
 export function makeEmptyItem(): LineItem {
     return { id: uuidv4(), product: "", qty: null, expiry: null };
 }
 
-export const isItemEmpty = (r: LineItem) => !r.product && r.qty == null && !r.expiry;
+export const isItemEmpty = (r: LineItem) =>
+    !r.product && r.qty == null && !r.expiry && r.cost == null;
 export const isItemComplete = (r: LineItem) => !!r.product && r.qty != null && !!r.expiry;
 
 // safe numeric parser: "" -> null, valid -> number
@@ -134,6 +134,25 @@ const SettingsModal: React.FC<Props> = ({
           />
         </label>
 
+        <label style={{ display: "grid", gap: 6 }}>
+          <span>商家名称（收据抬头）</span>
+          <input
+            type="text"
+            value={form.business_name}
+            onChange={onChange("business_name")}
+          />
+        </label>
+
+        <label style={{ display: "grid", gap: 6 }}>
+          <span>收据页脚</span>
+          <input
+            type="text"
+            placeholder="例如：感谢惠顾"
+            value={form.receipt_footer}
+            onChange={onChange("receipt_footer")}
+          />
+        </label>
+
         <div className="modal-actions">
           <button className="btn primary" onClick={save} disabled={busy}>
             {busy ? "验证并保存…" : "保存"}
@@ -4,12 +4,13 @@ import {
   Clock8,
   AlertTriangle,
   ArrowLeftRight,
+  Award,
 } from "lucide-react";
 import "./DashBoard.css";
 import type { Card } from "../../types/Card";
 import { useEffect, useState } from "react";
 import { invoke } from "@tauri-apps/api/core";
-import { DashboardValueData, DashboardSalesData } from "../../types/dashboard";
+import { DashboardValueData, DashboardSalesData, LoanValuation } from "../../types/dashboard";
 
 type Props = {
   currency?: string;
@@ -26,6 +27,12 @@ function formatCurrency(n: number | undefined, currency = "¥") {
   return `${currency}${s}`;
 }
 
+function formatPoints(pv: number | undefined, bv: number | undefined) {
+  if (pv === undefined || bv === undefined) return "—";
+  const f = new Intl.NumberFormat("zh-CN", { maximumFractionDigits: 2 });
+  return `PV ${f.format(pv)} / BV ${f.format(bv)}`;
+}
+
 export default function DashboardPane({
   currency = "¥",
   onRefresh,
@@ -34,16 +41,19 @@ export default function DashboardPane({
   const [valueData, setValueData] = useState<DashboardValueData | null>(null);
   const [valueLoading, setValueLoading] = useState(false);
   const [valueError, setValueError] = useState<string | null>(null);
+  const [loanValuation, setLoanValuation] = useState<LoanValuation>("current");
 
   const [salesStats, setSalesStats] = useState<DashboardSalesData | null>(null);
   const [salesStatsLoading, setSalesStatsLoading] = useState(false);
   const [salesStatsError, setSalesStatsError] = useState<string | null>(null);
 
-  const fetchDashboardData = async () => {
+  const fetchDashboardData = async (valuation: LoanValuation = loanValuation) => {
     setValueLoading(true);
     setValueError(null);
     try {
-      const result = await invoke<DashboardValueData>("get_dashboard_summary");
+      const result = await invoke<DashboardValueData>("get_dashboard_summary", {
+        loanValuation: valuation,
+      });
       setValueData(result);
     } catch (err) {
       setValueError(err instanceof Error ? err.message : "价值总览获取失败");
@@ -57,7 +67,7 @@ export default function DashboardPane({
   setSalesStatsLoading(true);
   setSalesStatsError(null);
   try {
-    const result = await invoke<{ this_month_total: number; last_month_same_period_total: number }>("get_monthly_sales_stats");
+    const result = await invoke<DashboardSalesData>("get_monthly_sales_stats");
     setSalesStats(result);
   } catch {
     setSalesStatsError("销售业绩获取失败");
@@ -123,6 +133,19 @@ export default function DashboardPane({
       valueClass: loanPositive ? "value-loan-pos" : "value-loan-neg",
       // tiny sign hint
       subtitle: loanPositive ? "（净资产）" : "（净负债）",
+      chips: (
+        <div className="chips">
+          <span className="chip chip-plus">
+            借出 {formatCurrency(valueData?.lentOutValue, currency)}
+          </span>
+          <span className="chip chip-minus">
+            借入 {formatCurrency(valueData?.borrowedInValue, currency)}
+          </span>
+          {valueData?.overdueLoanCount ? (
+            <span className="chip chip-minus">逾期未还 {valueData.overdueLoanCount} 项</span>
+          ) : null}
+        </div>
+      ),
     },
   ] as const;
 
@@ -143,12 +166,45 @@ export default function DashboardPane({
       accentClass: "accent-sales-last",
       valueClass: "value-sales-last",
     },
+    {
+      key: "this_month_points",
+      title: "本月积分",
+      value: undefined,
+      points: formatPoints(salesStats?.this_month_pv, salesStats?.this_month_bv),
+      icon: <Award size={50} strokeWidth={2.4} />,
+      accentClass: "accent-points-this",
+      valueClass: "value-points-this",
+    },
+    {
+      key: "last_month_points",
+      title: "上月同期积分",
+      value: undefined,
+      points: formatPoints(
+        salesStats?.last_month_same_period_pv,
+        salesStats?.last_month_same_period_bv
+      ),
+      icon: <Award size={50} strokeWidth={2.4} />,
+      accentClass: "accent-points-last",
+      valueClass: "value-points-last",
+    },
   ];
 
   return (
     <div className="dash-wrap">
       <div className="dash-header">
         <h2>价值总览</h2>
+        <select
+          value={loanValuation}
+          title="借还价值的计价方式"
+          onChange={(e) => {
+            const v = e.target.value as LoanValuation;
+            setLoanValuation(v);
+            fetchDashboardData(v);
+          }}
+        >
+          <option value="current">借还按当前价</option>
+          <option value="recorded">借还按登记时价格</option>
+        </select>
         <button className="dash-refresh" onClick={handleRefresh}>
           <RefreshCw size={16} />
           <span>刷新</span>
@@ -194,6 +250,31 @@ export default function DashboardPane({
         ))}
       </div>
 
+      {!!valueData?.counterpartyLoanValues?.length && (
+        <table className="dash-loan-table">
+          <thead>
+            <tr>
+              <th align="left">往来单位</th>
+              <th align="right">借出（对方持有）</th>
+              <th align="right">借入（我方持有）</th>
+              <th align="right">净值</th>
+            </tr>
+          </thead>
+          <tbody>
+            {valueData.counterpartyLoanValues.map((v) => (
+              <tr key={v.counterparty}>
+                <td>{v.counterparty}</td>
+                <td align="right">{formatCurrency(v.lentOutValue, currency)}</td>
+                <td align="right">{formatCurrency(v.borrowedInValue, currency)}</td>
+                <td align="right">
+                  {formatCurrency(v.lentOutValue - v.borrowedInValue, currency)}
+                </td>
+              </tr>
+            ))}
+          </tbody>
+        </table>
+      )}
+
       <div className="dash-header">
         <h2>销售总览</h2>
       </div>
@@ -215,6 +296,8 @@ export default function DashboardPane({
                   <span className="dash-skeleton" />
                 ) : salesStatsError ? (
                   <span style={{ color: '#c00', fontSize: 14 }}>{salesStatsError}</span>
+                ) : "points" in c && c.points ? (
+                  c.points
                 ) : (
                   formatCurrency(c.value, currency)
                 )}
@@ -2,7 +2,7 @@ import { useEffect, useMemo, useState, useCallback } from "react";
 import { invoke } from "@tauri-apps/api/core";
 import { v4 as uuidv4 } from "uuid";
 import type { Product } from "../../types/product";
-import type { Direction } from "../../types/loan";
+import type { Counterparty, Direction } from "../../types/loan";
 
 import LineItemsTable from "../../components/LineItems/LineItemsTable";
 import { useLineItems } from "../../components/LineItems/hook";
@@ -15,6 +15,7 @@ export default function AddLoanPane({
   onDidSubmit?: () => void;
 }) {
   const [products, setProducts] = useState<Product[]>([]);
+  const [counterparties, setCounterparties] = useState<Counterparty[]>([]);
   const {
     rows,
     setRow,
@@ -32,6 +33,7 @@ export default function AddLoanPane({
     new Date().toISOString().slice(0, 10)
   );
   const [adjustStock, setAdjustStock] = useState<boolean>(true);
+  const [dueDate, setDueDate] = useState<string>("");
 
   // fetchers ----------------------------------------------------------
   const fetchProducts = useCallback(async () => {
@@ -52,11 +54,23 @@ export default function AddLoanPane({
     fetchProducts().catch((e) => console.error(e));
   }, [refreshSignal, fetchProducts]);
 
+  // known counterparties, suggested while typing to avoid near-duplicate names
+  useEffect(() => {
+    if (refreshSignal === 0) return;
+    invoke<Counterparty[]>("get_counterparties")
+      .then(setCounterparties)
+      .catch((e) => console.error(e));
+  }, [refreshSignal]);
+
   const productOptions = useMemo(
     () => products.map((p) => ({ value: p.name, label: p.name })),
     [products]
   );
 
+  // Returns without expiry go back to the lots the counterparty still holds
+  const isReturn = direction === "return_in" || direction === "return_out";
+  const expiryRequired = adjustStock && !isReturn;
+
   // Conditional validator: require expiry only when adjustStock is true
   function isRowCompleteForLoan(r: {
     product: string;
@@ -64,8 +78,8 @@ export default function AddLoanPane({
     expiry: string | null;
   }) {
     if (!r.product || r.qty == null) return false;
-    if (adjustStock) return !!r.expiry; // must specify which expiry bucket we touch
-    return true; // expiry optional when not adjusting stock
+    if (expiryRequired) return !!r.expiry; // must specify which expiry bucket we touch
+    return true; // expiry optional when not adjusting stock, or for returns
   }
 
   const submit = async () => {
@@ -75,7 +89,7 @@ export default function AddLoanPane({
     if (items.length === 0) return alert("请至少填写一条记录。");
     if (items.some((r) => !isRowCompleteForLoan(r))) {
       return alert(
-        adjustStock
+        expiryRequired
           ? "存在未填写完整的行（产品、数量、有效期均必填）。"
           : "存在未填写完整的行（产品、数量必填）。"
       );
@@ -100,6 +114,7 @@ export default function AddLoanPane({
         direction,
         counterparty: counterparty.trim(),
         note: null as string | null,
+        due_date: isReturn ? null : dueDate || null,
       },
       items: itemsPayload,
       // IMPORTANT: camelCase here → Tauri maps to snake_case in Rust
@@ -113,6 +128,7 @@ export default function AddLoanPane({
       setDirection("loan_out");
       setTxnDate(new Date().toISOString().slice(0, 10));
       setAdjustStock(true);
+      setDueDate("");
       onDidSubmit?.(); // notify parent so it can trigger viewStock refresh
       alert("提交成功！");
     } catch (e: any) {
@@ -143,8 +159,14 @@ export default function AddLoanPane({
             value={counterparty}
             onChange={(e) => setCounterparty(e.target.value)}
             placeholder="例如：徐丽"
+            list="counterparty-options"
             style={{ width: "100%" }}
           />
+          <datalist id="counterparty-options">
+            {counterparties.map((c) => (
+              <option key={c.id} value={c.name} />
+            ))}
+          </datalist>
         </div>
 
         <div style={{ display: "flex", alignItems: "center", gap: 8 }}>
@@ -171,6 +193,19 @@ export default function AddLoanPane({
           />
         </div>
 
+        {!isReturn && (
+          <div style={{ display: "flex", alignItems: "center", gap: 8 }}>
+            <label style={{ whiteSpace: "nowrap" }}>应还日期</label>
+            <input
+              type="date"
+              style={{ width: 160 }}
+              value={dueDate}
+              min={txnDate}
+              onChange={(e) => setDueDate(e.target.value)}
+            />
+          </div>
+        )}
+
         <label
           style={{
             display: "flex",
@@ -179,9 +214,11 @@ export default function AddLoanPane({
             marginLeft: 8,
           }}
           title={
-            adjustStock
-              ? "将同时调整库存（需要填写有效期）"
-              : "仅记录借还，不调整库存（有效期可留空）"
+            !adjustStock
+              ? "仅记录借还，不调整库存（有效期可留空）"
+              : isReturn
+                ? "将同时调整库存（有效期留空则按对方未还的批次归还）"
+                : "将同时调整库存（需要填写有效期）"
           }
         >
           <input
@@ -0,0 +1,241 @@
+import { useEffect, useState } from "react";
+import { invoke } from "@tauri-apps/api/core";
+import { v4 as uuidv4 } from "uuid";
+import { DataGrid, GridColDef } from "@mui/x-data-grid";
+import { Box, Button, Typography } from "@mui/material";
+import type { Counterparty } from "../../types/loan";
+
+function emptyCounterparty(): Counterparty {
+  return { id: uuidv4(), name: "", phone: null, wechat: null, address: null, note: null };
+}
+
+export default function CounterpartyPane({
+  refreshSignal,
+  onDidSubmit,
+}: {
+  refreshSignal: number;
+  onDidSubmit?: () => void;
+}) {
+  const [rows, setRows] = useState<Counterparty[]>([]);
+  const [loading, setLoading] = useState(false);
+  const [error, setError] = useState<string | null>(null);
+
+  // editor: null = closed; `original` is the stored row when editing
+  const [editing, setEditing] = useState<{ draft: Counterparty; original?: Counterparty } | null>(
+    null
+  );
+  const [merging, setMerging] = useState<{ source: Counterparty; targetId: string } | null>(null);
+
+  const fetchRows = async () => {
+    setLoading(true);
+    setError(null);
+    try {
+      setRows(await invoke<Counterparty[]>("get_counterparties"));
+    } catch (err) {
+      setError(typeof err === "string" ? err : "未能获取往来单位");
+      setRows([]);
+    } finally {
+      setLoading(false);
+    }
+  };
+
+  useEffect(() => {
+    if (refreshSignal === 0) return; // Skip initial
+    fetchRows();
+  }, [refreshSignal]);
+
+  const setDraft = (patch: Partial<Counterparty>) =>
+    setEditing((e) => (e ? { ...e, draft: { ...e.draft, ...patch } } : e));
+
+  const save = async () => {
+    if (!editing) return;
+    const { draft, original } = editing;
+    try {
+      if (!original) {
+        await invoke("create_counterparty", { counterparty: draft });
+      } else {
+        await invoke("update_counterparty", { counterparty: draft });
+        if (draft.name.trim() !== original.name) {
+          await invoke("rename_counterparty", {
+            counterpartyId: draft.id,
+            newName: draft.name,
+          });
+        }
+      }
+      setEditing(null);
+      await fetchRows();
+      onDidSubmit?.();
+    } catch (err) {
+      alert(`保存失败：${err}`);
+    }
+  };
+
+  const merge = async () => {
+    if (!merging?.targetId) return;
+    const target = rows.find((r) => r.id === merging.targetId);
+    if (
+      !confirm(
+        `将 ${merging.source.name} 的全部借货记录并入 ${target?.name}，并删除 ${merging.source.name}？`
+      )
+    )
+      return;
+    try {
+      await invoke("merge_counterparties", {
+        sourceId: merging.source.id,
+        targetId: merging.targetId,
+      });
+      setMerging(null);
+      await fetchRows();
+      onDidSubmit?.();
+    } catch (err) {
+      alert(`合并失败：${err}`);
+    }
+  };
+
+  const remove = async (c: Counterparty) => {
+    if (!confirm(`删除往来单位 ${c.name}？`)) return;
+    try {
+      await invoke("delete_counterparty", { counterpartyId: c.id });
+      await fetchRows();
+    } catch (err) {
+      alert(`删除失败：${err}`);
+    }
+  };
+
+  const columns: GridColDef[] = [
+    { field: "name", headerName: "名称", flex: 1, minWidth: 100 },
+    { field: "phone", headerName: "电话", flex: 1, valueGetter: (v) => v || "-" },
+    { field: "wechat", headerName: "微信", flex: 1, valueGetter: (v) => v || "-" },
+    { field: "address", headerName: "地址", flex: 1.5, valueGetter: (v) => v || "-" },
+    { field: "note", headerName: "备注", flex: 1, valueGetter: (v) => v || "-" },
+    { field: "loan_count", headerName: "借货笔数", type: "number", width: 90 },
+    {
+      field: "actions",
+      headerName: "操作",
+      width: 200,
+      sortable: false,
+      renderCell: (params) => (
+        <Box sx={{ display: "flex", gap: 1, alignItems: "center", height: "100%" }}>
+          <Button
+            size="small"
+            variant="outlined"
+            onClick={() => setEditing({ draft: { ...params.row }, original: params.row })}
+            sx={{ minWidth: "auto", px: 1, fontSize: "0.75rem" }}
+          >
+            编辑
+          </Button>
+          <Button
+            size="small"
+            variant="outlined"
+            onClick={() => setMerging({ source: params.row, targetId: "" })}
+            sx={{ minWidth: "auto", px: 1, fontSize: "0.75rem" }}
+          >
+            合并
+          </Button>
+          <Button
+            size="small"
+            variant="outlined"
+            color="error"
+            disabled={(params.row.loan_count ?? 0) > 0}
+            onClick={() => remove(params.row)}
+            sx={{ minWidth: "auto", px: 1, fontSize: "0.75rem" }}
+          >
+            删除
+          </Button>
+        </Box>
+      ),
+    },
+  ];
+
+  return (
+    <Box sx={{ p: 2, height: "100%", display: "flex", flexDirection: "column", gap: 1 }}>
+      <Typography variant="h5">往来单位</Typography>
+
+      <Box>
+        <button className="btn primary" onClick={() => setEditing({ draft: emptyCounterparty() })}>
+          新建往来单位
+        </button>
+      </Box>
+
+      {error && <Typography color="error">{error}</Typography>}
+
+      {editing && (
+        <Box
+          sx={{
+            display: "flex",
+            gap: 1,
+            alignItems: "center",
+            flexWrap: "wrap",
+            p: 1,
+            border: "1px solid #ddd",
+          }}
+        >
+          <label>名称</label>
+          <input value={editing.draft.name} onChange={(e) => setDraft({ name: e.target.value })} />
+          <label>电话</label>
+          <input
+            value={editing.draft.phone ?? ""}
+            onChange={(e) => setDraft({ phone: e.target.value || null })}
+          />
+          <label>微信</label>
+          <input
+            value={editing.draft.wechat ?? ""}
+            onChange={(e) => setDraft({ wechat: e.target.value || null })}
+          />
+          <label>地址</label>
+          <input
+            value={editing.draft.address ?? ""}
+            onChange={(e) => setDraft({ address: e.target.value || null })}
+          />
+          <label>备注</label>
+          <input
+            value={editing.draft.note ?? ""}
+            onChange={(e) => setDraft({ note: e.target.value || null })}
+          />
+          <button className="btn primary" onClick={save}>
+            保存
+          </button>
+          <button className="btn" onClick={() => setEditing(null)}>
+            取消
+          </button>
+        </Box>
+      )}
+
+      {merging && (
+        <Box sx={{ display: "flex", gap: 1, alignItems: "center", p: 1, border: "1px solid #ddd" }}>
+          <span>将 {merging.source.name} 并入</span>
+          <select
+            value={merging.targetId}
+            onChange={(e) => setMerging({ ...merging, targetId: e.target.value })}
+          >
+            <option value="">选择往来单位</option>
+            {rows
+              .filter((r) => r.id !== merging.source.id)
+              .map((r) => (
+                <option key={r.id} value={r.id}>
+                  {r.name}
+                </option>
+              ))}
+          </select>
+          <button className="btn primary" disabled={!merging.targetId} onClick={merge}>
+            合并
+          </button>
+          <button className="btn" onClick={() => setMerging(null)}>
+            取消
+          </button>
+        </Box>
+      )}
+
+      <Box sx={{ flex: 1, minHeight: 0 }}>
+        <DataGrid
+          rows={rows}
+          columns={columns}
+          loading={loading}
+          disableColumnMenu
+          autoPageSize
+          density="compact"
+        />
+      </Box>
+    </Box>
+  );
+}
@@ -3,7 +3,7 @@ import { useEffect, useMemo, useState, useCallback } from "react";
 import { invoke } from "@tauri-apps/api/core";
 import { v4 as uuidv4 } from "uuid";
 import type { Product } from "../../types/product";
-import type { Direction, LoanHeader, LoanItem } from "../../types/loan";
+import type { Counterparty, Direction, LoanHeader, LoanItem } from "../../types/loan";
 
 import LineItemsTable from "../../components/LineItems/LineItemsTable";
 import { LineItem, useLineItems } from "../../components/LineItems/hook";
@@ -22,12 +22,14 @@ export default function EditLoanPane({
   onSave,
 }: EditLoanPaneProps) {
   const [products, setProducts] = useState<Product[]>([]);
+  const [counterparties, setCounterparties] = useState<Counterparty[]>([]);
 
   // Form fields (editable)
   const [counterparty, setCounterparty] = useState("");
   const [direction, setDirection] = useState<Direction>("loan_out");
   const [txnDate, setTxnDate] = useState<string>("");
   const [note, setNote] = useState<string>("");
+  const [dueDate, setDueDate] = useState<string>("");
 
   const {
     rows,
@@ -80,6 +82,7 @@ export default function EditLoanPane({
       setDirection(loan.direction as Direction);
       setTxnDate(loan.date);
       setNote(loan.note || "");
+      setDueDate(loan.due_date || "");
       fetchProducts();
       fetchLoanItems();
     }
@@ -89,12 +92,20 @@ export default function EditLoanPane({
     fetchProducts().catch((e) => console.error(e));
   }, [refreshSignal, fetchProducts]);
 
+  // known counterparties, suggested while typing to avoid near-duplicate names
+  useEffect(() => {
+    if (refreshSignal === 0) return;
+    invoke<Counterparty[]>("get_counterparties")
+      .then(setCounterparties)
+      .catch((e) => console.error(e));
+  }, [refreshSignal]);
+
   const productOptions = useMemo(
     () => products.map((p) => ({ value: p.name, label: p.name })),
     [products]
   );
 
-  // Conditional validator: require expiry only when adjustStock is true
+  // Expiry may stay empty: lines then keep the lots the loan already moved
   function isRowCompleteForLoan(r: {
     product: string;
     qty: number | null;
@@ -119,6 +130,7 @@ export default function EditLoanPane({
         id: r.id || uuidv4(),
         product_name: r.product,
         quantity: r.qty!,
+        expiry: r.expiry || null,
       }));
 
       const headerPayload: LoanHeader = {
@@ -127,12 +139,16 @@ export default function EditLoanPane({
         direction,
         counterparty: counterparty.trim(),
         note: note.trim() || null,
+        due_date: dueDate || null,
       };
 
-      await invoke("update_loan", {header: headerPayload, items: itemsPayload});
+      const stockNote = await invoke<string | null>("update_loan", {
+        header: headerPayload,
+        items: itemsPayload,
+      });
       onSave();
       onClose();
-      alert("更新成功！");
+      alert(stockNote ? `更新成功！\n${stockNote}` : "更新成功！");
     } catch (e: any) {
       console.error(e);
       alert(e?.toString?.() ?? "更新失败");
@@ -181,7 +197,6 @@ export default function EditLoanPane({
           removeRow={removeRow}
           inputRefs={inputRefs}
           handleEnter={handleEnter}
-          showExpiry={false}
         />
       </div>
 
@@ -203,8 +218,14 @@ export default function EditLoanPane({
             value={counterparty}
             onChange={(e) => setCounterparty(e.target.value)}
             placeholder="例如：徐丽"
+            list="edit-counterparty-options"
             style={{ width: "100%" }}
           />
+          <datalist id="edit-counterparty-options">
+            {counterparties.map((c) => (
+              <option key={c.id} value={c.name} />
+            ))}
+          </datalist>
         </div>
 
         <div style={{ display: "flex", alignItems: "center", gap: 8 }}>
@@ -231,6 +252,17 @@ export default function EditLoanPane({
           />
         </div>
 
+        <div style={{ display: "flex", alignItems: "center", gap: 8 }}>
+          <label style={{ whiteSpace: "nowrap" }}>应还日期</label>
+          <input
+            type="date"
+            style={{ width: 160 }}
+            value={dueDate}
+            min={txnDate}
+            onChange={(e) => setDueDate(e.target.value)}
+          />
+        </div>
+
         <div style={{ display: "flex", alignItems: "center", gap: 8, flex: 1 }}>
           <label style={{ whiteSpace: "nowrap" }}>备注</label>
           <input
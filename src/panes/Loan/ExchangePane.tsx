@@ -0,0 +1,283 @@
+import { useEffect, useMemo, useState, useCallback } from "react";
+import { invoke } from "@tauri-apps/api/core";
+import { v4 as uuidv4 } from "uuid";
+import { DataGrid, GridColDef } from "@mui/x-data-grid";
+import { Button, Typography } from "@mui/material";
+import type { Product } from "../../types/product";
+import type { Counterparty, Exchange } from "../../types/loan";
+
+import LineItemsTable from "../../components/LineItems/LineItemsTable";
+import { isItemComplete, useLineItems, LineItem } from "../../components/LineItems/hook";
+
+export default function ExchangePane({
+  refreshSignal = 0,
+  onDidSubmit,
+}: {
+  refreshSignal?: number;
+  onDidSubmit?: () => void;
+}) {
+  const [products, setProducts] = useState<Product[]>([]);
+  const [counterparties, setCounterparties] = useState<Counterparty[]>([]);
+  const [exchanges, setExchanges] = useState<Exchange[]>([]);
+  const give = useLineItems();
+  const receive = useLineItems();
+
+  const [counterparty, setCounterparty] = useState("");
+  const [txnDate, setTxnDate] = useState<string>(() => new Date().toISOString().slice(0, 10));
+  const [note, setNote] = useState("");
+  const [cash, setCash] = useState(""); // > 0 they pay us, < 0 we pay them
+
+  const fetchExchanges = useCallback(async () => {
+    try {
+      setExchanges(await invoke<Exchange[]>("get_exchanges", { counterparty: null }));
+    } catch (e) {
+      console.error(e);
+      setExchanges([]);
+    }
+  }, []);
+
+  // Do not load until refreshSignal changes
+  useEffect(() => {
+    if (refreshSignal === 0) return;
+    invoke<Product[]>("get_all_products")
+      .then((list) =>
+        setProducts(
+          [...list].sort((a, b) =>
+            a.name.localeCompare(b.name, undefined, { sensitivity: "base", numeric: true })
+          )
+        )
+      )
+      .catch((e) => console.error(e));
+    invoke<Counterparty[]>("get_counterparties")
+      .then(setCounterparties)
+      .catch((e) => console.error(e));
+    fetchExchanges();
+  }, [refreshSignal, fetchExchanges]);
+
+  const productOptions = useMemo(
+    () => products.map((p) => ({ value: p.name, label: p.name })),
+    [products]
+  );
+
+  // Value of a side at current member prices, the default agreed value
+  const valueOf = (rows: LineItem[]) =>
+    rows.reduce(
+      (sum, r) => sum + (r.qty ?? 0) * (products.find((p) => p.name === r.product)?.price ?? 0),
+      0
+    );
+  const giveValue = valueOf(give.nonGhostRows);
+  const receiveValue = valueOf(receive.nonGhostRows);
+
+  const submit = async () => {
+    if (!counterparty.trim()) return alert("请填写往来单位（counterparty）。");
+    if (!txnDate) return alert("请选择交易日期。");
+    const cashValue = parseInt(cash, 10) || 0;
+    const cashOnly = give.nonGhostRows.length === 0 && receive.nonGhostRows.length === 0;
+    if (cashOnly && cashValue === 0) {
+      return alert("换出和换入都至少需要一条记录，或填写现金结清差额。");
+    }
+    if (!cashOnly && (give.nonGhostRows.length === 0 || receive.nonGhostRows.length === 0)) {
+      return alert("换出和换入都至少需要一条记录。");
+    }
+    if ([...give.nonGhostRows, ...receive.nonGhostRows].some((r) => !isItemComplete(r))) {
+      return alert("存在未填写完整的行（产品、数量、有效期均必填）。");
+    }
+
+    const toItems = (rows: LineItem[], side: "give" | "receive") =>
+      rows.map((r) => ({
+        id: uuidv4(),
+        side,
+        product_name: r.product,
+        expiry: r.expiry!,
+        quantity: r.qty!, // safe due to validation above
+        unit_value: null,
+      }));
+
+    try {
+      await invoke("create_exchange", {
+        header: {
+          id: uuidv4(),
+          date: txnDate,
+          counterparty: counterparty.trim(),
+          note: note.trim() || null,
+          cash: cashValue,
+        },
+        items: [...toItems(give.nonGhostRows, "give"), ...toItems(receive.nonGhostRows, "receive")],
+      });
+      give.reset();
+      receive.reset();
+      setCounterparty("");
+      setNote("");
+      setCash("");
+      setTxnDate(new Date().toISOString().slice(0, 10));
+      fetchExchanges();
+      onDidSubmit?.();
+      alert("提交成功！");
+    } catch (e: any) {
+      console.error(e);
+      alert(e?.toString?.() ?? "提交失败");
+    }
+  };
+
+  const handleDelete = async (exchangeId: string) => {
+    try {
+      await invoke("delete_exchange", { exchangeId });
+      fetchExchanges();
+      onDidSubmit?.();
+    } catch (err) {
+      alert(`删除失败：${err}`);
+    }
+  };
+
+  const describe = (x: Exchange, side: "give" | "receive") =>
+    x.items
+      .filter((i) => i.side === side)
+      .map((i) => `${i.product_name}×${i.quantity}`)
+      .join("，");
+
+  const columns: GridColDef[] = [
+    { field: "date", headerName: "日期", width: 110, valueGetter: (_, x: Exchange) => x.header.date },
+    {
+      field: "counterparty",
+      headerName: "对方姓名",
+      flex: 1,
+      minWidth: 100,
+      valueGetter: (_, x: Exchange) => x.header.counterparty,
+    },
+    { field: "give", headerName: "换出", flex: 2, valueGetter: (_, x: Exchange) => describe(x, "give") },
+    {
+      field: "receive",
+      headerName: "换入",
+      flex: 2,
+      valueGetter: (_, x: Exchange) => describe(x, "receive"),
+    },
+    {
+      field: "balance",
+      headerName: "差额",
+      type: "number",
+      width: 90,
+      description: "换出价值减换入价值及现金；正数为对方欠我方",
+    },
+    {
+      field: "cash",
+      headerName: "现金",
+      type: "number",
+      width: 80,
+      description: "正数为对方付给我方，负数为我方付给对方",
+      valueGetter: (_, x: Exchange) => x.header.cash,
+    },
+    {
+      field: "note",
+      headerName: "备注",
+      flex: 1,
+      valueGetter: (_, x: Exchange) => x.header.note || "-",
+    },
+    {
+      field: "actions",
+      headerName: "操作",
+      width: 90,
+      sortable: false,
+      renderCell: (params) => (
+        <Button
+          size="small"
+          variant="outlined"
+          color="error"
+          onClick={() => {
+            if (confirm("删除这条换货记录并恢复库存？")) handleDelete(params.row.header.id);
+          }}
+          sx={{ minWidth: "auto", px: 1, fontSize: "0.75rem" }}
+        >
+          删除
+        </Button>
+      ),
+    },
+  ];
+
+  return (
+    <div className="product-pane">
+      <Typography variant="subtitle1">换出</Typography>
+      <LineItemsTable
+        rows={give.rows}
+        productOptions={productOptions}
+        setRow={give.setRow}
+        removeRow={give.removeRow}
+        inputRefs={give.inputRefs}
+        handleEnter={give.handleEnter}
+      />
+      <Typography variant="subtitle1">换入</Typography>
+      <LineItemsTable
+        rows={receive.rows}
+        productOptions={productOptions}
+        setRow={receive.setRow}
+        removeRow={receive.removeRow}
+        inputRefs={receive.inputRefs}
+        handleEnter={receive.handleEnter}
+      />
+      <Typography variant="body2" sx={{ opacity: 0.8 }}>
+        按会员价：换出 ¥{giveValue}，换入 ¥{receiveValue}，差额 ¥
+        {giveValue - receiveValue - (parseInt(cash, 10) || 0)}
+        （正数为对方欠我方；只填现金不填产品即为结清以往差额）
+      </Typography>
+
+      <div className="footer-bar" style={{ gap: 12, display: "flex", alignItems: "center" }}>
+        <div style={{ display: "flex", alignItems: "center", gap: 8, flex: 1 }}>
+          <label style={{ whiteSpace: "nowrap" }}>对方姓名</label>
+          <input
+            type="text"
+            value={counterparty}
+            onChange={(e) => setCounterparty(e.target.value)}
+            list="exchange-counterparty-options"
+            style={{ width: "100%" }}
+          />
+          <datalist id="exchange-counterparty-options">
+            {counterparties.map((c) => (
+              <option key={c.id} value={c.name} />
+            ))}
+          </datalist>
+        </div>
+
+        <div style={{ display: "flex", alignItems: "center", gap: 8 }}>
+          <label>日期</label>
+          <input
+            type="date"
+            style={{ width: 160 }}
+            value={txnDate}
+            onChange={(e) => setTxnDate(e.target.value)}
+          />
+        </div>
+
+        <div style={{ display: "flex", alignItems: "center", gap: 8 }}>
+          <label title="正数为对方付给我方，负数为我方付给对方">现金</label>
+          <input
+            type="number"
+            style={{ width: 90 }}
+            value={cash}
+            onChange={(e) => setCash(e.target.value)}
+          />
+        </div>
+
+        <div style={{ display: "flex", alignItems: "center", gap: 8 }}>
+          <label>备注</label>
+          <input type="text" value={note} onChange={(e) => setNote(e.target.value)} />
+        </div>
+
+        <div style={{ marginLeft: "auto" }}>
+          <button className="add-btn" onClick={submit}>
+            提交换货
+          </button>
+        </div>
+      </div>
+
+      <div style={{ flex: 1, minHeight: 300 }}>
+        <DataGrid
+          rows={exchanges}
+          columns={columns}
+          getRowId={(x: Exchange) => x.header.id}
+          disableColumnMenu
+          autoPageSize
+          density="compact"
+        />
+      </div>
+    </div>
+  );
+}
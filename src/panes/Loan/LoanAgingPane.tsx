@@ -0,0 +1,124 @@
+import { useEffect, useState } from "react";
+import { invoke } from "@tauri-apps/api/core";
+import { DataGrid, GridColDef } from "@mui/x-data-grid";
+import { Box, Chip, Typography } from "@mui/material";
+import type { Counterparty, LoanAging } from "../../types/loan";
+
+// Age buckets in days open: [label, upper bound inclusive]
+const buckets: [string, number][] = [
+  ["30 天内", 30],
+  ["31-60 天", 60],
+  ["61-90 天", 90],
+  ["90 天以上", Infinity],
+];
+
+const columns: GridColDef[] = [
+  { field: "date", headerName: "借货日期", width: 110 },
+  { field: "counterparty", headerName: "对方姓名", flex: 1, minWidth: 100 },
+  {
+    field: "direction",
+    headerName: "方向",
+    width: 70,
+    valueGetter: (v: string) => (v === "loan_out" ? "借出" : "借入"),
+  },
+  { field: "productName", headerName: "产品", flex: 1.5, minWidth: 140 },
+  { field: "quantity", headerName: "借货数量", type: "number", width: 90 },
+  { field: "returned", headerName: "已还", type: "number", width: 70 },
+  { field: "outstanding", headerName: "未还", type: "number", width: 70 },
+  { field: "daysOpen", headerName: "账龄(天)", type: "number", width: 90 },
+  {
+    field: "dueDate",
+    headerName: "应还日期",
+    width: 150,
+    valueGetter: (_, row: LoanAging) =>
+      row.dueDate == null
+        ? "-"
+        : row.outstanding > 0 && row.daysLeft != null && row.daysLeft < 0
+          ? `${row.dueDate}（逾期 ${-row.daysLeft} 天）`
+          : row.dueDate,
+  },
+];
+
+export default function LoanAgingPane({ refreshSignal }: { refreshSignal: number }) {
+  const [counterparties, setCounterparties] = useState<Counterparty[]>([]);
+  const [counterparty, setCounterparty] = useState("");
+  const [includeClosed, setIncludeClosed] = useState(false);
+  const [rows, setRows] = useState<LoanAging[]>([]);
+  const [loading, setLoading] = useState(false);
+  const [error, setError] = useState<string | null>(null);
+
+  useEffect(() => {
+    if (refreshSignal === 0) return; // Skip initial
+    invoke<Counterparty[]>("get_counterparties")
+      .then(setCounterparties)
+      .catch(() => setCounterparties([]));
+  }, [refreshSignal]);
+
+  useEffect(() => {
+    if (refreshSignal === 0) return; // Skip initial
+    setLoading(true);
+    setError(null);
+    invoke<LoanAging[]>("get_loan_aging", {
+      counterparty: counterparty || null,
+      includeClosed,
+    })
+      .then(setRows)
+      .catch((err) => {
+        setError(typeof err === "string" ? err : "未能获取借货账龄");
+        setRows([]);
+      })
+      .finally(() => setLoading(false));
+  }, [refreshSignal, counterparty, includeClosed]);
+
+  // Outstanding units per age bucket
+  const open = rows.filter((r) => r.outstanding > 0);
+  const bucketTotals = buckets.map(([label, max], i) => {
+    const min = i === 0 ? -Infinity : buckets[i - 1][1];
+    const total = open
+      .filter((r) => r.daysOpen > min && r.daysOpen <= max)
+      .reduce((sum, r) => sum + r.outstanding, 0);
+    return { label, total };
+  });
+
+  return (
+    <Box sx={{ p: 2, height: "100%", display: "flex", flexDirection: "column", gap: 1 }}>
+      <Typography variant="h5">借货账龄</Typography>
+
+      <Box sx={{ display: "flex", gap: 2, alignItems: "center", flexWrap: "wrap" }}>
+        <select value={counterparty} onChange={(e) => setCounterparty(e.target.value)}>
+          <option value="">全部往来单位</option>
+          {counterparties.map((c) => (
+            <option key={c.id} value={c.name}>
+              {c.name}
+            </option>
+          ))}
+        </select>
+        <label>
+          <input
+            type="checkbox"
+            checked={includeClosed}
+            onChange={(e) => setIncludeClosed(e.target.checked)}
+          />
+          显示已还清
+        </label>
+        {bucketTotals.map((b) => (
+          <Chip key={b.label} size="small" label={`${b.label}：未还 ${b.total} 件`} />
+        ))}
+      </Box>
+
+      {error && <Typography color="error">{error}</Typography>}
+
+      <Box sx={{ flex: 1, minHeight: 0 }}>
+        <DataGrid
+          rows={rows}
+          columns={columns}
+          getRowId={(row: LoanAging) => row.itemId}
+          loading={loading}
+          disableColumnMenu
+          autoPageSize
+          density="compact"
+        />
+      </Box>
+    </Box>
+  );
+}
@@ -0,0 +1,116 @@
+import { useEffect, useState } from "react";
+import { invoke } from "@tauri-apps/api/core";
+import { DataGrid, GridColDef } from "@mui/x-data-grid";
+import { Box, Typography } from "@mui/material";
+
+type LoanDue = {
+  counterparty: string;
+  productName: string;
+  productType: string | null;
+  netQuantity: number; // > 0 lent out, < 0 borrowed in
+  dueQuantity: number;
+  dueDate: string;
+  daysLeft: number; // negative = overdue
+  openSince: string;
+};
+
+const columns: GridColDef[] = [
+  { field: "counterparty", headerName: "对方姓名", flex: 1, minWidth: 100 },
+  { field: "productName", headerName: "产品", flex: 1.5, minWidth: 140 },
+  {
+    field: "netQuantity",
+    headerName: "方向",
+    width: 80,
+    valueGetter: (v: number) => (v > 0 ? "借出" : "借入"),
+  },
+  {
+    field: "dueQuantity",
+    headerName: "到期数量",
+    type: "number",
+    flex: 0.8,
+    valueGetter: (v: number) => Math.abs(v),
+  },
+  {
+    field: "netQuantity_abs",
+    headerName: "未还总数",
+    type: "number",
+    flex: 0.8,
+    valueGetter: (_, row: LoanDue) => Math.abs(row.netQuantity),
+  },
+  { field: "openSince", headerName: "最早借货", flex: 1, minWidth: 110 },
+  { field: "dueDate", headerName: "应还日期", flex: 1, minWidth: 110 },
+  {
+    field: "daysLeft",
+    headerName: "状态",
+    flex: 1,
+    valueGetter: (_, row: LoanDue) =>
+      row.daysLeft < 0
+        ? `已逾期 ${-row.daysLeft} 天`
+        : row.daysLeft === 0
+          ? "今天到期"
+          : `${row.daysLeft} 天后到期`,
+  },
+];
+
+export default function LoanDuePane({ refreshSignal }: { refreshSignal: number }) {
+  const [withinDays, setWithinDays] = useState(7);
+  const [rows, setRows] = useState<LoanDue[]>([]);
+  const [loading, setLoading] = useState(false);
+  const [error, setError] = useState<string | null>(null);
+
+  useEffect(() => {
+    if (refreshSignal === 0) return; // Skip initial
+    setLoading(true);
+    setError(null);
+    invoke<LoanDue[]>("get_loan_due_report", { withinDays })
+      .then(setRows)
+      .catch((err) => {
+        setError(typeof err === "string" ? err : "未能获取借货到期情况");
+        setRows([]);
+      })
+      .finally(() => setLoading(false));
+  }, [refreshSignal, withinDays]);
+
+  const overdue = rows.filter((r) => r.daysLeft < 0).length;
+
+  return (
+    <Box sx={{ p: 2, height: "100%", display: "flex", flexDirection: "column", gap: 1 }}>
+      <Typography variant="h5">借货到期</Typography>
+
+      <Box sx={{ display: "flex", gap: 2, alignItems: "center", flexWrap: "wrap" }}>
+        <label>
+          未来
+          <select
+            style={{ margin: "0 4px" }}
+            value={withinDays}
+            onChange={(e) => setWithinDays(Number(e.target.value))}
+          >
+            <option value={0}>0</option>
+            <option value={3}>3</option>
+            <option value={7}>7</option>
+            <option value={14}>14</option>
+            <option value={30}>30</option>
+          </select>
+          天内到期
+        </label>
+        <Typography variant="body2" sx={{ opacity: 0.8 }}>
+          共 {rows.length} 项，其中已逾期 {overdue} 项
+        </Typography>
+      </Box>
+
+      {error && <Typography color="error">{error}</Typography>}
+
+      <Box sx={{ flex: 1, minHeight: 0 }}>
+        <DataGrid
+          rows={rows}
+          columns={columns}
+          getRowId={(row: LoanDue) => `${row.counterparty}|${row.productName}`}
+          loading={loading}
+          disableColumnMenu
+          autoPageSize
+          density="compact"
+        />
+      </Box>
+    </Box>
+  );
+}
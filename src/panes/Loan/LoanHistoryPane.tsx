@@ -1,9 +1,18 @@
-import { useState, useEffect } from "react";
+import { useState, useEffect, ChangeEvent } from "react";
 import { invoke } from "@tauri-apps/api/core";
 import { DataGrid, GridColDef } from "@mui/x-data-grid";
 import { Button, Typography, Box } from "@mui/material";
-import type { LoanHeader } from "../../types/loan";
+import type { Product } from "../../types/product";
+import type {
+  Counterparty,
+  LoanHeader,
+  LoanHistoryCursor,
+  LoanHistoryFilter,
+  LoanHistoryPage,
+  LoanHistoryRow,
+} from "../../types/loan";
 import EditLoanPane from "./EditLoanPane";
+import MatchReturnDialog from "./MatchReturnDialog";
 
 interface LoanHistoryPaneProps {
   refreshSignal?: number;
@@ -18,17 +27,31 @@ export default function LoanHistoryPane({
   editingLoanId,
   onCloseEdit,
 }: LoanHistoryPaneProps) {
-  const [loans, setLoans] = useState<LoanHeader[]>([]);
+  const [loans, setLoans] = useState<LoanHistoryRow[]>([]);
   const [loading, setLoading] = useState(false);
   const [error, setError] = useState<string | null>(null);
   const [editingLoan, setEditingLoan] = useState<LoanHeader | null>(null);
+  const [matchingLoan, setMatchingLoan] = useState<LoanHeader | null>(null);
+  const [filter, setFilter] = useState<LoanHistoryFilter>({});
+  const [nextCursor, setNextCursor] = useState<LoanHistoryCursor | null>(null);
+  const [counterparties, setCounterparties] = useState<Counterparty[]>([]);
+  const [productNames, setProductNames] = useState<string[]>([]);
 
-  const fetchLoanHistory = async () => {
+  const setFilterField =
+    (key: keyof LoanHistoryFilter) =>
+    (e: ChangeEvent<HTMLInputElement | HTMLSelectElement>) =>
+      setFilter((f) => ({ ...f, [key]: e.target.value || undefined }));
+
+  // cursor = null loads the first page, otherwise appends the next one
+  const fetchLoanHistory = async (cursor: LoanHistoryCursor | null = null) => {
     setLoading(true);
     setError(null);
     try {
-      const result = await invoke<LoanHeader[]>("get_loan_history");
-      setLoans(result);
+      const page = await invoke<LoanHistoryPage>("get_loan_history", {
+        filter: { ...filter, limit: 100, cursor },
+      });
+      setLoans((prev) => (cursor ? [...prev, ...page.loans] : page.loans));
+      setNextCursor(page.next_cursor);
     } catch (err) {
       setError(
         err instanceof Error ? err.message : "未能获取借贷记录"
@@ -44,10 +67,11 @@ export default function LoanHistoryPane({
       // Find the loan with this ID and set it for editing
       const findAndEditLoan = async () => {
         try {
-          const loans = await invoke<LoanHeader[]>("get_loan_history");
-          const loanToEdit = loans.find((loan) => loan.id === editingLoanId);
-          if (loanToEdit) {
-            setEditingLoan(loanToEdit);
+          const page = await invoke<LoanHistoryPage>("get_loan_history", {
+            filter: { loan_id: editingLoanId },
+          });
+          if (page.loans.length > 0 && page.loans[0].kind === "loan") {
+            setEditingLoan(page.loans[0].header as LoanHeader);
           }
         } catch (err) {
           console.error("Error finding loan to edit:", err);
@@ -61,8 +85,17 @@ export default function LoanHistoryPane({
   useEffect(() => {
     if (refreshSignal === 0) return; // Skip initial load if signal is 0
     fetchLoanHistory();
+    invoke<Counterparty[]>("get_counterparties")
+      .then(setCounterparties)
+      .catch((err) => console.error("Error fetching counterparties:", err));
   }, [refreshSignal]);
 
+  useEffect(() => {
+    invoke<Product[]>("get_all_products")
+      .then((list) => setProductNames(list.map((p) => p.name)))
+      .catch((err) => console.error("Error fetching products:", err));
+  }, []);
+
   const getDirectionLabel = (direction: string) => {
     switch (direction) {
       case "loan_out":
@@ -73,6 +106,8 @@ export default function LoanHistoryPane({
         return "还入";
       case "return_out":
         return "还出";
+      case "exchange":
+        return "换货";
       default:
         return direction;
     }
@@ -84,45 +119,91 @@ export default function LoanHistoryPane({
       headerName: "日期",
       flex: 1,
       minWidth: 120,
+      valueGetter: (_, row: LoanHistoryRow) => row.header.date,
     },
     {
       field: "counterparty",
       headerName: "对方姓名",
       flex: 1,
       minWidth: 150,
+      valueGetter: (_, row: LoanHistoryRow) => row.header.counterparty,
     },
     {
       field: "direction",
       headerName: "方向",
       width: 100,
-      renderCell: (params) => getDirectionLabel(params.value),
+      valueGetter: (_, row: LoanHistoryRow) => getDirectionLabel(row.header.direction),
+    },
+    {
+      field: "item_count",
+      headerName: "品项",
+      type: "number",
+      width: 70,
+    },
+    {
+      field: "total_quantity",
+      headerName: "数量",
+      type: "number",
+      width: 80,
+    },
+    {
+      field: "total_value",
+      headerName: "金额",
+      type: "number",
+      width: 100,
+      description: "按登记时的会员价计算；换货为差额，正数为对方欠我方",
+    },
+    {
+      field: "due_date",
+      headerName: "应还日期",
+      width: 120,
+      valueGetter: (_, row: LoanHistoryRow) => row.header.due_date || "-",
     },
     {
       field: "note",
       headerName: "备注",
       flex: 1,
       minWidth: 200,
-      valueGetter: (value) => value || "-",
+      valueGetter: (_, row: LoanHistoryRow) => row.header.note || "-",
     },
     {
       field: "actions",
       headerName: "操作",
       width: 150,
       renderCell: (params) => (
-        <Button
-          size="small"
-          variant="outlined"
-          color="error"
-          onClick={(e) => {
-            e.stopPropagation(); // Prevent triggering row click
-            if (confirm("确定要删除这条借贷记录吗？")) {
-              handleDeleteLoan(params.row.id);
-            }
-          }}
-          sx={{ minWidth: "auto", px: 1, fontSize: "0.75rem" }}
-        >
-          删除
-        </Button>
+        <Box sx={{ display: "flex", gap: 1, alignItems: "center", height: "100%" }}>
+          {params.row.header.direction.startsWith("return_") && (
+            <Button
+              size="small"
+              variant="outlined"
+              onClick={(e) => {
+                e.stopPropagation(); // Prevent triggering row click
+                setMatchingLoan(params.row.header as LoanHeader);
+              }}
+              sx={{ minWidth: "auto", px: 1, fontSize: "0.75rem" }}
+            >
+              匹配
+            </Button>
+          )}
+          <Button
+            size="small"
+            variant="outlined"
+            color="error"
+            onClick={(e) => {
+              e.stopPropagation(); // Prevent triggering row click
+              if (params.row.kind === "exchange") {
+                if (confirm("删除这条换货记录并恢复库存？")) {
+                  handleDeleteExchange(params.row.header.id);
+                }
+              } else if (confirm("确定要删除这条借贷记录吗？")) {
+                handleDeleteLoan(params.row.header.id);
+              }
+            }}
+            sx={{ minWidth: "auto", px: 1, fontSize: "0.75rem" }}
+          >
+            删除
+          </Button>
+        </Box>
       ),
     },
   ];
@@ -130,7 +211,8 @@ export default function LoanHistoryPane({
   const handleDeleteLoan = async (loanId: string) => {
     try {
       // TODO: Implement backend delete function
-      await invoke("delete_loan", { loanId });
+      const stockNote = await invoke<string | null>("delete_loan", { loanId });
+      if (stockNote) alert(stockNote);
       fetchLoanHistory(); // Refresh the list
       onDidSubmit?.(); // Notify parent to refresh other panes
     } catch (err) {
@@ -139,11 +221,21 @@ export default function LoanHistoryPane({
     }
   };
 
+  const handleDeleteExchange = async (exchangeId: string) => {
+    try {
+      await invoke("delete_exchange", { exchangeId });
+      fetchLoanHistory();
+      onDidSubmit?.();
+    } catch (err) {
+      alert(`删除失败：${err}`);
+    }
+  };
+
   if (error) {
     return (
       <Box sx={{ p: 3, textAlign: "center" }}>
         <Typography color="error">加载失败: {error}</Typography>
-        <button onClick={fetchLoanHistory}>重试</button>
+        <button onClick={() => fetchLoanHistory()}>重试</button>
       </Box>
     );
   }
@@ -172,14 +264,67 @@ export default function LoanHistoryPane({
             借贷记录
           </Typography>
 
+          <Box
+            sx={{ display: "flex", gap: 1, alignItems: "center", flexWrap: "wrap", mb: 1 }}
+          >
+            <select value={filter.counterparty ?? ""} onChange={setFilterField("counterparty")}>
+              <option value="">全部往来单位</option>
+              {counterparties.map((c) => (
+                <option key={c.id} value={c.name}>
+                  {c.name}
+                </option>
+              ))}
+            </select>
+            <select value={filter.direction ?? ""} onChange={setFilterField("direction")}>
+              <option value="">全部方向</option>
+              <option value="loan_out">借出</option>
+              <option value="loan_in">借入</option>
+              <option value="return_in">还入</option>
+              <option value="return_out">还出</option>
+              <option value="exchange">换货</option>
+            </select>
+            <select value={filter.product_name ?? ""} onChange={setFilterField("product_name")}>
+              <option value="">全部商品</option>
+              {productNames.map((name) => (
+                <option key={name} value={name}>
+                  {name}
+                </option>
+              ))}
+            </select>
+            <input
+              type="date"
+              value={filter.date_from ?? ""}
+              onChange={setFilterField("date_from")}
+            />
+            <span>至</span>
+            <input
+              type="date"
+              value={filter.date_to ?? ""}
+              onChange={setFilterField("date_to")}
+            />
+            <select value={filter.status ?? ""} onChange={setFilterField("status")}>
+              <option value="">全部状态</option>
+              <option value="open">未结清</option>
+              <option value="closed">已结清</option>
+            </select>
+            <Button size="small" variant="contained" onClick={() => fetchLoanHistory()}>
+              查询
+            </Button>
+          </Box>
+
           <Box sx={{ flex: 1, minHeight: 0 }}>
             <DataGrid
-              rows={loans.map((loan) => ({ ...loan }))}
+              rows={loans}
               columns={columns}
+              getRowId={(row: LoanHistoryRow) => row.header.id}
               loading={loading}
               disableColumnMenu
+              disableColumnSorting
               autoPageSize
-              onRowClick={(params) => setEditingLoan(params.row)}
+              onRowClick={(params) => {
+                if (params.row.kind === "exchange") return; // swaps are edited under 换货
+                setEditingLoan(params.row.header as LoanHeader);
+              }}
               sx={{
                 height: "100%",
                 borderRadius: 1,
@@ -191,6 +336,23 @@ export default function LoanHistoryPane({
               }}
             />
           </Box>
+          {nextCursor && (
+            <Box sx={{ textAlign: "center", mt: 1 }}>
+              <Button
+                size="small"
+                disabled={loading}
+                onClick={() => fetchLoanHistory(nextCursor)}
+              >
+                加载更多
+              </Button>
+            </Box>
+          )}
+
+          <MatchReturnDialog
+            loan={matchingLoan}
+            onClose={() => setMatchingLoan(null)}
+            onSaved={() => onDidSubmit?.()}
+          />
         </>
       )}
     </Box>
@@ -0,0 +1,177 @@
+import { useEffect, useState } from "react";
+import { invoke } from "@tauri-apps/api/core";
+import { downloadDir, join } from "@tauri-apps/api/path";
+import { revealItemInDir } from "@tauri-apps/plugin-opener";
+import { DataGrid, GridColDef } from "@mui/x-data-grid";
+import { Box, Typography } from "@mui/material";
+import type { Counterparty, LoanStatement } from "../../types/loan";
+
+const directionLabels: Record<string, string> = {
+  loan_out: "借出",
+  loan_in: "借入",
+  return_in: "还入",
+  return_out: "还出",
+};
+
+const lineColumns: GridColDef[] = [
+  { field: "date", headerName: "日期", width: 110 },
+  {
+    field: "direction",
+    headerName: "类型",
+    width: 90,
+    valueGetter: (v: string) => directionLabels[v] ?? v,
+  },
+  { field: "product_name", headerName: "产品", flex: 1.5, minWidth: 140 },
+  { field: "quantity", headerName: "数量", type: "number", width: 80 },
+  { field: "unit_price", headerName: "单价", type: "number", width: 80 },
+  { field: "value", headerName: "金额", type: "number", width: 90 },
+  { field: "balance_quantity", headerName: "结余数量", type: "number", width: 90 },
+  { field: "balance_value", headerName: "结余金额", type: "number", width: 100 },
+  { field: "note", headerName: "备注", flex: 1, valueGetter: (v) => v || "-" },
+];
+
+const productColumns: GridColDef[] = [
+  { field: "product_name", headerName: "产品", flex: 1.5, minWidth: 140 },
+  { field: "opening_quantity", headerName: "期初数量", type: "number", flex: 1 },
+  { field: "opening_value", headerName: "期初金额", type: "number", flex: 1 },
+  { field: "closing_quantity", headerName: "期末数量", type: "number", flex: 1 },
+  { field: "closing_value", headerName: "期末金额", type: "number", flex: 1 },
+];
+
+const exchangeColumns: GridColDef[] = [
+  { field: "date", headerName: "换货日期", width: 110 },
+  { field: "description", headerName: "内容", flex: 2, minWidth: 200 },
+  { field: "value", headerName: "差额", type: "number", width: 90 },
+  { field: "balance", headerName: "结余差额", type: "number", width: 100 },
+  { field: "note", headerName: "备注", flex: 1, valueGetter: (v) => v || "-" },
+];
+
+export default function LoanStatementPane({ refreshSignal }: { refreshSignal: number }) {
+  const [counterparties, setCounterparties] = useState<Counterparty[]>([]);
+  const [counterparty, setCounterparty] = useState("");
+  const [from, setFrom] = useState("");
+  const [to, setTo] = useState("");
+  const [statement, setStatement] = useState<LoanStatement | null>(null);
+  const [loading, setLoading] = useState(false);
+  const [error, setError] = useState<string | null>(null);
+
+  useEffect(() => {
+    if (refreshSignal === 0) return; // Skip initial
+    invoke<Counterparty[]>("get_counterparties")
+      .then(setCounterparties)
+      .catch(() => setCounterparties([]));
+  }, [refreshSignal]);
+
+  useEffect(() => {
+    if (refreshSignal === 0 || !counterparty) {
+      setStatement(null);
+      return;
+    }
+    setLoading(true);
+    setError(null);
+    invoke<LoanStatement>("get_loan_statement", {
+      counterparty,
+      from: from || null,
+      to: to || null,
+    })
+      .then(setStatement)
+      .catch((err) => {
+        setError(typeof err === "string" ? err : "未能生成对账单");
+        setStatement(null);
+      })
+      .finally(() => setLoading(false));
+  }, [refreshSignal, counterparty, from, to]);
+
+  const handleExport = async (ext: "csv" | "pdf") => {
+    try {
+      const path = await join(
+        await downloadDir(),
+        `对账单_${counterparty}_${from || "期初"}_${to || "至今"}.${ext}`
+      );
+      await invoke("export_loan_statement", {
+        counterparty,
+        from: from || null,
+        to: to || null,
+        path,
+      });
+      await revealItemInDir(path);
+    } catch (err) {
+      console.error("Error exporting statement:", err);
+      alert(`导出对账单失败：${err}`);
+    }
+  };
+
+  return (
+    <Box sx={{ p: 2, height: "100%", display: "flex", flexDirection: "column", gap: 1 }}>
+      <Typography variant="h5">借货对账单</Typography>
+
+      <Box sx={{ display: "flex", gap: 2, alignItems: "center", flexWrap: "wrap" }}>
+        <select value={counterparty} onChange={(e) => setCounterparty(e.target.value)}>
+          <option value="">选择往来单位</option>
+          {counterparties.map((c) => (
+            <option key={c.id} value={c.name}>
+              {c.name}
+            </option>
+          ))}
+        </select>
+        <label>
+          从 <input type="date" value={from} onChange={(e) => setFrom(e.target.value)} />
+        </label>
+        <label>
+          至 <input type="date" value={to} onChange={(e) => setTo(e.target.value)} />
+        </label>
+        <button className="btn" disabled={!statement} onClick={() => handleExport("csv")}>
+          导出 CSV
+        </button>
+        <button className="btn" disabled={!statement} onClick={() => handleExport("pdf")}>
+          导出 PDF
+        </button>
+      </Box>
+
+      {error && <Typography color="error">{error}</Typography>}
+
+      {statement && (
+        <Typography variant="body2" sx={{ opacity: 0.8 }}>
+          期初结余 ¥{statement.opening_value}，期末结余 ¥{statement.closing_value}
+          （正数为对方欠我方，按当前价格计）；换货差额期初 ¥{statement.exchange_opening_value}，
+          期末 ¥{statement.exchange_closing_value}
+        </Typography>
+      )}
+
+      <Box sx={{ flex: 2, minHeight: 0 }}>
+        <DataGrid
+          rows={(statement?.lines ?? []).map((l, i) => ({ ...l, id: i }))}
+          columns={lineColumns}
+          loading={loading}
+          disableColumnMenu
+          autoPageSize
+          density="compact"
+        />
+      </Box>
+      <Box sx={{ flex: 1, minHeight: 0 }}>
+        <DataGrid
+          rows={statement?.products ?? []}
+          columns={productColumns}
+          getRowId={(row) => row.product_name}
+          loading={loading}
+          disableColumnMenu
+          autoPageSize
+          density="compact"
+        />
+      </Box>
+      {(statement?.exchanges.length ?? 0) > 0 && (
+        <Box sx={{ flex: 1, minHeight: 0 }}>
+          <DataGrid
+            rows={statement?.exchanges ?? []}
+            columns={exchangeColumns}
+            getRowId={(row) => row.exchange_id}
+            loading={loading}
+            disableColumnMenu
+            autoPageSize
+            density="compact"
+          />
+        </Box>
+      )}
+    </Box>
+  );
+}
@@ -5,7 +5,9 @@ import { invoke } from "@tauri-apps/api/core";
 import { DataGrid, GridColDef } from "@mui/x-data-grid";
 import { FormControl, InputLabel, MenuItem, Select } from "@mui/material";
 import { filter } from "fuzzaldrin-plus";
+import { v4 as uuidv4 } from "uuid";
 import TransactionDetailsModal from "./TransactionDetails";
+import SettleLoanDialog from "./SettleLoanDialog";
 
 type LoanSummary = {
   counterparty: string;
@@ -13,11 +15,14 @@ type LoanSummary = {
   productType: string | null;
   netQuantity: number;
   direction: string;
+  kind: "loan" | "exchange"; // exchange rows carry the value balance of swaps
+  netValue: number; // > 0 they owe us
 };
 
 interface LoanSummaryPaneProps {
   refreshSignal?: number;
   onEditLoan?: (loanId: string) => void; // Add this line
+  onDidSettle?: () => void;
 }
 
 const Container: FC<{ children: ReactNode }> = ({ children }) => (
@@ -40,6 +45,7 @@ const ALL = "__ALL__";
 export default function LoanSummaryPane({
   refreshSignal = 0,
   onEditLoan, // Add this line
+  onDidSettle,
 }: LoanSummaryPaneProps) {
   const [rows, setRows] = useState<LoanSummary[]>([]);
   const [search, setSearch] = useState("");
@@ -51,6 +57,8 @@ export default function LoanSummaryPane({
     productName: string;
   } | null>(null);
 
+  const [settling, setSettling] = useState<LoanSummary | null>(null);
+
   const fetchLoanSummary = useCallback(async () => {
     setLoading(true);
     try {
@@ -64,6 +72,36 @@ export default function LoanSummaryPane({
     }
   }, []);
 
+  // Settle an exchange value balance with cash: an exchange without items
+  const settleExchange = useCallback(
+    async (row: LoanSummary) => {
+      const input = prompt(
+        `${row.counterparty} 换货差额 ¥${row.netValue}，结清金额（正数为对方付给我方）：`,
+        String(row.netValue)
+      );
+      if (input === null) return;
+      const cash = parseInt(input, 10);
+      if (!cash) return alert("请填写结清金额。");
+      try {
+        await invoke("create_exchange", {
+          header: {
+            id: uuidv4(),
+            date: new Date().toISOString().slice(0, 10),
+            counterparty: row.counterparty,
+            note: "换货差额结清",
+            cash,
+          },
+          items: [],
+        });
+        fetchLoanSummary();
+        onDidSettle?.();
+      } catch (err) {
+        alert(`结清失败：${err}`);
+      }
+    },
+    [fetchLoanSummary, onDidSettle]
+  );
+
   // Do not load until refreshSignal is set (skip initial 0)
   useEffect(() => {
     if (refreshSignal === 0) return;
@@ -119,6 +157,7 @@ export default function LoanSummaryPane({
         headerName: "产品",
         flex: 1,
         minWidth: 160,
+        valueGetter: (v, row) => (row.kind === "exchange" ? "换货差额" : v),
         sortComparator: (a, b) =>
           String(a ?? "").localeCompare(String(b ?? ""), undefined, {
             sensitivity: "base",
@@ -129,7 +168,8 @@ export default function LoanSummaryPane({
         field: "productType",
         headerName: "类型",
         width: 100,
-        valueGetter: (_value, row) => row.productType ?? "未分类",
+        valueGetter: (_value, row) =>
+          row.kind === "exchange" ? "-" : row.productType ?? "未分类",
         sortComparator: (a, b) =>
           (a ?? "未分类").localeCompare(b ?? "未分类", undefined, {
             sensitivity: "base",
@@ -141,6 +181,14 @@ export default function LoanSummaryPane({
         headerName: "净数量",
         type: "number",
         width: 100,
+        valueGetter: (v, row) => (row.kind === "exchange" ? null : v ?? 0),
+      },
+      {
+        field: "netValue",
+        headerName: "净值",
+        type: "number",
+        width: 100,
+        description: "借货按当前会员价计；换货按约定价值计",
         valueGetter: (v) => v ?? 0,
       },
       {
@@ -148,14 +196,45 @@ export default function LoanSummaryPane({
         headerName: "状态",
         width: 80,
         renderCell: (params) => {
-          const value = params.row.netQuantity ?? 0;
+          const value =
+            params.row.kind === "exchange" ? params.row.netValue : params.row.netQuantity ?? 0;
           if (value > 0) return <span style={{ color: "#d32f2f" }}>借出</span>; // We owe them
           if (value < 0) return <span style={{ color: "#2e7d32" }}>借入</span>; // They owe us
           return "平衡";
         },
       },
+      {
+        field: "actions",
+        headerName: "操作",
+        width: 90,
+        sortable: false,
+        renderCell: (params) =>
+          params.row.kind === "exchange" ? (
+            <button
+              className="btn"
+              title="以现金结清换货差额"
+              onClick={(e) => {
+                e.stopPropagation();
+                settleExchange(params.row);
+              }}
+            >
+              结清
+            </button>
+          ) : (
+            <button
+              className="btn"
+              title={params.row.netQuantity > 0 ? "对方留下货品，转为销售" : "我方留下货品，转为购买"}
+              onClick={(e) => {
+                e.stopPropagation(); // don't open the transaction details
+                setSettling(params.row);
+              }}
+            >
+              结算
+            </button>
+          ),
+      },
     ],
-    []
+    [settleExchange]
   );
 
   const rowsForGrid = useMemo(
@@ -215,6 +294,7 @@ export default function LoanSummaryPane({
           disableColumnMenu
           autoPageSize
           onRowClick={(params) => {
+            if (params.row.kind === "exchange") return; // swaps are listed under 换货
             setSelectedTransaction({
               counterparty: params.row.counterparty,
               productName: params.row.productName,
@@ -245,6 +325,17 @@ export default function LoanSummaryPane({
           setSelectedTransaction(null); // Close the modal
         }}
       />
+      <SettleLoanDialog
+        open={!!settling}
+        counterparty={settling?.counterparty || ""}
+        productName={settling?.productName || ""}
+        netQuantity={settling?.netQuantity ?? 0}
+        onClose={() => setSettling(null)}
+        onSettled={() => {
+          fetchLoanSummary();
+          onDidSettle?.();
+        }}
+      />
     </Container>
   );
 }
@@ -0,0 +1,154 @@
+import { useEffect, useState } from "react";
+import { invoke } from "@tauri-apps/api/core";
+import {
+  Box,
+  Button,
+  Dialog,
+  DialogActions,
+  DialogContent,
+  DialogTitle,
+  Typography,
+} from "@mui/material";
+import type { LoanHeader, LoanItem, MatchCandidate } from "../../types/loan";
+
+interface MatchReturnDialogProps {
+  loan: LoanHeader | null; // a return_in / return_out
+  onClose: () => void;
+  onSaved: () => void;
+}
+
+export default function MatchReturnDialog({ loan, onClose, onSaved }: MatchReturnDialogProps) {
+  const [items, setItems] = useState<LoanItem[]>([]);
+  const [itemId, setItemId] = useState("");
+  const [candidates, setCandidates] = useState<MatchCandidate[]>([]);
+  const [quantities, setQuantities] = useState<Record<string, number>>({});
+  const [saving, setSaving] = useState(false);
+
+  useEffect(() => {
+    if (!loan) return;
+    invoke<LoanItem[]>("get_loan_items", { loanId: loan.id })
+      .then((rows) => {
+        setItems(rows);
+        setItemId(rows[0]?.id ?? "");
+      })
+      .catch(() => setItems([]));
+  }, [loan]);
+
+  const loadCandidates = async (returnItemId: string) => {
+    if (!returnItemId) return setCandidates([]);
+    try {
+      const rows = await invoke<MatchCandidate[]>("get_return_matches", { returnItemId });
+      setCandidates(rows);
+      setQuantities(Object.fromEntries(rows.map((c) => [c.loan_item_id, c.matched])));
+    } catch (err) {
+      alert(`未能获取可匹配的借货：${err}`);
+      setCandidates([]);
+    }
+  };
+
+  useEffect(() => {
+    loadCandidates(itemId);
+  }, [itemId]);
+
+  const item = items.find((i) => i.id === itemId);
+  const total = Object.values(quantities).reduce((sum, q) => sum + (q || 0), 0);
+
+  const save = async (auto: boolean) => {
+    if (!item) return;
+    if (!auto && total > item.quantity) {
+      return alert(`匹配数量合计 ${total} 超出归还数量 ${item.quantity}。`);
+    }
+    setSaving(true);
+    try {
+      await invoke("set_return_matches", {
+        returnItemId: item.id,
+        matches: auto
+          ? null
+          : Object.entries(quantities)
+              .filter(([, q]) => q > 0)
+              .map(([loan_item_id, quantity]) => ({ loan_item_id, quantity })),
+      });
+      await loadCandidates(item.id);
+      onSaved();
+    } catch (err) {
+      alert(`匹配失败：${err}`);
+    } finally {
+      setSaving(false);
+    }
+  };
+
+  return (
+    <Dialog open={!!loan} onClose={onClose} maxWidth="sm" fullWidth>
+      <DialogTitle>
+        匹配归还 - {loan?.counterparty} - {loan?.date}
+      </DialogTitle>
+      <DialogContent>
+        <Box sx={{ display: "flex", flexDirection: "column", gap: 1.5, pt: 1 }}>
+          <label>
+            归还明细{" "}
+            <select value={itemId} onChange={(e) => setItemId(e.target.value)}>
+              {items.map((i) => (
+                <option key={i.id} value={i.id}>
+                  {i.product_name} × {i.quantity}
+                  {i.expiry ? `（${i.expiry}）` : ""}
+                </option>
+              ))}
+            </select>
+          </label>
+          <Typography variant="body2" sx={{ opacity: 0.8 }}>
+            已匹配 {total} / {item?.quantity ?? 0} 件；未匹配的数量按先借先还计入最早的借货。
+          </Typography>
+          {candidates.length === 0 ? (
+            <Typography variant="body2">没有可匹配的借货记录。</Typography>
+          ) : (
+            <table>
+              <thead>
+                <tr>
+                  <th align="left">借货日期</th>
+                  <th align="left">到期日</th>
+                  <th align="right">借货数量</th>
+                  <th align="right">可匹配</th>
+                  <th align="right">本次匹配</th>
+                </tr>
+              </thead>
+              <tbody>
+                {candidates.map((c) => (
+                  <tr key={c.loan_item_id}>
+                    <td>{c.date}</td>
+                    <td>{c.expiry || "-"}</td>
+                    <td align="right">{c.quantity}</td>
+                    <td align="right">{c.available}</td>
+                    <td align="right">
+                      <input
+                        type="number"
+                        min={0}
+                        max={c.available}
+                        style={{ width: 70 }}
+                        value={quantities[c.loan_item_id] ?? 0}
+                        onChange={(e) =>
+                          setQuantities({
+                            ...quantities,
+                            [c.loan_item_id]: parseInt(e.target.value, 10) || 0,
+                          })
+                        }
+                      />
+                    </td>
+                  </tr>
+                ))}
+              </tbody>
+            </table>
+          )}
+        </Box>
+      </DialogContent>
+      <DialogActions>
+        <Button onClick={onClose}>关闭</Button>
+        <Button disabled={saving || !item} onClick={() => save(true)}>
+          自动匹配
+        </Button>
+        <Button variant="contained" disabled={saving || !item} onClick={() => save(false)}>
+          保存
+        </Button>
+      </DialogActions>
+    </Dialog>
+  );
+}
@@ -0,0 +1,138 @@
+import { useEffect, useState } from "react";
+import { invoke } from "@tauri-apps/api/core";
+import {
+  Box,
+  Button,
+  Dialog,
+  DialogActions,
+  DialogContent,
+  DialogTitle,
+  Typography,
+} from "@mui/material";
+
+interface SettleLoanDialogProps {
+  open: boolean;
+  counterparty: string;
+  productName: string;
+  netQuantity: number; // > 0 they hold our stock, < 0 we hold theirs
+  onClose: () => void;
+  onSettled: () => void;
+}
+
+const today = () => new Date().toISOString().slice(0, 10);
+
+export default function SettleLoanDialog({
+  open,
+  counterparty,
+  productName,
+  netQuantity,
+  onClose,
+  onSettled,
+}: SettleLoanDialogProps) {
+  const sold = netQuantity > 0;
+  const outstanding = Math.abs(netQuantity);
+
+  const [quantity, setQuantity] = useState(outstanding);
+  const [price, setPrice] = useState<number | null>(null);
+  const [date, setDate] = useState(today());
+  const [paymentMethod, setPaymentMethod] = useState("");
+  const [paid, setPaid] = useState(false);
+  const [saving, setSaving] = useState(false);
+
+  useEffect(() => {
+    if (!open) return;
+    setQuantity(outstanding);
+    setPrice(null);
+    setDate(today());
+    setPaymentMethod("");
+    setPaid(false);
+  }, [open, outstanding]);
+
+  const submit = async () => {
+    if (quantity <= 0 || quantity > outstanding) {
+      return alert(`数量须在 1 到 ${outstanding} 之间。`);
+    }
+    setSaving(true);
+    try {
+      await invoke<string>("settle_loan", {
+        settlement: {
+          counterparty,
+          product_name: productName,
+          quantity,
+          date,
+          price,
+          expiry: null,
+          note: null,
+          payment_method: paymentMethod.trim() || null,
+          paid,
+        },
+      });
+      onSettled();
+      onClose();
+    } catch (err) {
+      alert(`结算失败：${err}`);
+    } finally {
+      setSaving(false);
+    }
+  };
+
+  return (
+    <Dialog open={open} onClose={onClose} maxWidth="xs" fullWidth>
+      <DialogTitle>
+        {sold ? "借出转销售" : "借入转购买"} - {counterparty} - {productName}
+      </DialogTitle>
+      <DialogContent>
+        <Box sx={{ display: "flex", flexDirection: "column", gap: 1.5, pt: 1 }}>
+          <Typography variant="body2" sx={{ opacity: 0.8 }}>
+            {sold
+              ? `对方留下借出的货品并付款，未结清 ${outstanding} 件。库存不再变动。`
+              : `我方留下借入的货品并付款给对方，未结清 ${outstanding} 件。库存不再变动。`}
+          </Typography>
+          <label>
+            数量{" "}
+            <input
+              type="number"
+              min={1}
+              max={outstanding}
+              value={quantity}
+              onChange={(e) => setQuantity(parseInt(e.target.value, 10) || 0)}
+            />
+          </label>
+          <label>
+            单价{" "}
+            <input
+              type="number"
+              min={0}
+              placeholder={sold ? "默认会员价" : "默认进货价"}
+              value={price ?? ""}
+              onChange={(e) =>
+                setPrice(e.target.value === "" ? null : parseInt(e.target.value, 10))
+              }
+            />
+          </label>
+          <label>
+            日期 <input type="date" value={date} onChange={(e) => setDate(e.target.value)} />
+          </label>
+          {sold && (
+            <>
+              <label>
+                付款方式{" "}
+                <input value={paymentMethod} onChange={(e) => setPaymentMethod(e.target.value)} />
+              </label>
+              <label>
+                <input type="checkbox" checked={paid} onChange={(e) => setPaid(e.target.checked)} />
+                已全额付款
+              </label>
+            </>
+          )}
+        </Box>
+      </DialogContent>
+      <DialogActions>
+        <Button onClick={onClose}>取消</Button>
+        <Button variant="contained" disabled={saving} onClick={submit}>
+          结算
+        </Button>
+      </DialogActions>
+    </Dialog>
+  );
+}
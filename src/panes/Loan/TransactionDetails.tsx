@@ -14,6 +14,7 @@ interface Transaction {
   date: string;
   direction: string;
   quantity: number;
+  expiry: string | null;
   note: string | null;
 }
 
@@ -101,6 +102,12 @@ export default function TransactionDetailsModal({
       type: "number",
       width: 100,
     },
+    {
+      field: "expiry",
+      headerName: "有效期",
+      width: 120,
+      valueGetter: (value) => value || "-",
+    },
     {
       field: "note",
       headerName: "备注",
@@ -120,6 +127,7 @@ export default function TransactionDetailsModal({
           <DataGrid
             rows={transactions}
             columns={columns}
+            getRowId={(row: Transaction) => `${row.id}|${row.expiry ?? ""}`}
             loading={loading}
             disableColumnMenu
             autoPageSize
@@ -1,6 +1,6 @@
 import { useState, useEffect, useRef } from "react";
 import { invoke } from "@tauri-apps/api/core";
-import { Product } from "../../types/product";
+import { Product, ProductPoints } from "../../types/product";
 import { toDataUrl, stripDataUrl } from "./pictureHandler"
 
 import "./productFormModal.css";
@@ -21,6 +21,14 @@ export default function ProductFormModal({
   const [name, setName] = useState("");
   const [originalName, setOriginalName] = useState<string | undefined>(undefined);
   const [price, setPrice] = useState<number | null>(null);
+  const [cost, setCost] = useState<number | null>(null);
+  const [pv, setPv] = useState<number | null>(null);
+  const [bv, setBv] = useState<number | null>(null);
+  const [consumptionDays, setConsumptionDays] = useState<number | null>(null);
+  const [pointHistory, setPointHistory] = useState<ProductPoints[]>([]);
+  const [pointDate, setPointDate] = useState("");
+  const [pointPv, setPointPv] = useState("");
+  const [pointBv, setPointBv] = useState("");
   const [picture, setPicture] = useState<string | null>(null); // Raw base64 payload for backend
   const [pictureURL, setPictureURL] = useState<string | null>(null); // Data URL for <img src=...>
   const [type, setType] = useState<string | null>(null); // Not used in this modal but can be extended
@@ -39,7 +47,12 @@ export default function ProductFormModal({
         setName(result.name);
         setOriginalName(result.name);
         setPrice(result.price);
+        setCost(result.cost ?? null);
+        setPv(result.pv ?? null);
+        setBv(result.bv ?? null);
+        setConsumptionDays(result.consumption_days ?? null);
         setType(result.type);
+        await loadPointHistory(result.name);
 
         // result.picture is RAW base64 (per your backend) or null
         setPicture(result.picture ?? null);
@@ -49,12 +62,59 @@ export default function ProductFormModal({
       setName("");
       setOriginalName(undefined);
       setPrice(null);
+      setCost(null);
+      setPv(null);
+      setBv(null);
+      setConsumptionDays(null);
+      setPointHistory([]);
       setPicture(null);
       setPictureURL(null);
       setType(null);
     }
   }, [mode, product]);
 
+  const loadPointHistory = async (productName: string) => {
+    const history = await invoke<ProductPoints[]>("get_product_point_history", {
+      name: productName,
+    });
+    setPointHistory(history);
+  };
+
+  // Back-dated entries re-value the sales made since that date
+  const handleAddPoints = async () => {
+    if (!originalName || !pointDate) return;
+    try {
+      await invoke("set_product_points", {
+        name: originalName,
+        effectiveDate: pointDate,
+        pv: pointPv === "" ? null : parseFloat(pointPv),
+        bv: pointBv === "" ? null : parseFloat(pointBv),
+      });
+      const result = await invoke<Product>("get_product", { name: originalName });
+      setPv(result.pv ?? null);
+      setBv(result.bv ?? null);
+      await loadPointHistory(originalName);
+      setPointDate("");
+      setPointPv("");
+      setPointBv("");
+    } catch (err) {
+      alert(`保存积分失败：${err}`);
+    }
+  };
+
+  const handleDeletePoints = async (effectiveDate: string) => {
+    if (!originalName) return;
+    try {
+      await invoke("delete_product_points", { name: originalName, effectiveDate });
+      const result = await invoke<Product>("get_product", { name: originalName });
+      setPv(result.pv ?? null);
+      setBv(result.bv ?? null);
+      await loadPointHistory(originalName);
+    } catch (err) {
+      alert(`删除积分记录失败：${err}`);
+    }
+  };
+
   const fileToDataUrl = (file: File): Promise<string> =>
     new Promise((resolve, reject) => {
       const reader = new FileReader();
@@ -85,7 +145,10 @@ export default function ProductFormModal({
 
   const handleSubmit = () => {
     if (!name) return;
-    onSubmit({ name, price, picture, type }, originalName ?? product?.name); // send RAW base64
+    onSubmit(
+      { name, price, picture, type, cost, pv, bv, consumption_days: consumptionDays },
+      originalName ?? product?.name
+    ); // send RAW base64
     onClose();
   };
 
@@ -120,6 +183,107 @@ export default function ProductFormModal({
           }}
         />
 
+        <label htmlFor="cost">进货单价</label>
+        <input
+          id="cost"
+          type="number"
+          value={cost ?? ""}
+          onChange={(e) => {
+            const value = e.target.value;
+            setCost(value === "" ? null : parseInt(value, 10));
+          }}
+        />
+
+        <label htmlFor="consumption-days">消耗周期（天/件）</label>
+        <input
+          id="consumption-days"
+          type="number"
+          min={1}
+          value={consumptionDays ?? ""}
+          onChange={(e) => {
+            const value = e.target.value;
+            setConsumptionDays(value === "" ? null : parseInt(value, 10));
+          }}
+        />
+
+        <label htmlFor="pv">PV（积分）</label>
+        <input
+          id="pv"
+          type="number"
+          step="0.01"
+          value={pv ?? ""}
+          onChange={(e) => {
+            const value = e.target.value;
+            setPv(value === "" ? null : parseFloat(value));
+          }}
+        />
+
+        <label htmlFor="bv">BV（业务量）</label>
+        <input
+          id="bv"
+          type="number"
+          step="0.01"
+          value={bv ?? ""}
+          onChange={(e) => {
+            const value = e.target.value;
+            setBv(value === "" ? null : parseFloat(value));
+          }}
+        />
+
+        {mode === "edit" && (
+          <div className="point-history">
+            <label>积分历史</label>
+            {pointHistory.length === 0 ? (
+              <p className="drop-hint">暂无记录</p>
+            ) : (
+              <table>
+                <thead>
+                  <tr>
+                    <th>生效日期</th>
+                    <th>PV</th>
+                    <th>BV</th>
+                    <th />
+                  </tr>
+                </thead>
+                <tbody>
+                  {pointHistory.map((h) => (
+                    <tr key={h.effective_date}>
+                      <td>{h.effective_date}</td>
+                      <td>{h.pv ?? "—"}</td>
+                      <td>{h.bv ?? "—"}</td>
+                      <td>
+                        <button className="btn" onClick={() => handleDeletePoints(h.effective_date)}>
+                          删除
+                        </button>
+                      </td>
+                    </tr>
+                  ))}
+                </tbody>
+              </table>
+            )}
+            <div style={{ display: "flex", gap: 4 }}>
+              <input type="date" value={pointDate} onChange={(e) => setPointDate(e.target.value)} />
+              <input
+                type="number"
+                step="0.01"
+                placeholder="PV"
+                value={pointPv}
+                onChange={(e) => setPointPv(e.target.value)}
+              />
+              <input
+                type="number"
+                step="0.01"
+                placeholder="BV"
+                value={pointBv}
+                onChange={(e) => setPointBv(e.target.value)}
+              />
+              <button className="btn" onClick={handleAddPoints} disabled={!pointDate}>
+                添加
+              </button>
+            </div>
+          </div>
+        )}
+
         <div
           className={`picture-upload ${dragOver ? "drag-over" : ""}`}
           onDragOver={(e) => {
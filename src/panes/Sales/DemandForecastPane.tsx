@@ -0,0 +1,148 @@
+import { useEffect, useState } from "react";
+import Plot from "react-plotly.js";
+import { invoke } from "@tauri-apps/api/core";
+import { Box, CircularProgress, Typography } from "@mui/material";
+import type { ForecastMethod, ProductForecast } from "../../types/sale";
+
+const METHOD_LABELS: Record<ProductForecast["method"], string> = {
+  exponential_smoothing: "指数平滑",
+  holt_winters: "指数平滑（含季节性）",
+  moving_average: "移动平均",
+};
+
+export default function DemandForecastPane({ refreshSignal }: { refreshSignal: number }) {
+  const [granularity, setGranularity] = useState<"week" | "month">("month");
+  const [method, setMethod] = useState<ForecastMethod>("exponential_smoothing");
+  const [horizon, setHorizon] = useState(3);
+  const [forecasts, setForecasts] = useState<ProductForecast[]>([]);
+  const [selected, setSelected] = useState("");
+  const [loading, setLoading] = useState(false);
+  const [error, setError] = useState<string | null>(null);
+
+  useEffect(() => {
+    if (refreshSignal === 0) return; // Skip initial
+    setLoading(true);
+    setError(null);
+    invoke<ProductForecast[]>("get_demand_forecast", { granularity, horizon, method })
+      .then((list) => {
+        setForecasts(list);
+        if (!list.some((f) => f.product_name === selected)) {
+          setSelected(list[0]?.product_name ?? "");
+        }
+      })
+      .catch((err) => {
+        setError(typeof err === "string" ? err : "未能生成预测");
+        setForecasts([]);
+      })
+      .finally(() => setLoading(false));
+  }, [refreshSignal, granularity, horizon, method]);
+
+  const current = forecasts.find((f) => f.product_name === selected);
+  const unit = granularity === "week" ? "周" : "月";
+
+  return (
+    <Box sx={{ p: 2, height: "100%", display: "flex", flexDirection: "column", gap: 1 }}>
+      <Typography variant="h5">需求预测</Typography>
+
+      <Box sx={{ display: "flex", gap: 1, alignItems: "center", flexWrap: "wrap" }}>
+        <select value={selected} onChange={(e) => setSelected(e.target.value)}>
+          {forecasts.map((f) => (
+            <option key={f.product_name} value={f.product_name}>
+              {f.product_name}
+            </option>
+          ))}
+        </select>
+        <select
+          value={granularity}
+          onChange={(e) => setGranularity(e.target.value as "week" | "month")}
+        >
+          <option value="month">按月</option>
+          <option value="week">按周</option>
+        </select>
+        <select value={method} onChange={(e) => setMethod(e.target.value as ForecastMethod)}>
+          <option value="exponential_smoothing">指数平滑</option>
+          <option value="moving_average">移动平均</option>
+        </select>
+        <label>
+          预测
+          <input
+            type="number"
+            min={1}
+            max={granularity === "week" ? 52 : 12}
+            style={{ width: 60, margin: "0 4px" }}
+            value={horizon}
+            onChange={(e) => setHorizon(Math.max(1, Number(e.target.value) || 1))}
+          />
+          {unit}
+        </label>
+      </Box>
+
+      {error && <Typography color="error">{error}</Typography>}
+
+      {loading ? (
+        <div style={{ padding: 32, textAlign: "center" }}>
+          <CircularProgress />
+        </div>
+      ) : !current ? (
+        <div style={{ padding: 32, opacity: 0.7 }}>暂无销售数据</div>
+      ) : (
+        <>
+          <Typography variant="body2" sx={{ opacity: 0.8 }}>
+            模型：{METHOD_LABELS[current.method]}；未来 {current.forecast.length} {unit}合计约{" "}
+            {current.forecast.reduce((s, p) => s + p.quantity, 0).toFixed(1)}（95% 区间{" "}
+            {current.forecast.reduce((s, p) => s + p.lower, 0).toFixed(1)} –{" "}
+            {current.forecast.reduce((s, p) => s + p.upper, 0).toFixed(1)}）
+          </Typography>
+          <Box sx={{ flex: 1, minHeight: 360 }}>
+            <Plot
+              data={[
+                {
+                  type: "bar",
+                  name: "历史销量",
+                  x: current.history.map((h) => h.period),
+                  y: current.history.map((h) => h.quantity),
+                },
+                {
+                  type: "scatter",
+                  mode: "lines",
+                  name: "区间下限",
+                  x: current.forecast.map((p) => p.period),
+                  y: current.forecast.map((p) => p.lower),
+                  line: { width: 0 },
+                  showlegend: false,
+                },
+                {
+                  type: "scatter",
+                  mode: "lines",
+                  name: "95% 区间",
+                  x: current.forecast.map((p) => p.period),
+                  y: current.forecast.map((p) => p.upper),
+                  fill: "tonexty",
+                  line: { width: 0 },
+                },
+                {
+                  type: "scatter",
+                  mode: "lines+markers",
+                  name: "预测销量",
+                  x: current.forecast.map((p) => p.period),
+                  y: current.forecast.map((p) => p.quantity),
+                },
+              ]}
+              layout={{
+                paper_bgcolor: "rgba(0,0,0,0)",
+                plot_bgcolor: "rgba(0,0,0,0)",
+                xaxis: { type: "category", automargin: true },
+                yaxis: { title: { text: "数量" }, rangemode: "tozero" },
+                margin: { t: 24, r: 24, b: 64, l: 64 },
+                autosize: true,
+              }}
+              config={{ displaylogo: false, responsive: true }}
+              style={{ width: "100%", height: "100%" }}
+              useResizeHandler
+            />
+          </Box>
+        </>
+      )}
+    </Box>
+  );
+}
@@ -24,6 +24,9 @@ export default function EditSalesPane({
   const [products, setProducts] = useState<Product[]>([]);
   const [txnDate, setTxnDate] = useState<string>("");
   const [note, setNote] = useState<string>("");
+  const [customer, setCustomer] = useState<string>("");
+  // sale prices of existing lines, kept so saving does not reset them
+  const [pricesById, setPricesById] = useState<Record<string, number | null>>({});
 
   const {
     rows,
@@ -60,6 +63,9 @@ export default function EditSalesPane({
         expiry: item.expiry,
       }));
       setAllRows(rowsData);
+      setPricesById(
+        Object.fromEntries(salesItems.map((item) => [item.id, item.price ?? null]))
+      );
     } catch (err) {
       console.error("Error fetching sales details:", err);
     }
@@ -70,6 +76,7 @@ export default function EditSalesPane({
     if (sale) {
       setTxnDate(sale.date);
       setNote(sale.note || "");
+      setCustomer(sale.customer || "");
       fetchProducts();
       fetchSalesItems();
     }
@@ -106,11 +113,14 @@ export default function EditSalesPane({
         product_name: r.product,
         quantity: r.qty!,
         expiry: r.expiry ?? "",
+        price: pricesById[r.id] ?? null,
       }));
       const headerPayload: SalesHeader = {
         id: sale!.id,
         date: txnDate,
         note: note.trim(),
+        customer: customer.trim() || null,
+        payment_method: sale!.payment_method ?? null,
       };
       await invoke("update_sale", { header: headerPayload, items: itemsPayload });
       onSave();
@@ -187,6 +197,17 @@ export default function EditSalesPane({
           />
         </div>
 
+        <div style={{ display: "flex", alignItems: "center", gap: 8 }}>
+          <label style={{ whiteSpace: "nowrap" }}>客户</label>
+          <input
+            type="text"
+            value={customer}
+            onChange={(e) => setCustomer(e.target.value)}
+            placeholder="可选"
+            style={{ width: 160 }}
+          />
+        </div>
+
         <div style={{ display: "flex", alignItems: "center", gap: 8, flex: 1 }}>
           <label style={{ whiteSpace: "nowrap" }}>备注</label>
           <input
@@ -0,0 +1,131 @@
+import { useEffect, useState } from "react";
+import { invoke } from "@tauri-apps/api/core";
+import { DataGrid, GridColDef } from "@mui/x-data-grid";
+import { Box, Typography } from "@mui/material";
+import type { ProfitGroup, ProfitLine, ProfitReport } from "../../types/sale";
+
+const GROUPS: { value: ProfitGroup; label: string }[] = [
+  { value: "product", label: "按商品" },
+  { value: "month", label: "按月" },
+  { value: "customer", label: "按客户" },
+  { value: "sale", label: "按销售单" },
+];
+
+function formatMargin(m: number | null) {
+  return m === null ? "—" : `${m.toFixed(1)}%`;
+}
+
+function columnsFor(group: ProfitGroup): GridColDef[] {
+  const keyColumns: GridColDef[] =
+    group === "sale"
+      ? [
+          { field: "date", headerName: "日期", flex: 1, minWidth: 110 },
+          {
+            field: "customer",
+            headerName: "客户",
+            flex: 1,
+            valueGetter: (_, row: ProfitLine) => row.customer || "-",
+          },
+        ]
+      : [
+          {
+            field: "key",
+            headerName: { product: "商品", month: "月份", customer: "客户" }[group],
+            flex: 1.5,
+            minWidth: 140,
+            valueGetter: (_, row: ProfitLine) =>
+              row.key ?? (group === "customer" ? "未填写客户" : "-"),
+          },
+        ];
+  return [
+    ...keyColumns,
+    { field: "quantity", headerName: "数量", type: "number", flex: 1 },
+    { field: "revenue", headerName: "销售额", type: "number", flex: 1 },
+    { field: "cost", headerName: "成本", type: "number", flex: 1 },
+    { field: "gross_profit", headerName: "毛利", type: "number", flex: 1 },
+    {
+      field: "margin",
+      headerName: "毛利率",
+      flex: 1,
+      valueGetter: (_, row: ProfitLine) => formatMargin(row.margin),
+    },
+    {
+      field: "uncosted_quantity",
+      headerName: "无成本数量",
+      type: "number",
+      flex: 1,
+    },
+  ];
+}
+
+export default function ProfitReportPane({ refreshSignal }: { refreshSignal: number }) {
+  const [group, setGroup] = useState<ProfitGroup>("product");
+  const [dateFrom, setDateFrom] = useState("");
+  const [dateTo, setDateTo] = useState("");
+  const [report, setReport] = useState<ProfitReport | null>(null);
+  const [loading, setLoading] = useState(false);
+  const [error, setError] = useState<string | null>(null);
+
+  useEffect(() => {
+    if (refreshSignal === 0) return; // Skip initial
+    setLoading(true);
+    setError(null);
+    invoke<ProfitReport>("get_profit_report", {
+      groupBy: group,
+      dateFrom: dateFrom || null,
+      dateTo: dateTo || null,
+    })
+      .then(setReport)
+      .catch((err) => {
+        setError(typeof err === "string" ? err : "未能获取利润报表");
+        setReport(null);
+      })
+      .finally(() => setLoading(false));
+  }, [refreshSignal, group, dateFrom, dateTo]);
+
+  const total = report?.total;
+
+  return (
+    <Box sx={{ p: 2, height: "100%", display: "flex", flexDirection: "column", gap: 1 }}>
+      <Typography variant="h5">利润报表</Typography>
+
+      <Box sx={{ display: "flex", gap: 1, alignItems: "center", flexWrap: "wrap" }}>
+        {GROUPS.map((g) => (
+          <button
+            key={g.value}
+            className={g.value === group ? "btn primary" : "btn"}
+            onClick={() => setGroup(g.value)}
+          >
+            {g.label}
+          </button>
+        ))}
+        <input type="date" value={dateFrom} onChange={(e) => setDateFrom(e.target.value)} />
+        <span>至</span>
+        <input type="date" value={dateTo} onChange={(e) => setDateTo(e.target.value)} />
+      </Box>
+
+      {error && <Typography color="error">{error}</Typography>}
+
+      {total && (
+        <Typography variant="body2" sx={{ opacity: 0.8 }}>
+          合计：销售额 {total.revenue}，成本 {total.cost}，毛利 {total.gross_profit}，毛利率{" "}
+          {formatMargin(total.margin)}
+          {total.uncosted_quantity !== 0 &&
+            `（${total.uncosted_quantity} 件商品缺少进货成本，未计入成本）`}
+        </Typography>
+      )}
+
+      <Box sx={{ flex: 1, minHeight: 0 }}>
+        <DataGrid
+          rows={report?.lines ?? []}
+          columns={columnsFor(group)}
+          getRowId={(row: ProfitLine) => row.key ?? ""}
+          loading={loading}
+          disableColumnMenu
+          autoPageSize
+          density="compact"
+        />
+      </Box>
+    </Box>
+  );
+}
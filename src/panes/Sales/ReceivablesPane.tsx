@@ -0,0 +1,76 @@
+import { useEffect, useState } from "react";
+import { invoke } from "@tauri-apps/api/core";
+import { DataGrid, GridColDef } from "@mui/x-data-grid";
+import { Box, Chip, Typography } from "@mui/material";
+import type { Receivable } from "../../types/sale";
+
+const columns: GridColDef[] = [
+  {
+    field: "customer",
+    headerName: "客户",
+    flex: 1,
+    minWidth: 120,
+    valueGetter: (v: string | null) => v || "散客",
+  },
+  { field: "sales_count", headerName: "欠款单数", type: "number", width: 90 },
+  { field: "total_outstanding", headerName: "未收合计", type: "number", width: 100 },
+  { field: "days_0_30", headerName: "30 天内", type: "number", width: 90 },
+  { field: "days_31_60", headerName: "31-60 天", type: "number", width: 90 },
+  { field: "days_61_90", headerName: "61-90 天", type: "number", width: 90 },
+  { field: "days_over_90", headerName: "90 天以上", type: "number", width: 100 },
+  { field: "oldest_date", headerName: "最早欠款", width: 110 },
+];
+
+export default function ReceivablesPane({ refreshSignal }: { refreshSignal: number }) {
+  const [asOf, setAsOf] = useState(() => new Date().toISOString().slice(0, 10));
+  const [rows, setRows] = useState<Receivable[]>([]);
+  const [loading, setLoading] = useState(false);
+  const [error, setError] = useState<string | null>(null);
+
+  useEffect(() => {
+    if (refreshSignal === 0) return; // Skip initial
+    setLoading(true);
+    setError(null);
+    invoke<Receivable[]>("get_receivables", { asOf: asOf || null })
+      .then(setRows)
+      .catch((err) => {
+        setError(typeof err === "string" ? err : "未能获取应收账款");
+        setRows([]);
+      })
+      .finally(() => setLoading(false));
+  }, [refreshSignal, asOf]);
+
+  const total = rows.reduce((sum, r) => sum + r.total_outstanding, 0);
+  const overdue = rows.reduce((sum, r) => sum + r.days_61_90 + r.days_over_90, 0);
+
+  return (
+    <Box sx={{ p: 2, height: "100%", display: "flex", flexDirection: "column", gap: 1 }}>
+      <Typography variant="h5">应收账款</Typography>
+
+      <Box sx={{ display: "flex", gap: 2, alignItems: "center", flexWrap: "wrap" }}>
+        <label>
+          截至 <input type="date" value={asOf} onChange={(e) => setAsOf(e.target.value)} />
+        </label>
+        <Chip size="small" label={`未收合计 ¥${total}`} />
+        <Chip size="small" color={overdue > 0 ? "warning" : "default"} label={`超过 60 天 ¥${overdue}`} />
+        <Typography variant="body2" sx={{ opacity: 0.7 }}>
+          在销售记录中点击“收款”登记后续付款。
+        </Typography>
+      </Box>
+
+      {error && <Typography color="error">{error}</Typography>}
+
+      <Box sx={{ flex: 1, minHeight: 0 }}>
+        <DataGrid
+          rows={rows}
+          columns={columns}
+          getRowId={(row: Receivable) => row.customer ?? ""}
+          loading={loading}
+          disableColumnMenu
+          autoPageSize
+          density="compact"
+        />
+      </Box>
+    </Box>
+  );
+}
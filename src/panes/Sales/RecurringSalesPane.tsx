@@ -0,0 +1,388 @@
+import { useEffect, useState } from "react";
+import { invoke } from "@tauri-apps/api/core";
+import { v4 as uuidv4 } from "uuid";
+import { DataGrid, GridColDef } from "@mui/x-data-grid";
+import { Box, Button, Typography } from "@mui/material";
+import type { Product } from "../../types/product";
+import type {
+  IntervalUnit,
+  RecurringSale,
+  RecurringSaleHeader,
+  RecurringSaleItem,
+} from "../../types/sale";
+
+const UNIT_LABELS: Record<IntervalUnit, string> = { day: "天", week: "周", month: "个月" };
+
+const today = () => new Date().toISOString().slice(0, 10);
+
+function emptyHeader(): RecurringSaleHeader {
+  return {
+    id: uuidv4(),
+    customer: "",
+    interval_unit: "month",
+    interval_count: 1,
+    next_date: today(),
+    payment_method: null,
+    payment_status: "unpaid",
+    note: null,
+    active: true,
+  };
+}
+
+function emptyItem(): RecurringSaleItem {
+  return { id: uuidv4(), product_name: "", quantity: 1, price: null };
+}
+
+function dueLabel(r: RecurringSale) {
+  if (!r.header.active) return "已暂停";
+  if (r.days_until_due < 0) return `已逾期 ${-r.days_until_due} 天`;
+  if (r.days_until_due === 0) return "今天到期";
+  return `${r.days_until_due} 天后到期`;
+}
+
+export default function RecurringSalesPane({
+  refreshSignal,
+  onDidSubmit,
+}: {
+  refreshSignal: number;
+  onDidSubmit?: () => void;
+}) {
+  const [rows, setRows] = useState<RecurringSale[]>([]);
+  const [products, setProducts] = useState<Product[]>([]);
+  const [dueWithinDays, setDueWithinDays] = useState<number | null>(7);
+  const [loading, setLoading] = useState(false);
+  const [error, setError] = useState<string | null>(null);
+
+  // editor: null = closed
+  const [editing, setEditing] = useState<{ isNew: boolean; header: RecurringSaleHeader } | null>(
+    null
+  );
+  const [items, setItems] = useState<RecurringSaleItem[]>([]);
+
+  const fetchRows = async () => {
+    setLoading(true);
+    setError(null);
+    try {
+      setRows(await invoke<RecurringSale[]>("get_recurring_sales", { dueWithinDays }));
+    } catch (err) {
+      setError(typeof err === "string" ? err : "未能获取定期订单");
+      setRows([]);
+    } finally {
+      setLoading(false);
+    }
+  };
+
+  useEffect(() => {
+    if (refreshSignal === 0) return; // Skip initial
+    fetchRows();
+  }, [refreshSignal, dueWithinDays]);
+
+  useEffect(() => {
+    if (refreshSignal === 0) return;
+    invoke<Product[]>("get_all_products")
+      .then((list) => setProducts([...list].sort((a, b) => a.name.localeCompare(b.name))))
+      .catch((err) => console.error("Error fetching products:", err));
+  }, [refreshSignal]);
+
+  const openEditor = (r?: RecurringSale) => {
+    if (r) {
+      setEditing({ isNew: false, header: { ...r.header } });
+      setItems(r.items.map((it) => ({ ...it })));
+    } else {
+      setEditing({ isNew: true, header: emptyHeader() });
+      setItems([emptyItem()]);
+    }
+  };
+
+  const setHeader = (patch: Partial<RecurringSaleHeader>) =>
+    setEditing((e) => (e ? { ...e, header: { ...e.header, ...patch } } : e));
+
+  const setItem = (i: number, patch: Partial<RecurringSaleItem>) =>
+    setItems((list) => list.map((it, j) => (j === i ? { ...it, ...patch } : it)));
+
+  const save = async () => {
+    if (!editing) return;
+    const payload = {
+      header: { ...editing.header, customer: editing.header.customer.trim() },
+      items: items
+        .filter((it) => it.product_name)
+        .map(({ id, product_name, quantity, price }) => ({ id, product_name, quantity, price })),
+    };
+    try {
+      await invoke(editing.isNew ? "create_recurring_sale" : "update_recurring_sale", payload);
+      setEditing(null);
+      await fetchRows();
+    } catch (err) {
+      alert(`保存失败：${err}`);
+    }
+  };
+
+  const generate = async (r: RecurringSale) => {
+    if (!confirm(`为 ${r.header.customer} 生成今天的销售并扣减库存？`)) return;
+    try {
+      await invoke<string>("generate_recurring_sale", { recurringId: r.header.id });
+      await fetchRows();
+      onDidSubmit?.();
+    } catch (err) {
+      alert(`生成失败：${err}`);
+    }
+  };
+
+  const skip = async (r: RecurringSale) => {
+    try {
+      await invoke("skip_recurring_sale", { recurringId: r.header.id });
+      await fetchRows();
+    } catch (err) {
+      alert(`操作失败：${err}`);
+    }
+  };
+
+  const remove = async (r: RecurringSale) => {
+    if (!confirm(`删除 ${r.header.customer} 的定期订单？已生成的销售不受影响。`)) return;
+    try {
+      await invoke("delete_recurring_sale", { recurringId: r.header.id });
+      await fetchRows();
+    } catch (err) {
+      alert(`删除失败：${err}`);
+    }
+  };
+
+  const columns: GridColDef[] = [
+    {
+      field: "customer",
+      headerName: "客户",
+      flex: 1,
+      minWidth: 100,
+      valueGetter: (_, row: RecurringSale) => row.header.customer,
+    },
+    {
+      field: "items",
+      headerName: "商品",
+      flex: 2,
+      minWidth: 220,
+      valueGetter: (_, row: RecurringSale) =>
+        row.items
+          .map(
+            (it) =>
+              `${it.product_name}×${it.quantity}` +
+              ((it.available ?? 0) < it.quantity ? `（库存${it.available ?? 0}）` : "")
+          )
+          .join("、"),
+    },
+    {
+      field: "interval",
+      headerName: "周期",
+      flex: 0.7,
+      valueGetter: (_, row: RecurringSale) =>
+        `每 ${row.header.interval_count} ${UNIT_LABELS[row.header.interval_unit]}`,
+    },
+    {
+      field: "next_date",
+      headerName: "下次日期",
+      flex: 1,
+      minWidth: 110,
+      valueGetter: (_, row: RecurringSale) => row.header.next_date,
+    },
+    {
+      field: "status",
+      headerName: "状态",
+      flex: 1,
+      valueGetter: (_, row: RecurringSale) => dueLabel(row),
+    },
+    {
+      field: "actions",
+      headerName: "操作",
+      width: 260,
+      renderCell: (params) => (
+        <Box sx={{ display: "flex", gap: 1, alignItems: "center", height: "100%" }}>
+          <Button
+            size="small"
+            variant="contained"
+            disabled={!params.row.header.active}
+            onClick={() => generate(params.row)}
+            sx={{ minWidth: "auto", px: 1, fontSize: "0.75rem" }}
+          >
+            生成销售
+          </Button>
+          <Button
+            size="small"
+            variant="outlined"
+            onClick={() => skip(params.row)}
+            sx={{ minWidth: "auto", px: 1, fontSize: "0.75rem" }}
+          >
+            跳过
+          </Button>
+          <Button
+            size="small"
+            variant="outlined"
+            onClick={() => openEditor(params.row)}
+            sx={{ minWidth: "auto", px: 1, fontSize: "0.75rem" }}
+          >
+            编辑
+          </Button>
+          <Button
+            size="small"
+            variant="outlined"
+            color="error"
+            onClick={() => remove(params.row)}
+            sx={{ minWidth: "auto", px: 1, fontSize: "0.75rem" }}
+          >
+            删除
+          </Button>
+        </Box>
+      ),
+    },
+  ];
+
+  return (
+    <Box sx={{ p: 2, height: "100%", display: "flex", flexDirection: "column", gap: 1 }}>
+      <Typography variant="h5">定期订单</Typography>
+
+      <Box sx={{ display: "flex", gap: 2, alignItems: "center", flexWrap: "wrap" }}>
+        <label>
+          显示
+          <select
+            style={{ margin: "0 4px" }}
+            value={dueWithinDays ?? ""}
+            onChange={(e) =>
+              setDueWithinDays(e.target.value === "" ? null : Number(e.target.value))
+            }
+          >
+            <option value={0}>今天及逾期</option>
+            <option value={7}>7 天内到期</option>
+            <option value={30}>30 天内到期</option>
+            <option value="">全部</option>
+          </select>
+        </label>
+        <button className="btn primary" onClick={() => openEditor()}>
+          新建定期订单
+        </button>
+      </Box>
+
+      {error && <Typography color="error">{error}</Typography>}
+
+      {editing && (
+        <Box sx={{ display: "flex", flexDirection: "column", gap: 1, p: 1, border: "1px solid #ddd" }}>
+          <Box sx={{ display: "flex", gap: 1, alignItems: "center", flexWrap: "wrap" }}>
+            <label>客户</label>
+            <input
+              value={editing.header.customer}
+              onChange={(e) => setHeader({ customer: e.target.value })}
+            />
+            <label>每</label>
+            <input
+              type="number"
+              min={1}
+              style={{ width: 60 }}
+              value={editing.header.interval_count}
+              onChange={(e) => setHeader({ interval_count: parseInt(e.target.value, 10) || 1 })}
+            />
+            <select
+              value={editing.header.interval_unit}
+              onChange={(e) => setHeader({ interval_unit: e.target.value as IntervalUnit })}
+            >
+              <option value="day">天</option>
+              <option value="week">周</option>
+              <option value="month">个月</option>
+            </select>
+            <label>下次日期</label>
+            <input
+              type="date"
+              value={editing.header.next_date}
+              onChange={(e) => setHeader({ next_date: e.target.value })}
+            />
+            <label>付款方式</label>
+            <input
+              value={editing.header.payment_method ?? ""}
+              onChange={(e) => setHeader({ payment_method: e.target.value || null })}
+            />
+            <label>生成后</label>
+            <select
+              value={editing.header.payment_status ?? "unpaid"}
+              onChange={(e) =>
+                setHeader({ payment_status: e.target.value as "paid" | "unpaid" })
+              }
+            >
+              <option value="unpaid">待收款</option>
+              <option value="paid">已付款</option>
+            </select>
+            <label>备注</label>
+            <input
+              value={editing.header.note ?? ""}
+              onChange={(e) => setHeader({ note: e.target.value || null })}
+            />
+            <label>
+              <input
+                type="checkbox"
+                checked={editing.header.active}
+                onChange={(e) => setHeader({ active: e.target.checked })}
+              />
+              启用
+            </label>
+          </Box>
+
+          {items.map((it, i) => (
+            <Box key={it.id} sx={{ display: "flex", gap: 1, alignItems: "center" }}>
+              <select
+                value={it.product_name}
+                onChange={(e) => setItem(i, { product_name: e.target.value })}
+              >
+                <option value="">选择产品</option>
+                {products.map((p) => (
+                  <option key={p.name} value={p.name}>
+                    {p.name}
+                  </option>
+                ))}
+              </select>
+              <input
+                type="number"
+                min={1}
+                style={{ width: 80 }}
+                placeholder="数量"
+                value={it.quantity}
+                onChange={(e) => setItem(i, { quantity: parseInt(e.target.value, 10) || 0 })}
+              />
+              <input
+                type="number"
+                min={0}
+                style={{ width: 100 }}
+                placeholder="单价（默认会员价）"
+                value={it.price ?? ""}
+                onChange={(e) =>
+                  setItem(i, { price: e.target.value === "" ? null : parseInt(e.target.value, 10) })
+                }
+              />
+              <button className="btn" onClick={() => setItems((l) => l.filter((_, j) => j !== i))}>
+                移除
+              </button>
+            </Box>
+          ))}
+
+          <Box sx={{ display: "flex", gap: 1 }}>
+            <button className="btn" onClick={() => setItems((l) => [...l, emptyItem()])}>
+              添加商品
+            </button>
+            <button className="btn primary" onClick={save}>
+              保存
+            </button>
+            <button className="btn" onClick={() => setEditing(null)}>
+              取消
+            </button>
+          </Box>
+        </Box>
+      )}
+
+      <Box sx={{ flex: 1, minHeight: 0 }}>
+        <DataGrid
+          rows={rows}
+          columns={columns}
+          getRowId={(row: RecurringSale) => row.header.id}
+          loading={loading}
+          disableColumnMenu
+          autoPageSize
+          density="compact"
+        />
+      </Box>
+    </Box>
+  );
+}
@@ -0,0 +1,116 @@
+import { useEffect, useState } from "react";
+import { invoke } from "@tauri-apps/api/core";
+import { DataGrid, GridColDef } from "@mui/x-data-grid";
+import { Box, Typography } from "@mui/material";
+
+type RefillReminder = {
+  customer: string;
+  product_name: string;
+  type?: string | null;
+  last_purchase: string;
+  quantity: number;
+  consumption_days: number;
+  due_date: string;
+  days_left: number; // negative = overdue
+  purchase_count: number;
+};
+
+const columns: GridColDef[] = [
+  { field: "customer", headerName: "客户", flex: 1, minWidth: 100 },
+  { field: "product_name", headerName: "产品", flex: 1.5, minWidth: 140 },
+  { field: "last_purchase", headerName: "上次购买", flex: 1, minWidth: 110 },
+  { field: "quantity", headerName: "购买数量", type: "number", flex: 0.8 },
+  { field: "consumption_days", headerName: "消耗周期（天）", type: "number", flex: 1 },
+  { field: "due_date", headerName: "预计用完", flex: 1, minWidth: 110 },
+  {
+    field: "days_left",
+    headerName: "状态",
+    flex: 1,
+    valueGetter: (_, row: RefillReminder) =>
+      row.days_left < 0
+        ? `已逾期 ${-row.days_left} 天`
+        : row.days_left === 0
+          ? "今天到期"
+          : `${row.days_left} 天后到期`,
+  },
+  { field: "purchase_count", headerName: "购买次数", type: "number", flex: 0.8 },
+];
+
+export default function RefillReminderPane({ refreshSignal }: { refreshSignal: number }) {
+  const [withinDays, setWithinDays] = useState(7);
+  const [maxOverdueDays, setMaxOverdueDays] = useState<number | null>(90);
+  const [rows, setRows] = useState<RefillReminder[]>([]);
+  const [loading, setLoading] = useState(false);
+  const [error, setError] = useState<string | null>(null);
+
+  useEffect(() => {
+    if (refreshSignal === 0) return; // Skip initial
+    setLoading(true);
+    setError(null);
+    invoke<RefillReminder[]>("get_refill_reminders", { withinDays, maxOverdueDays })
+      .then(setRows)
+      .catch((err) => {
+        setError(typeof err === "string" ? err : "未能获取续购提醒");
+        setRows([]);
+      })
+      .finally(() => setLoading(false));
+  }, [refreshSignal, withinDays, maxOverdueDays]);
+
+  const overdue = rows.filter((r) => r.days_left < 0).length;
+
+  return (
+    <Box sx={{ p: 2, height: "100%", display: "flex", flexDirection: "column", gap: 1 }}>
+      <Typography variant="h5">续购提醒</Typography>
+
+      <Box sx={{ display: "flex", gap: 2, alignItems: "center", flexWrap: "wrap" }}>
+        <label>
+          未来
+          <select
+            style={{ margin: "0 4px" }}
+            value={withinDays}
+            onChange={(e) => setWithinDays(Number(e.target.value))}
+          >
+            <option value={0}>0</option>
+            <option value={3}>3</option>
+            <option value={7}>7</option>
+            <option value={14}>14</option>
+            <option value={30}>30</option>
+          </select>
+          天内到期
+        </label>
+        <label>
+          逾期不超过
+          <select
+            style={{ margin: "0 4px" }}
+            value={maxOverdueDays ?? ""}
+            onChange={(e) =>
+              setMaxOverdueDays(e.target.value === "" ? null : Number(e.target.value))
+            }
+          >
+            <option value={30}>30 天</option>
+            <option value={90}>90 天</option>
+            <option value={180}>180 天</option>
+            <option value="">不限</option>
+          </select>
+        </label>
+        <Typography variant="body2" sx={{ opacity: 0.8 }}>
+          共 {rows.length} 条，其中已逾期 {overdue} 条
+        </Typography>
+      </Box>
+
+      {error && <Typography color="error">{error}</Typography>}
+
+      <Box sx={{ flex: 1, minHeight: 0 }}>
+        <DataGrid
+          rows={rows}
+          columns={columns}
+          getRowId={(row: RefillReminder) => `${row.customer}|${row.product_name}`}
+          loading={loading}
+          disableColumnMenu
+          autoPageSize
+          density="compact"
+        />
+      </Box>
+    </Box>
+  );
+}
@@ -0,0 +1,167 @@
+import { useEffect, useState } from "react";
+import { invoke } from "@tauri-apps/api/core";
+import { v4 as uuidv4 } from "uuid";
+import {
+  Box,
+  Button,
+  Dialog,
+  DialogActions,
+  DialogContent,
+  DialogTitle,
+  Typography,
+} from "@mui/material";
+import type { SalesHeader, SalesPayment } from "../../types/sale";
+
+interface SalePaymentsDialogProps {
+  sale: SalesHeader | null;
+  total: number; // net value of the sale
+  onClose: () => void;
+  onSaved: () => void;
+}
+
+export default function SalePaymentsDialog({
+  sale,
+  total,
+  onClose,
+  onSaved,
+}: SalePaymentsDialogProps) {
+  const [payments, setPayments] = useState<SalesPayment[]>([]);
+  const [date, setDate] = useState(() => new Date().toISOString().slice(0, 10));
+  const [amount, setAmount] = useState("");
+  const [method, setMethod] = useState("");
+  const [note, setNote] = useState("");
+  const [saving, setSaving] = useState(false);
+
+  const load = async (saleId: string) => {
+    try {
+      setPayments(await invoke<SalesPayment[]>("get_sale_payments", { saleId }));
+    } catch (err) {
+      alert(`未能获取收款记录：${err}`);
+      setPayments([]);
+    }
+  };
+
+  useEffect(() => {
+    if (!sale) return;
+    setAmount("");
+    setNote("");
+    setMethod(sale.payment_method ?? "");
+    setDate(new Date().toISOString().slice(0, 10));
+    load(sale.id);
+  }, [sale]);
+
+  const paid = payments.reduce((sum, p) => sum + p.amount, 0);
+
+  const save = async () => {
+    if (!sale) return;
+    const value = parseInt(amount, 10);
+    if (!value || value <= 0) return alert("请填写收款金额。");
+    setSaving(true);
+    try {
+      await invoke("record_payment", {
+        payment: {
+          id: uuidv4(),
+          sale_id: sale.id,
+          date,
+          amount: value,
+          method: method.trim() || null,
+          note: note.trim() || null,
+        },
+      });
+      await load(sale.id);
+      setAmount("");
+      setNote("");
+      onSaved();
+    } catch (err) {
+      alert(`收款失败：${err}`);
+    } finally {
+      setSaving(false);
+    }
+  };
+
+  const handleDelete = async (paymentId: string) => {
+    if (!sale || !confirm("删除这条收款记录？")) return;
+    try {
+      await invoke("delete_payment", { paymentId });
+      await load(sale.id);
+      onSaved();
+    } catch (err) {
+      alert(`删除失败：${err}`);
+    }
+  };
+
+  return (
+    <Dialog open={!!sale} onClose={onClose} maxWidth="sm" fullWidth>
+      <DialogTitle>
+        收款 - {sale?.customer || "散客"} - {sale?.date}
+      </DialogTitle>
+      <DialogContent>
+        <Box sx={{ display: "flex", flexDirection: "column", gap: 1.5, pt: 1 }}>
+          <Typography variant="body2">
+            应收 ¥{total}，已收 ¥{paid}，未收 ¥{Math.max(total - paid, 0)}
+          </Typography>
+          {payments.length === 0 ? (
+            <Typography variant="body2">暂无收款记录。</Typography>
+          ) : (
+            <table>
+              <thead>
+                <tr>
+                  <th align="left">日期</th>
+                  <th align="right">金额</th>
+                  <th align="left">方式</th>
+                  <th align="left">备注</th>
+                  <th />
+                </tr>
+              </thead>
+              <tbody>
+                {payments.map((p) => (
+                  <tr key={p.id}>
+                    <td>{p.date}</td>
+                    <td align="right">{p.amount}</td>
+                    <td>{p.method || "-"}</td>
+                    <td>{p.note || ""}</td>
+                    <td align="right">
+                      <Button size="small" color="error" onClick={() => handleDelete(p.id)}>
+                        删除
+                      </Button>
+                    </td>
+                  </tr>
+                ))}
+              </tbody>
+            </table>
+          )}
+          <Box sx={{ display: "flex", gap: 1, alignItems: "center", flexWrap: "wrap" }}>
+            <input type="date" value={date} onChange={(e) => setDate(e.target.value)} />
+            <input
+              type="number"
+              min={1}
+              placeholder="金额"
+              style={{ width: 90 }}
+              value={amount}
+              onChange={(e) => setAmount(e.target.value)}
+            />
+            <input
+              type="text"
+              placeholder="付款方式"
+              style={{ width: 100 }}
+              value={method}
+              onChange={(e) => setMethod(e.target.value)}
+            />
+            <input
+              type="text"
+              placeholder="备注"
+              value={note}
+              onChange={(e) => setNote(e.target.value)}
+            />
+          </Box>
+        </Box>
+      </DialogContent>
+      <DialogActions>
+        <Button onClick={onClose}>关闭</Button>
+        <Button variant="contained" disabled={saving || !sale} onClick={save}>
+          登记收款
+        </Button>
+      </DialogActions>
+    </Dialog>
+  );
+}
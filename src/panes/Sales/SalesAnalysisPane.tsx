@@ -0,0 +1,281 @@
+import { useEffect, useState } from "react";
+import { invoke } from "@tauri-apps/api/core";
+import { DataGrid, GridColDef } from "@mui/x-data-grid";
+import { Box, Typography } from "@mui/material";
+import type {
+  ComparisonLine,
+  ComparisonPeriod,
+  RankingEntry,
+  RankingMetric,
+  SalesComparison,
+  SalesRankings,
+} from "../../types/sale";
+
+const PERIODS: { value: ComparisonPeriod; label: string }[] = [
+  { value: "week", label: "本周" },
+  { value: "month", label: "本月" },
+  { value: "quarter", label: "本季度" },
+  { value: "ytd", label: "年初至今" },
+  { value: "custom", label: "自定义" },
+];
+
+function formatPct(p: number | null) {
+  if (p === null) return "—";
+  return `${p > 0 ? "+" : ""}${p.toFixed(1)}%`;
+}
+
+function formatChange(n: number) {
+  return `${n > 0 ? "+" : ""}${n}`;
+}
+
+function comparisonColumns(keyHeader: string): GridColDef[] {
+  return [
+    {
+      field: "key",
+      headerName: keyHeader,
+      flex: 1.5,
+      minWidth: 140,
+      valueGetter: (_, row: ComparisonLine) => row.key ?? "未分类",
+    },
+    {
+      field: "current",
+      headerName: "本期销售额",
+      type: "number",
+      flex: 1,
+      valueGetter: (_, row: ComparisonLine) => row.current.value,
+    },
+    {
+      field: "current_quantity",
+      headerName: "本期数量",
+      type: "number",
+      flex: 1,
+      valueGetter: (_, row: ComparisonLine) => row.current.quantity,
+    },
+    {
+      field: "previous",
+      headerName: "上期销售额",
+      type: "number",
+      flex: 1,
+      valueGetter: (_, row: ComparisonLine) => row.previous.value,
+    },
+    {
+      field: "vs_previous",
+      headerName: "环比",
+      flex: 1,
+      minWidth: 120,
+      valueGetter: (_, row: ComparisonLine) =>
+        `${formatChange(row.vs_previous.value)}（${formatPct(row.vs_previous.value_pct)}）`,
+    },
+    {
+      field: "last_year",
+      headerName: "去年同期销售额",
+      type: "number",
+      flex: 1,
+      valueGetter: (_, row: ComparisonLine) => row.last_year.value,
+    },
+    {
+      field: "vs_last_year",
+      headerName: "同比",
+      flex: 1,
+      minWidth: 120,
+      valueGetter: (_, row: ComparisonLine) =>
+        `${formatChange(row.vs_last_year.value)}（${formatPct(row.vs_last_year.value_pct)}）`,
+    },
+  ];
+}
+
+const rankingColumns: GridColDef[] = [
+  { field: "rank", headerName: "排名", width: 70 },
+  {
+    field: "key",
+    headerName: "名称",
+    flex: 1.5,
+    minWidth: 140,
+    valueGetter: (_, row: RankingEntry) => row.key ?? "未分类",
+  },
+  { field: "value", headerName: "销售额", type: "number", flex: 1 },
+  {
+    field: "value_share",
+    headerName: "金额占比",
+    flex: 1,
+    valueGetter: (_, row: RankingEntry) => `${row.value_share.toFixed(1)}%`,
+  },
+  { field: "quantity", headerName: "数量", type: "number", flex: 1 },
+  {
+    field: "quantity_share",
+    headerName: "数量占比",
+    flex: 1,
+    valueGetter: (_, row: RankingEntry) => `${row.quantity_share.toFixed(1)}%`,
+  },
+  { field: "sales_count", headerName: "销售单数", type: "number", flex: 1 },
+  {
+    field: "trend",
+    headerName: "较上期",
+    flex: 1.5,
+    minWidth: 160,
+    valueGetter: (_, row: RankingEntry) => {
+      const rank =
+        row.previous_rank === null
+          ? "新上榜"
+          : row.previous_rank === row.rank
+            ? "排名不变"
+            : `排名${row.previous_rank > row.rank ? "↑" : "↓"}${Math.abs(row.previous_rank - row.rank)}`;
+      return `${formatPct(row.vs_previous.value_pct)}，${rank}`;
+    },
+  },
+];
+
+// Period used for rankings: the current comparison period, or the last 30 days
+function rankingRange(comparison: SalesComparison | null) {
+  if (comparison) return comparison.current;
+  const d = new Date();
+  d.setDate(d.getDate() - 29);
+  return { from: d.toISOString().slice(0, 10), to: "" };
+}
+
+export default function SalesAnalysisPane({ refreshSignal }: { refreshSignal: number }) {
+  const [period, setPeriod] = useState<ComparisonPeriod>("month");
+  const [dateFrom, setDateFrom] = useState("");
+  const [dateTo, setDateTo] = useState("");
+  const [comparison, setComparison] = useState<SalesComparison | null>(null);
+  const [loading, setLoading] = useState(false);
+  const [error, setError] = useState<string | null>(null);
+  const [rankings, setRankings] = useState<SalesRankings | null>(null);
+  const [rankBy, setRankBy] = useState<RankingMetric>("value");
+  const [rankGroup, setRankGroup] = useState<"product" | "type">("product");
+
+  const fetchComparison = async () => {
+    if (period === "custom" && (!dateFrom || !dateTo)) return;
+    setLoading(true);
+    setError(null);
+    try {
+      const result = await invoke<SalesComparison>("get_sales_comparison", {
+        period,
+        dateFrom: period === "custom" ? dateFrom : null,
+        dateTo: period === "custom" ? dateTo : null,
+      });
+      setComparison(result);
+    } catch (err) {
+      setError(typeof err === "string" ? err : "未能获取销售对比");
+      console.error("Error fetching sales comparison:", err);
+    } finally {
+      setLoading(false);
+    }
+  };
+
+  useEffect(() => {
+    if (refreshSignal === 0) return; // Skip initial
+    fetchComparison();
+  }, [refreshSignal, period, dateFrom, dateTo]);
+
+  useEffect(() => {
+    if (refreshSignal === 0) return; // Skip initial
+    const { from, to } = rankingRange(comparison);
+    invoke<SalesRankings>("get_sales_rankings", {
+      dateFrom: from,
+      dateTo: to || null,
+      groupBy: rankGroup,
+      sortBy: rankBy,
+    })
+      .then(setRankings)
+      .catch((err) => {
+        console.error("Error fetching sales rankings:", err);
+        setRankings(null);
+      });
+  }, [refreshSignal, comparison, rankBy, rankGroup]);
+
+  const total = comparison?.total;
+
+  return (
+    <Box sx={{ p: 2, height: "100%", display: "flex", flexDirection: "column", gap: 1 }}>
+      <Typography variant="h5">销售分析</Typography>
+
+      <Box sx={{ display: "flex", gap: 1, alignItems: "center", flexWrap: "wrap" }}>
+        {PERIODS.map((p) => (
+          <button
+            key={p.value}
+            className={p.value === period ? "btn primary" : "btn"}
+            onClick={() => setPeriod(p.value)}
+          >
+            {p.label}
+          </button>
+        ))}
+        {period === "custom" && (
+          <>
+            <input type="date" value={dateFrom} onChange={(e) => setDateFrom(e.target.value)} />
+            <span>至</span>
+            <input type="date" value={dateTo} onChange={(e) => setDateTo(e.target.value)} />
+          </>
+        )}
+      </Box>
+
+      {error && <Typography color="error">{error}</Typography>}
+
+      {comparison && total && (
+        <Typography variant="body2" sx={{ opacity: 0.8 }}>
+          本期 {comparison.current.from} 至 {comparison.current.to}：销售额 {total.current.value}，
+          数量 {total.current.quantity}；环比（{comparison.previous.from} 至{" "}
+          {comparison.previous.to}）{formatChange(total.vs_previous.value)}（
+          {formatPct(total.vs_previous.value_pct)}）；同比（{comparison.last_year.from} 至{" "}
+          {comparison.last_year.to}）{formatChange(total.vs_last_year.value)}（
+          {formatPct(total.vs_last_year.value_pct)}）
+        </Typography>
+      )}
+
+      <Box sx={{ display: "flex", gap: 1, alignItems: "center" }}>
+        <Typography variant="subtitle1">
+          销售排行{rankings && `（${rankings.current.from} 至 ${rankings.current.to}，共 ${rankings.total_sales_count} 单）`}
+        </Typography>
+        <select value={rankBy} onChange={(e) => setRankBy(e.target.value as RankingMetric)}>
+          <option value="value">按销售额</option>
+          <option value="quantity">按数量</option>
+          <option value="sales_count">按销售单数</option>
+        </select>
+        <select
+          value={rankGroup}
+          onChange={(e) => setRankGroup(e.target.value as "product" | "type")}
+        >
+          <option value="product">商品</option>
+          <option value="type">类型</option>
+        </select>
+      </Box>
+      <Box sx={{ height: 300 }}>
+        <DataGrid
+          rows={rankings?.entries ?? []}
+          columns={rankingColumns}
+          getRowId={(row: RankingEntry) => row.key ?? ""}
+          disableColumnMenu
+          disableColumnSorting
+          autoPageSize
+          density="compact"
+        />
+      </Box>
+
+      <Typography variant="subtitle1">按类型</Typography>
+      <Box sx={{ height: 220 }}>
+        <DataGrid
+          rows={comparison?.types ?? []}
+          columns={comparisonColumns("类型")}
+          getRowId={(row: ComparisonLine) => row.key ?? ""}
+          loading={loading}
+          disableColumnMenu
+          hideFooter
+          density="compact"
+        />
+      </Box>
+
+      <Typography variant="subtitle1">按商品</Typography>
+      <Box sx={{ flex: 1, minHeight: 240 }}>
+        <DataGrid
+          rows={comparison?.products ?? []}
+          columns={comparisonColumns("商品")}
+          getRowId={(row: ComparisonLine) => row.key ?? ""}
+          loading={loading}
+          disableColumnMenu
+          autoPageSize
+          density="compact"
+        />
+      </Box>
+    </Box>
+  );
+}
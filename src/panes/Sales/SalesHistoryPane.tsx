@@ -1,9 +1,20 @@
-import { useState, useEffect } from "react";
+import { useState, useEffect, ChangeEvent } from "react";
 import { invoke } from "@tauri-apps/api/core";
+import { downloadDir, join } from "@tauri-apps/api/path";
+import { revealItemInDir } from "@tauri-apps/plugin-opener";
 import { DataGrid, GridColDef } from "@mui/x-data-grid";
 import { Button, Typography, Box } from "@mui/material";
-import type { SalesHeader, SalesSummary } from "../../types/sale";
+import type { Product } from "../../types/product";
+import type {
+  SalesHeader,
+  SalesHistoryCursor,
+  SalesHistoryFilter,
+  SalesHistoryPage,
+  SalesSummary,
+} from "../../types/sale";
 import EditSalesPane from "./EditSalesPane";
+import SalesReturnDialog from "./SalesReturnDialog";
+import SalePaymentsDialog from "./SalePaymentsDialog";
 
 interface SalesHistoryPaneProps {
   refreshSignal?: number;
@@ -18,13 +29,30 @@ export default function SalesHistoryPane({
   const [loading, setLoading] = useState(false);
   const [error, setError] = useState<string | null>(null);
   const [editingSale, setEditingSale] = useState<SalesHeader | null>(null);
+  const [returningSale, setReturningSale] = useState<SalesHeader | null>(null);
+  const [payingSale, setPayingSale] = useState<SalesSummary | null>(null);
+  const [filter, setFilter] = useState<SalesHistoryFilter>({
+    sort_by: "date",
+    sort_dir: "desc",
+  });
+  const [nextCursor, setNextCursor] = useState<SalesHistoryCursor | null>(null);
+  const [productNames, setProductNames] = useState<string[]>([]);
 
-  const fetchSalesHistory = async () => {
+  const setFilterField =
+    (key: keyof SalesHistoryFilter) =>
+    (e: ChangeEvent<HTMLInputElement | HTMLSelectElement>) =>
+      setFilter((f) => ({ ...f, [key]: e.target.value || undefined }));
+
+  // cursor = null loads the first page, otherwise appends the next one
+  const fetchSalesHistory = async (cursor: SalesHistoryCursor | null = null) => {
     setLoading(true);
     setError(null);
     try {
-      const result = await invoke<SalesSummary[]>("get_sales_history");
-      setSales(result);
+      const page = await invoke<SalesHistoryPage>("get_sales_history", {
+        filter: { ...filter, limit: 100, cursor },
+      });
+      setSales((prev) => (cursor ? [...prev, ...page.sales] : page.sales));
+      setNextCursor(page.next_cursor);
     } catch (err) {
       setError(
         err instanceof Error ? err.message : "未能获取销售记录"
@@ -40,6 +68,12 @@ export default function SalesHistoryPane({
     fetchSalesHistory();
   }, [refreshSignal]);
 
+  useEffect(() => {
+    invoke<Product[]>("get_all_products")
+      .then((list) => setProductNames(list.map((p) => p.name)))
+      .catch((err) => console.error("Error fetching products:", err));
+  }, []);
+
   const columns: GridColDef[] = [
     {
       field: "date",
@@ -48,6 +82,13 @@ export default function SalesHistoryPane({
       minWidth: 120,
       valueGetter: (_, row: SalesSummary) => row.header.date
     },
+    {
+      field: "customer",
+      headerName: "客户",
+      flex: 1,
+      minWidth: 100,
+      valueGetter: (_, row: SalesSummary) => row.header.customer || "-",
+    },
     {
       field: "top_products",
       headerName: "畅销商品",
@@ -62,6 +103,30 @@ export default function SalesHistoryPane({
       flex: 1,
       minWidth: 60,
     },
+    {
+      field: "total_pv",
+      headerName: "PV",
+      type: "number",
+      flex: 0.6,
+      minWidth: 60,
+    },
+    {
+      field: "total_bv",
+      headerName: "BV",
+      type: "number",
+      flex: 0.6,
+      minWidth: 60,
+    },
+    {
+      field: "payment_status",
+      headerName: "付款",
+      flex: 1,
+      minWidth: 80,
+      valueGetter: (_, row: SalesSummary) =>
+        ({ unpaid: "未付", partial: "部分付款", paid: "已付" } as const)[
+          row.header.payment_status ?? "paid"
+        ],
+    },
     {
       field: "note",
       headerName: "备注",
@@ -72,29 +137,79 @@ export default function SalesHistoryPane({
     {
       field: "actions",
       headerName: "操作",
-      width: 150,
+      width: 250,
       renderCell: (params) => (
-        <Button
-          size="small"
-          variant="outlined"
-          color="error"
-          onClick={(e) => {
-            e.stopPropagation();
-            if (confirm("确定要删除这条销售记录吗？")) {
-              handleDeleteSale(params.row.header.id);
-            }
-          }}
-          sx={{ minWidth: "auto", px: 1, fontSize: "0.75rem" }}
-        >
-          删除
-        </Button>
+        <Box sx={{ display: "flex", gap: 1, alignItems: "center", height: "100%" }}>
+          <Button
+            size="small"
+            variant="outlined"
+            onClick={(e) => {
+              e.stopPropagation();
+              handleExportReceipt(params.row.header);
+            }}
+            sx={{ minWidth: "auto", px: 1, fontSize: "0.75rem" }}
+          >
+            收据
+          </Button>
+          <Button
+            size="small"
+            variant="outlined"
+            onClick={(e) => {
+              e.stopPropagation();
+              setReturningSale(params.row.header);
+            }}
+            sx={{ minWidth: "auto", px: 1, fontSize: "0.75rem" }}
+          >
+            退货
+          </Button>
+          <Button
+            size="small"
+            variant="outlined"
+            onClick={(e) => {
+              e.stopPropagation();
+              setPayingSale(params.row);
+            }}
+            sx={{ minWidth: "auto", px: 1, fontSize: "0.75rem" }}
+          >
+            收款
+          </Button>
+          <Button
+            size="small"
+            variant="outlined"
+            color="error"
+            onClick={(e) => {
+              e.stopPropagation();
+              if (confirm("确定要删除这条销售记录吗？")) {
+                handleDeleteSale(params.row.header.id);
+              }
+            }}
+            sx={{ minWidth: "auto", px: 1, fontSize: "0.75rem" }}
+          >
+            删除
+          </Button>
+        </Box>
       ),
     },
   ];
 
+  const handleExportReceipt = async (header: SalesHeader) => {
+    try {
+      const path = await join(
+        await downloadDir(),
+        `收据_${header.date}_${header.id.slice(0, 8)}.pdf`
+      );
+      await invoke("export_sale_receipt", { saleId: header.id, path });
+      await revealItemInDir(path);
+    } catch (err) {
+      console.error("Error exporting receipt:", err);
+      alert("导出收据失败");
+    }
+  };
+
   const handleDeleteSale = async (saleId: string) => {
+    const restoreStock = confirm("是否将该销售的数量退回库存？\n确定：退回库存；取消：仅删除记录");
     try {
-      await invoke("delete_sale", { saleId });
+      await invoke("delete_sale", { saleId, restoreStock });
       fetchSalesHistory();
       onDidSubmit?.();
     } catch (err) {
@@ -107,7 +222,7 @@ export default function SalesHistoryPane({
     return (
       <Box sx={{ p: 3, textAlign: "center" }}>
         <Typography color="error">加载失败: {error}</Typography>
-        <button onClick={fetchSalesHistory}>重试</button>
+        <button onClick={() => fetchSalesHistory()}>重试</button>
       </Box>
     );
   }
@@ -133,6 +248,57 @@ export default function SalesHistoryPane({
             销售记录
           </Typography>
 
+          <Box
+            sx={{ display: "flex", gap: 1, alignItems: "center", flexWrap: "wrap", mb: 1 }}
+          >
+            <input
+              type="date"
+              value={filter.date_from ?? ""}
+              onChange={setFilterField("date_from")}
+            />
+            <span>至</span>
+            <input
+              type="date"
+              value={filter.date_to ?? ""}
+              onChange={setFilterField("date_to")}
+            />
+            <select
+              value={filter.product_name ?? ""}
+              onChange={setFilterField("product_name")}
+            >
+              <option value="">全部商品</option>
+              {productNames.map((name) => (
+                <option key={name} value={name}>
+                  {name}
+                </option>
+              ))}
+            </select>
+            <input
+              type="text"
+              placeholder="客户"
+              value={filter.customer ?? ""}
+              onChange={setFilterField("customer")}
+            />
+            <input
+              type="text"
+              placeholder="备注包含"
+              value={filter.note ?? ""}
+              onChange={setFilterField("note")}
+            />
+            <select value={filter.sort_by} onChange={setFilterField("sort_by")}>
+              <option value="date">按日期</option>
+              <option value="total_value">按金额</option>
+              <option value="customer">按客户</option>
+            </select>
+            <select value={filter.sort_dir} onChange={setFilterField("sort_dir")}>
+              <option value="desc">降序</option>
+              <option value="asc">升序</option>
+            </select>
+            <Button size="small" variant="contained" onClick={() => fetchSalesHistory()}>
+              查询
+            </Button>
+          </Box>
+
           <Box sx={{ flex: 1, minHeight: 0 }}>
             <DataGrid
               rows={sales}
@@ -140,6 +306,7 @@ export default function SalesHistoryPane({
               getRowId={(row: SalesSummary) => row.header.id}
               loading={loading}
               disableColumnMenu
+              disableColumnSorting
               autoPageSize
               onRowClick={(params) => setEditingSale(params.row.header)}
               sx={{
@@ -153,6 +320,35 @@ export default function SalesHistoryPane({
               }}
             />
           </Box>
+          {nextCursor && (
+            <Box sx={{ textAlign: "center", mt: 1 }}>
+              <Button
+                size="small"
+                disabled={loading}
+                onClick={() => fetchSalesHistory(nextCursor)}
+              >
+                加载更多
+              </Button>
+            </Box>
+          )}
+
+          <SalesReturnDialog
+            sale={returningSale}
+            onClose={() => setReturningSale(null)}
+            onSaved={() => {
+              fetchSalesHistory();
+              onDidSubmit?.();
+            }}
+          />
+          <SalePaymentsDialog
+            sale={payingSale?.header ?? null}
+            total={payingSale?.total_value ?? 0}
+            onClose={() => setPayingSale(null)}
+            onSaved={() => {
+              fetchSalesHistory();
+              onDidSubmit?.();
+            }}
+          />
         </>
       )}
     </Box>
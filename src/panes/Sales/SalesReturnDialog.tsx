@@ -0,0 +1,226 @@
+import { useEffect, useState } from "react";
+import { invoke } from "@tauri-apps/api/core";
+import { v4 as uuidv4 } from "uuid";
+import {
+  Box,
+  Button,
+  Dialog,
+  DialogActions,
+  DialogContent,
+  DialogTitle,
+  Typography,
+} from "@mui/material";
+import type { SalesHeader, SalesItem, SalesReturn } from "../../types/sale";
+
+interface SalesReturnDialogProps {
+  sale: SalesHeader | null;
+  onClose: () => void;
+  onSaved: () => void;
+}
+
+// Per sale line: quantity to return, written off or restocked, refund override
+interface ReturnInput {
+  quantity: number;
+  damaged: boolean;
+  refund: string; // empty = quantity * sale price
+}
+
+export default function SalesReturnDialog({ sale, onClose, onSaved }: SalesReturnDialogProps) {
+  const [items, setItems] = useState<SalesItem[]>([]);
+  const [returns, setReturns] = useState<SalesReturn[]>([]);
+  const [inputs, setInputs] = useState<Record<string, ReturnInput>>({});
+  const [date, setDate] = useState(() => new Date().toISOString().slice(0, 10));
+  const [note, setNote] = useState("");
+  const [saving, setSaving] = useState(false);
+
+  const load = async (saleId: string) => {
+    try {
+      const [saleItems, saleReturns] = await Promise.all([
+        invoke<SalesItem[]>("get_sales_items", { saleId }),
+        invoke<SalesReturn[]>("get_sales_returns", { saleId }),
+      ]);
+      setItems(saleItems);
+      setReturns(saleReturns);
+      setInputs({});
+    } catch (err) {
+      alert(`未能获取退货信息：${err}`);
+    }
+  };
+
+  useEffect(() => {
+    if (!sale) return;
+    setNote("");
+    setDate(new Date().toISOString().slice(0, 10));
+    load(sale.id);
+  }, [sale]);
+
+  // Quantity of a sale line already returned
+  const returnedOf = (itemId: string) =>
+    returns
+      .flatMap((r) => r.items)
+      .filter((i) => i.sale_item_id === itemId)
+      .reduce((sum, i) => sum + i.quantity, 0);
+
+  const inputOf = (itemId: string): ReturnInput =>
+    inputs[itemId] ?? { quantity: 0, damaged: false, refund: "" };
+
+  const setInput = (itemId: string, patch: Partial<ReturnInput>) =>
+    setInputs({ ...inputs, [itemId]: { ...inputOf(itemId), ...patch } });
+
+  const save = async () => {
+    if (!sale) return;
+    const lines = items.filter((i) => inputOf(i.id).quantity > 0);
+    if (lines.length === 0) return alert("请至少填写一条退货数量。");
+    for (const i of lines) {
+      if (inputOf(i.id).quantity > i.quantity - returnedOf(i.id)) {
+        return alert(`退货数量超出可退数量：${i.product_name}`);
+      }
+    }
+    setSaving(true);
+    try {
+      await invoke("create_sales_return", {
+        header: { id: uuidv4(), sale_id: sale.id, date, note: note.trim() || null },
+        items: lines.map((i) => {
+          const input = inputOf(i.id);
+          return {
+            id: uuidv4(),
+            sale_item_id: i.id,
+            quantity: input.quantity,
+            damaged: input.damaged,
+            refund: input.refund === "" ? null : parseInt(input.refund, 10) || 0,
+          };
+        }),
+      });
+      await load(sale.id);
+      setNote("");
+      onSaved();
+    } catch (err) {
+      alert(`退货失败：${err}`);
+    } finally {
+      setSaving(false);
+    }
+  };
+
+  const handleDelete = async (returnId: string) => {
+    if (!sale || !confirm("删除这条退货记录？已退回库存的数量将重新扣除。")) return;
+    try {
+      await invoke("delete_sales_return", { returnId });
+      await load(sale.id);
+      onSaved();
+    } catch (err) {
+      alert(`删除失败：${err}`);
+    }
+  };
+
+  return (
+    <Dialog open={!!sale} onClose={onClose} maxWidth="md" fullWidth>
+      <DialogTitle>
+        退货 - {sale?.customer || "散客"} - {sale?.date}
+      </DialogTitle>
+      <DialogContent>
+        <Box sx={{ display: "flex", flexDirection: "column", gap: 1.5, pt: 1 }}>
+          <table>
+            <thead>
+              <tr>
+                <th align="left">产品</th>
+                <th align="left">有效期</th>
+                <th align="right">已售</th>
+                <th align="right">已退</th>
+                <th align="right">本次退货</th>
+                <th align="center">报损</th>
+                <th align="right">退款</th>
+              </tr>
+            </thead>
+            <tbody>
+              {items.map((i) => {
+                const input = inputOf(i.id);
+                const returned = returnedOf(i.id);
+                return (
+                  <tr key={i.id}>
+                    <td>{i.product_name}</td>
+                    <td>{i.expiry}</td>
+                    <td align="right">{i.quantity}</td>
+                    <td align="right">{returned}</td>
+                    <td align="right">
+                      <input
+                        type="number"
+                        min={0}
+                        max={i.quantity - returned}
+                        style={{ width: 70 }}
+                        value={input.quantity}
+                        onChange={(e) =>
+                          setInput(i.id, { quantity: parseInt(e.target.value, 10) || 0 })
+                        }
+                      />
+                    </td>
+                    <td align="center">
+                      <input
+                        type="checkbox"
+                        checked={input.damaged}
+                        onChange={(e) => setInput(i.id, { damaged: e.target.checked })}
+                      />
+                    </td>
+                    <td align="right">
+                      <input
+                        type="number"
+                        min={0}
+                        placeholder="按售价"
+                        style={{ width: 90 }}
+                        value={input.refund}
+                        onChange={(e) => setInput(i.id, { refund: e.target.value })}
+                      />
+                    </td>
+                  </tr>
+                );
+              })}
+            </tbody>
+          </table>
+          <Box sx={{ display: "flex", gap: 2, alignItems: "center" }}>
+            <label>
+              日期 <input type="date" value={date} onChange={(e) => setDate(e.target.value)} />
+            </label>
+            <label>
+              备注 <input type="text" value={note} onChange={(e) => setNote(e.target.value)} />
+            </label>
+          </Box>
+          <Typography variant="body2" sx={{ opacity: 0.8 }}>
+            未勾选报损的数量退回原批次库存；退款留空时按销售单价计算。
+          </Typography>
+
+          {returns.length > 0 && (
+            <>
+              <Typography variant="subtitle1">已有退货</Typography>
+              <table>
+                <tbody>
+                  {returns.map((r) => (
+                    <tr key={r.header.id}>
+                      <td>{r.header.date}</td>
+                      <td>
+                        {r.items
+                          .map((i) => `${i.product_name}×${i.quantity}${i.damaged ? "（报损）" : ""}`)
+                          .join("，")}
+                      </td>
+                      <td align="right">退款 ¥{r.total_refund}</td>
+                      <td>{r.header.note || ""}</td>
+                      <td align="right">
+                        <Button size="small" color="error" onClick={() => handleDelete(r.header.id)}>
+                          删除
+                        </Button>
+                      </td>
+                    </tr>
+                  ))}
+                </tbody>
+              </table>
+            </>
+          )}
+        </Box>
+      </DialogContent>
+      <DialogActions>
+        <Button onClick={onClose}>关闭</Button>
+        <Button variant="contained" disabled={saving || !sale} onClick={save}>
+          提交退货
+        </Button>
+      </DialogActions>
+    </Dialog>
+  );
+}
@@ -2,8 +2,42 @@ import { useEffect, useState } from "react";
 import Plot from "react-plotly.js";
 import { invoke } from "@tauri-apps/api/core";
 import { CircularProgress } from "@mui/material";
+import type { Granularity, SalesBucket } from "../../types/sale";
 
-type MonthlySales = { month: string; total: number }; // month: "2025-01"
+const GRANULARITIES: { value: Granularity; label: string; title: string }[] = [
+  { value: "day", label: "日", title: "近30天每日" },
+  { value: "week", label: "周", title: "近26周每周" },
+  { value: "month", label: "月", title: "近一年每月" },
+  { value: "quarter", label: "季", title: "近两年每季度" },
+  { value: "year", label: "年", title: "近五年每年" },
+];
+
+// Start date ("YYYY-MM-DD") of the default range for each granularity
+function defaultFrom(g: Granularity): string {
+  const d = new Date();
+  switch (g) {
+    case "day":
+      d.setDate(d.getDate() - 29);
+      break;
+    case "week":
+      d.setDate(d.getDate() - 7 * 25);
+      break;
+    case "month":
+      d.setDate(1);
+      d.setMonth(d.getMonth() - 11);
+      break;
+    case "quarter":
+      d.setDate(1);
+      d.setMonth(d.getMonth() - d.getMonth() % 3 - 21);
+      break;
+    case "year":
+      d.setMonth(0, 1);
+      d.setFullYear(d.getFullYear() - 4);
+      break;
+  }
+  const pad = (n: number) => String(n).padStart(2, "0");
+  return `${d.getFullYear()}-${pad(d.getMonth() + 1)}-${pad(d.getDate())}`;
+}
 
 // Extend window type for Plotly
 declare global {
@@ -39,19 +73,26 @@ function cssVar(name: string, fallback: string) {
 }
 
 export default function SalesTrendPane({ refreshSignal }: { refreshSignal: number }) {
-  const [data, setData] = useState<MonthlySales[]>([]);
+  const [data, setData] = useState<SalesBucket[]>([]);
   const [loading, setLoading] = useState(true);
   const [plotKey, setPlotKey] = useState(0);
+  const [granularity, setGranularity] = useState<Granularity>("month");
+  const [metric, setMetric] = useState<"value" | "quantity">("value");
+  const [groupBy, setGroupBy] = useState<"" | "product" | "type">("");
   const isDark = usePrefersDark();
 
   useEffect(() => {
     if (refreshSignal === 0) return; // Skip initial
     setLoading(true);
-    invoke<MonthlySales[]>("get_monthly_sales", { months: 12 })
+    invoke<SalesBucket[]>("get_sales_series", {
+      dateFrom: defaultFrom(granularity),
+      granularity,
+      groupBy: groupBy || null,
+    })
       .then(setData)
       .catch(() => setData([]))
       .finally(() => setLoading(false));
-  }, [refreshSignal]);
+  }, [refreshSignal, granularity, groupBy]);
 
   // Force plot re-render when data changes to ensure proper sizing
   useEffect(() => {
@@ -63,16 +104,68 @@ export default function SalesTrendPane({ refreshSignal }: { refreshSignal: numbe
     }
   }, [loading, data.length]);
 
+  const controls = (
+    <div style={{ display: "flex", gap: 8, alignItems: "center", padding: "0 8px" }}>
+      {GRANULARITIES.map((g) => (
+        <button
+          key={g.value}
+          className={g.value === granularity ? "btn primary" : "btn"}
+          onClick={() => setGranularity(g.value)}
+        >
+          {g.label}
+        </button>
+      ))}
+      <select value={metric} onChange={(e) => setMetric(e.target.value as "value" | "quantity")}>
+        <option value="value">销售额</option>
+        <option value="quantity">销售数量</option>
+      </select>
+      <select
+        value={groupBy}
+        onChange={(e) => setGroupBy(e.target.value as "" | "product" | "type")}
+      >
+        <option value="">不分组</option>
+        <option value="product">按商品</option>
+        <option value="type">按类型</option>
+      </select>
+    </div>
+  );
+
   if (loading) {
     return <div style={{ padding: 32, textAlign: "center" }}><CircularProgress /></div>;
   }
-  if (!data.length) {
-    return <div style={{ padding: 32, opacity: 0.7 }}>暂无销售数据</div>;
+  if (!data.some((d) => d.value !== 0 || d.quantity !== 0)) {
+    return (
+      <div>
+        {controls}
+        <div style={{ padding: 32, opacity: 0.7 }}>暂无销售数据</div>
+      </div>
+    );
   }
 
-  const x = data.map(d => d.month); // e.g. "2025-01"
-  const y = data.map(d => d.total);
-  const maxY = Math.max(...y);
+  const x = data.map(d => d.period); // e.g. "2025-01", "2025-W11"
+  const y = data.map(d => d[metric]);
+  const maxY = Math.max(...y, 0);
+  const current = GRANULARITIES.find((g) => g.value === granularity)!;
+  const metricLabel = metric === "value" ? "销售额" : "销售数量";
+
+  // One trace per product / type when grouped (stacked), otherwise a single series
+  const traces = groupBy
+    ? (data[0]?.breakdown ?? []).map((b, i) => ({
+        type: "bar" as const,
+        name: b.key ?? "未分类",
+        x,
+        y: data.map((d) => d.breakdown[i]?.[metric] ?? 0),
+        hovertemplate: `%{x}<br>%{fullData.name}：%{y}<extra></extra>`,
+      }))
+    : [
+        {
+          type: "bar" as const,
+          x,
+          y,
+          marker: { color: cssVar("--accent", "#1976d2") },
+          hovertemplate: `%{x}<br>${metricLabel}：%{y}<extra></extra>`,
+        },
+      ];
 
   // Theme-aware colors from your CSS vars (with sensible fallbacks)
   const textColor = cssVar("--text", isDark ? "#e6e6e6" : "#222");
@@ -80,21 +173,13 @@ export default function SalesTrendPane({ refreshSignal }: { refreshSignal: numbe
     "--border",
     isDark ? "rgba(255,255,255,.25)" : "rgba(0,0,0,.2)"
   );
-  const barColor = cssVar("--accent", "#1976d2");
 
   return (
     <div style={{ width: "100%", height: "100%", minHeight: "400px" }}>
+      {controls}
       <Plot
         key={plotKey}
-        data={[
-          {
-            type: "bar",
-            x,
-            y,
-            marker: { color: barColor },
-            hovertemplate: "月份：%{x}<br>销售额：%{y}<extra></extra>",
-          }
-        ]}
+        data={traces}
         layout={
           {
             template: isDark ? "plotly_dark" : "plotly_white",
@@ -102,14 +187,13 @@ export default function SalesTrendPane({ refreshSignal }: { refreshSignal: numbe
             plot_bgcolor: "rgba(0,0,0,0)",
             font: { color: textColor },
             title: { 
-              text: "近一年每月销售额趋势",
+              text: `${current.title}${metricLabel}趋势`,
               font: { color: textColor }
             },
             dragmode: 'pan',
             xaxis: {
-              title: "月份",
+              title: "时间",
               type: "category",
-              tickformat: "%Y-%m",
               tickangle: -45,
               showgrid: true,
               gridcolor: borderColor,
@@ -122,7 +206,7 @@ export default function SalesTrendPane({ refreshSignal }: { refreshSignal: numbe
               fixedrange: false,
             },
             yaxis: {
-              title: "销售额",
+              title: metricLabel,
               rangemode: "tozero",
               showgrid: true,
               gridcolor: borderColor,
@@ -132,10 +216,12 @@ export default function SalesTrendPane({ refreshSignal }: { refreshSignal: numbe
               titlefont: { color: textColor },
               automargin: true,
               fixedrange: true,
-              range: [0, maxY * 1.1], // Fixed range with bottom at 0
+              range: groupBy ? undefined : [0, maxY * 1.1], // Fixed range with bottom at 0
             },
             margin: { t: 64, r: 32, b: 80, l: 80 },
             bargap: 0.3,
+            barmode: "stack",
+            showlegend: !!groupBy,
             autosize: true,
           } as Partial<Plotly.Layout>
         }
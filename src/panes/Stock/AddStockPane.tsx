@@ -56,11 +56,15 @@ export default function AddStockPane({
     if (items.some((r) => !isItemComplete(r))) {
       return alert("存在未填写完整的行（产品、数量、有效期均必填）。");
     }
+    if (items.some((r) => r.cost != null && r.cost < 0)) {
+      return alert("进货单价不能为负数。");
+    }
 
     const payload = items.map((r) => ({
       name: r.product,
       expiry_date: r.expiry!, // validated above
       qty: r.qty!,
+      cost: r.cost ?? null, // empty = the product's default cost
     }));
 
     try {
@@ -82,6 +86,7 @@ export default function AddStockPane({
         removeRow={removeRow}
         inputRefs={inputRefs}
         handleEnter={handleEnter}
+        showCost
       />
 
       <div className="footer-bar">
@@ -10,6 +10,7 @@ type Row = {
   product: string;
   expiry: string | null; // YYYY-MM-DD
   qty: number | null;
+  price?: number | null; // sale price per unit; empty = product price
   err?: string | null;
 };
 
@@ -27,6 +28,13 @@ export default function RemoveStockPane({
   onDidSubmit?: () => void;
 }) {
   const [markAsSale, setMarkAsSale] = useState(true);
+  const [saleDate, setSaleDate] = useState<string>(() =>
+    new Date().toISOString().slice(0, 10)
+  );
+  const [customer, setCustomer] = useState("");
+  const [note, setNote] = useState("");
+  const [paid, setPaid] = useState(true);
+  const [paymentMethod, setPaymentMethod] = useState("");
   const [products, setProducts] = useState<string[]>([]);
   const [rows, setRows] = useState<Row[]>([makeEmptyRow()]);
   const [lotsByProduct, setLotsByProduct] = useState<Record<string, StockLot[]>>({});
@@ -180,14 +188,31 @@ export default function RemoveStockPane({
       }
     }
 
+    if (markAsSale && !saleDate) {
+      alert("请选择销售日期。");
+      return;
+    }
+
     const payload = nonGhost.map((r) => ({
       name: r.product,
       expiry_date: r.expiry!,
       qty: r.qty!, // to remove
+      price: markAsSale ? r.price ?? null : null,
     }));
 
+    const sale = markAsSale
+      ? {
+          id: uuidv4(),
+          date: saleDate,
+          note: note.trim() || null,
+          customer: customer.trim() || null,
+          payment_method: paymentMethod.trim() || null,
+          payment_status: paid ? "paid" : "unpaid",
+        }
+      : null;
+
     try {
-      await invoke("remove_stock", { changes: payload, markAsSale: markAsSale });
+      await invoke("remove_stock", { changes: payload, markAsSale: markAsSale, sale });
       alert("移除成功！");
       // notify parent (e.g. to refresh viewStock)
       onDidSubmit?.();
@@ -196,6 +221,11 @@ export default function RemoveStockPane({
       await fetchInStockProducts();
       setLotsByProduct({});
       setRows([makeEmptyRow()]);
+      setSaleDate(new Date().toISOString().slice(0, 10));
+      setCustomer("");
+      setNote("");
+      setPaid(true);
+      setPaymentMethod("");
     } catch (e: any) {
       alert(e?.toString?.() ?? "移除失败");
     }
@@ -210,6 +240,7 @@ export default function RemoveStockPane({
               <th style={{ width: 380 }}>产品（仅在库）</th>
               <th style={{ width: 160 }}>有效期（必须在库）</th>
               <th style={{ width: 160 }}>数量（≤库存）</th>
+              {markAsSale && <th style={{ width: 120 }}>单价</th>}
               <th style={{ width: 80 }}>操作</th>
             </tr>
           </thead>
@@ -304,6 +335,25 @@ export default function RemoveStockPane({
                     )}
                   </td>
 
+                  {/* Sale price (optional, defaults to product price) */}
+                  {markAsSale && (
+                    <td>
+                      <input
+                        type="number"
+                        min={0}
+                        value={r.price ?? ""}
+                        placeholder="默认售价"
+                        onChange={(e) => {
+                          const n = e.target.value.trim() === "" ? null : Number(e.target.value);
+                          setRow(r.id, (row) => {
+                            row.price = n != null && Number.isFinite(n) && n >= 0 ? n : null;
+                            return row;
+                          });
+                        }}
+                      />
+                    </td>
+                  )}
+
                   {/* Actions */}
                   <td>
                     <button
@@ -331,6 +381,46 @@ export default function RemoveStockPane({
           />
           记为销售
         </label>
+        {markAsSale && (
+          <>
+            <label>日期</label>
+            <input
+              type="date"
+              style={{ width: 160 }}
+              value={saleDate}
+              onChange={(e) => setSaleDate(e.target.value)}
+            />
+            <label style={{ whiteSpace: "nowrap" }}>客户</label>
+            <input
+              type="text"
+              value={customer}
+              onChange={(e) => setCustomer(e.target.value)}
+              placeholder="可选"
+            />
+            <label style={{ whiteSpace: "nowrap" }}>备注</label>
+            <input
+              type="text"
+              value={note}
+              onChange={(e) => setNote(e.target.value)}
+              placeholder="可选备注"
+            />
+            <label style={{ whiteSpace: "nowrap" }}>付款方式</label>
+            <input
+              type="text"
+              value={paymentMethod}
+              onChange={(e) => setPaymentMethod(e.target.value)}
+              placeholder="如 现金、转账"
+            />
+            <label style={{ whiteSpace: "nowrap" }}>
+              <input
+                type="checkbox"
+                checked={paid}
+                onChange={(e) => setPaid(e.target.checked)}
+              />
+              已付清
+            </label>
+          </>
+        )}
       </div>
     </div>
   );
@@ -0,0 +1,112 @@
+import { useEffect, useState } from "react";
+import { invoke } from "@tauri-apps/api/core";
+import { DataGrid, GridColDef } from "@mui/x-data-grid";
+import { Box, Typography } from "@mui/material";
+
+type LotExpiryRisk = {
+  name: string;
+  type?: string | null;
+  expiry: string;
+  quantity: number;
+  days_left: number; // negative = already expired
+  daily_rate: number;
+  expected_sold: number;
+  expected_unsold: number;
+  unit_price: number;
+  unsold_value: number;
+};
+
+const columns: GridColDef[] = [
+  { field: "name", headerName: "产品", flex: 1.5, minWidth: 140 },
+  {
+    field: "type",
+    headerName: "类型",
+    flex: 1,
+    valueGetter: (_, row: LotExpiryRisk) => row.type ?? "未分类",
+  },
+  { field: "expiry", headerName: "到期日", flex: 1, minWidth: 110 },
+  {
+    field: "days_left",
+    headerName: "剩余天数",
+    type: "number",
+    flex: 1,
+    valueGetter: (_, row: LotExpiryRisk) => (row.days_left < 0 ? "已过期" : row.days_left),
+  },
+  { field: "quantity", headerName: "库存", type: "number", flex: 1 },
+  { field: "daily_rate", headerName: "日均销量", type: "number", flex: 1 },
+  { field: "expected_sold", headerName: "预计售出", type: "number", flex: 1 },
+  { field: "expected_unsold", headerName: "预计过期", type: "number", flex: 1 },
+  { field: "unsold_value", headerName: "预计损失", type: "number", flex: 1 },
+];
+
+export default function ExpiryRiskPane({ refreshSignal }: { refreshSignal: number }) {
+  const [lookbackDays, setLookbackDays] = useState(90);
+  const [onlyAtRisk, setOnlyAtRisk] = useState(true);
+  const [rows, setRows] = useState<LotExpiryRisk[]>([]);
+  const [loading, setLoading] = useState(false);
+  const [error, setError] = useState<string | null>(null);
+
+  useEffect(() => {
+    if (refreshSignal === 0) return; // Skip initial
+    setLoading(true);
+    setError(null);
+    invoke<LotExpiryRisk[]>("get_expiry_risk", { lookbackDays })
+      .then(setRows)
+      .catch((err) => {
+        setError(typeof err === "string" ? err : "未能获取临期风险");
+        setRows([]);
+      })
+      .finally(() => setLoading(false));
+  }, [refreshSignal, lookbackDays]);
+
+  const shown = onlyAtRisk ? rows.filter((r) => r.expected_unsold > 0) : rows;
+  const totalLoss = shown.reduce((s, r) => s + r.unsold_value, 0);
+
+  return (
+    <Box sx={{ p: 2, height: "100%", display: "flex", flexDirection: "column", gap: 1 }}>
+      <Typography variant="h5">临期风险</Typography>
+
+      <Box sx={{ display: "flex", gap: 2, alignItems: "center", flexWrap: "wrap" }}>
+        <label>
+          按近
+          <select
+            style={{ margin: "0 4px" }}
+            value={lookbackDays}
+            onChange={(e) => setLookbackDays(Number(e.target.value))}
+          >
+            <option value={30}>30</option>
+            <option value={90}>90</option>
+            <option value={180}>180</option>
+            <option value={365}>365</option>
+          </select>
+          天的销量估算
+        </label>
+        <label>
+          <input
+            type="checkbox"
+            checked={onlyAtRisk}
+            onChange={(e) => setOnlyAtRisk(e.target.checked)}
+          />
+          仅显示预计过期的批次
+        </label>
+        <Typography variant="body2" sx={{ opacity: 0.8 }}>
+          预计过期损失合计：{totalLoss}
+        </Typography>
+      </Box>
+
+      {error && <Typography color="error">{error}</Typography>}
+
+      <Box sx={{ flex: 1, minHeight: 0 }}>
+        <DataGrid
+          rows={shown}
+          columns={columns}
+          getRowId={(row: LotExpiryRisk) => `${row.name}|${row.expiry}`}
+          loading={loading}
+          disableColumnMenu
+          autoPageSize
+          density="compact"
+        />
+      </Box>
+    </Box>
+  );
+}
@@ -2,12 +2,24 @@ import ProductManagementPane from "./panes/ProductManagement/productManagementPa
 import AddStockPane from "./panes/Stock/AddStockPane";
 import RemoveStockPane from "./panes/Stock/RemoveStockPane";
 import ViewStockPane from "./panes/Summary/ViewStock";
+import ExpiryRiskPane from "./panes/Summary/ExpiryRiskPane";
 import AddLoanPane from "./panes/Loan/AddLoanPane";
 import DashboardPane from "./panes/Dashboard/DashboardPane";
 import SalesHistoryPane from "./panes/Sales/SalesHistoryPane";
 import SalesTrendPane from "./panes/Sales/SalesTrendPane";
+import SalesAnalysisPane from "./panes/Sales/SalesAnalysisPane";
+import DemandForecastPane from "./panes/Sales/DemandForecastPane";
+import ProfitReportPane from "./panes/Sales/ProfitReportPane";
+import RefillReminderPane from "./panes/Sales/RefillReminderPane";
+import RecurringSalesPane from "./panes/Sales/RecurringSalesPane";
+import ReceivablesPane from "./panes/Sales/ReceivablesPane";
 import LoanHistoryPane from "./panes/Loan/LoanHistoryPane";
 import LoanSummaryPane from "./panes/Loan/LoanSummaryPane";
+import LoanDuePane from "./panes/Loan/LoanDuePane";
+import LoanStatementPane from "./panes/Loan/LoanStatementPane";
+import LoanAgingPane from "./panes/Loan/LoanAgingPane";
+import ExchangePane from "./panes/Loan/ExchangePane";
+import CounterpartyPane from "./panes/Loan/CounterpartyPane";
 
 import { useState } from "react";
 
@@ -19,13 +31,25 @@ export type TabKey =
   | "boot"
   | "dashboard"
   | "viewStock"
+  | "expiryRisk"
   | "addStock"
   | "removeStock"
   | "salesTrend"
+  | "salesAnalysis"
+  | "demandForecast"
+  | "profitReport"
+  | "refillReminder"
+  | "recurringSales"
+  | "receivables"
   | "salesHistory"
   | "loanSummary"
+  | "loanDue"
+  | "loanStatement"
+  | "loanAging"
   | "loanHistory"
   | "addLoan"
+  | "exchanges"
+  | "counterparties"
   | "productManagement";
 
 export const sidebarStructure: SidebarItem[] = [
@@ -37,6 +61,10 @@ export const sidebarStructure: SidebarItem[] = [
     key: "viewStock" as TabKey,
     label: "查看库存",
   },
+  {
+    key: "expiryRisk" as TabKey,
+    label: "临期风险",
+  },
   {
     key: null,
     label: "库存增减",
@@ -50,6 +78,12 @@ export const sidebarStructure: SidebarItem[] = [
     label: "销售管理",
     children: [
       { key: "salesTrend" as TabKey, label: "销售趋势" },
+      { key: "salesAnalysis" as TabKey, label: "销售分析" },
+      { key: "demandForecast" as TabKey, label: "需求预测" },
+      { key: "profitReport" as TabKey, label: "利润报表" },
+      { key: "refillReminder" as TabKey, label: "续购提醒" },
+      { key: "recurringSales" as TabKey, label: "定期订单" },
+      { key: "receivables" as TabKey, label: "应收账款" },
       { key: "salesHistory" as TabKey, label: "销售记录" },
     ],
   },
@@ -61,6 +95,18 @@ export const sidebarStructure: SidebarItem[] = [
         key: "loanSummary" as TabKey,
         label: "借货总览",
       },
+      {
+        key: "loanDue" as TabKey,
+        label: "借货到期",
+      },
+      {
+        key: "loanAging" as TabKey,
+        label: "借货账龄",
+      },
+      {
+        key: "loanStatement" as TabKey,
+        label: "借货对账单",
+      },
       {
         key: "loanHistory" as TabKey,
         label: "借货记录",
@@ -69,6 +115,14 @@ export const sidebarStructure: SidebarItem[] = [
         key: "addLoan" as TabKey,
         label: "新增借货/归还",
       },
+      {
+        key: "exchanges" as TabKey,
+        label: "换货",
+      },
+      {
+        key: "counterparties" as TabKey,
+        label: "往来单位",
+      },
     ],
   },
   {
@@ -79,14 +133,26 @@ export const sidebarStructure: SidebarItem[] = [
 
 export const defaultRefreshCounters = {
   viewStock: 0,
+  expiryRisk: 0,
   dashboard: 0,
   addStock: 0,
   removeStock: 0,
   salesHistory: 0,
+  receivables: 0,
   salesTrend: 0,
+  salesAnalysis: 0,
+  demandForecast: 0,
+  profitReport: 0,
+  refillReminder: 0,
+  recurringSales: 0,
   loanSummary: 0,
+  loanDue: 0,
+  loanStatement: 0,
+  loanAging: 0,
   loanHistory: 0,
   addLoan: 0,
+  exchanges: 0,
+  counterparties: 0,
   productManagement: 0,
 };
 
@@ -132,13 +198,25 @@ export function RenderedTabs({
           onRefresh={() =>
             triggerRefresh(
               "viewStock",
+              "expiryRisk",
               "addStock",
               "removeStock",
               "salesTrend",
+              "salesAnalysis",
+              "demandForecast",
+              "profitReport",
+              "refillReminder",
+              "recurringSales",
               "salesHistory",
+              "receivables",
               "loanSummary",
+              "loanDue",
+              "loanAging",
+              "loanStatement",
               "loanHistory",
               "addLoan",
+              "exchanges",
+              "counterparties",
               "productManagement"
             )
           }
@@ -155,6 +233,16 @@ export function RenderedTabs({
         <ViewStockPane refreshSignal={refresh.viewStock} />
       </div>
 
+      {/* expiry risk */}
+      <div
+        style={{
+          display: activeTab === "expiryRisk" ? "block" : "none",
+          height: "100%",
+        }}
+      >
+        <ExpiryRiskPane refreshSignal={refresh.expiryRisk} />
+      </div>
+
       {/* add stock */}
       <div
         style={{
@@ -165,7 +253,7 @@ export function RenderedTabs({
         <AddStockPane
           refreshSignal={refresh.addStock}
           onDidSubmit={() => {
-            triggerRefresh("viewStock", "dashboard"); // adding stock affects summary
+            triggerRefresh("viewStock", "expiryRisk", "dashboard"); // adding stock affects summary
           }}
         />
       </div>
@@ -182,9 +270,16 @@ export function RenderedTabs({
           onDidSubmit={() => {
             triggerRefresh(
               "viewStock",
+              "expiryRisk",
               "dashboard",
               "salesTrend",
-              "salesHistory"
+              "salesAnalysis",
+              "demandForecast",
+              "profitReport",
+              "refillReminder",
+              "recurringSales",
+              "salesHistory",
+              "receivables"
             );
           }}
         />
@@ -200,6 +295,83 @@ export function RenderedTabs({
         <SalesTrendPane refreshSignal={refresh.salesTrend} />
       </div>
 
+      {/* sales analysis */}
+      <div
+        style={{
+          display: activeTab === "salesAnalysis" ? "block" : "none",
+          height: "100%",
+        }}
+      >
+        <SalesAnalysisPane refreshSignal={refresh.salesAnalysis} />
+      </div>
+
+      {/* demand forecast */}
+      <div
+        style={{
+          display: activeTab === "demandForecast" ? "block" : "none",
+          height: "100%",
+        }}
+      >
+        <DemandForecastPane refreshSignal={refresh.demandForecast} />
+      </div>
+
+      {/* profit report */}
+      <div
+        style={{
+          display: activeTab === "profitReport" ? "block" : "none",
+          height: "100%",
+        }}
+      >
+        <ProfitReportPane refreshSignal={refresh.profitReport} />
+      </div>
+
+      {/* refill reminders */}
+      <div
+        style={{
+          display: activeTab === "refillReminder" ? "block" : "none",
+          height: "100%",
+        }}
+      >
+        <RefillReminderPane refreshSignal={refresh.refillReminder} />
+      </div>
+
+      {/* recurring sales */}
+      <div
+        style={{
+          display: activeTab === "recurringSales" ? "block" : "none",
+          height: "100%",
+        }}
+      >
+        <RecurringSalesPane
+          refreshSignal={refresh.recurringSales}
+          onDidSubmit={() => {
+            triggerRefresh(
+              "viewStock",
+              "expiryRisk",
+              "removeStock",
+              "dashboard",
+              "salesTrend",
+              "salesAnalysis",
+              "demandForecast",
+              "profitReport",
+              "refillReminder",
+              "salesHistory",
+              "receivables"
+            ); // a generated sale takes stock like remove_stock
+          }}
+        />
+      </div>
+
+      {/* receivables */}
+      <div
+        style={{
+          display: activeTab === "receivables" ? "block" : "none",
+          height: "100%",
+        }}
+      >
+        <ReceivablesPane refreshSignal={refresh.receivables} />
+      </div>
+
       {/* sales history */}
       <div
         style={{
@@ -210,7 +382,17 @@ export function RenderedTabs({
         <SalesHistoryPane
           refreshSignal={refresh.salesHistory}
           onDidSubmit={() => {
-            triggerRefresh("dashboard", "salesTrend"); // sales history may impact dashboard?
+            triggerRefresh(
+              "dashboard",
+              "salesTrend",
+              "salesAnalysis",
+              "demandForecast",
+              "profitReport",
+              "refillReminder",
+              "recurringSales",
+              "receivables",
+              "expiryRisk"
+            ); // sales history may impact dashboard?
           }}
         />
       </div>
@@ -228,9 +410,57 @@ export function RenderedTabs({
             setEditingLoanId(loanId);
             setActiveTab("loanHistory"); // Switch to loan history tab
           }}
+          onDidSettle={() => {
+            triggerRefresh(
+              "viewStock",
+              "expiryRisk",
+              "dashboard",
+              "salesTrend",
+              "salesAnalysis",
+              "demandForecast",
+              "profitReport",
+              "refillReminder",
+              "salesHistory",
+              "receivables",
+              "loanDue",
+              "loanAging",
+              "loanStatement",
+              "loanHistory"
+            ); // settling records a sale or a stock receipt
+          }}
         />
       </div>
 
+      {/* loan due dates */}
+      <div
+        style={{
+          display: activeTab === "loanDue" ? "block" : "none",
+          height: "100%",
+        }}
+      >
+        <LoanDuePane refreshSignal={refresh.loanDue} />
+      </div>
+
+      {/* loan aging */}
+      <div
+        style={{
+          display: activeTab === "loanAging" ? "block" : "none",
+          height: "100%",
+        }}
+      >
+        <LoanAgingPane refreshSignal={refresh.loanAging} />
+      </div>
+
+      {/* loan statement */}
+      <div
+        style={{
+          display: activeTab === "loanStatement" ? "block" : "none",
+          height: "100%",
+        }}
+      >
+        <LoanStatementPane refreshSignal={refresh.loanStatement} />
+      </div>
+
       {/* loan history */}
       <div
         style={{
@@ -242,7 +472,17 @@ export function RenderedTabs({
           refreshSignal={refresh.loanHistory}
           editingLoanId={editingLoanId}
           onDidSubmit={() => {
-            triggerRefresh("loanSummary", "dashboard");
+            triggerRefresh(
+              "viewStock",
+              "expiryRisk",
+              "removeStock",
+              "loanSummary",
+              "loanDue",
+              "loanAging",
+              "loanStatement",
+              "counterparties",
+              "dashboard"
+            ); // edits and deletes move stock
           }}
           onCloseEdit={() => setEditingLoanId(null)}
         />
@@ -260,15 +500,66 @@ export function RenderedTabs({
           onDidSubmit={() => {
             triggerRefresh(
               "viewStock",
+              "expiryRisk",
               "removeStock",
               "loanSummary",
+              "loanDue",
+              "loanAging",
+              "loanStatement",
               "loanHistory",
+              "counterparties",
               "dashboard"
             ); // loans adjust stock buckets
           }}
         />
       </div>
 
+      {/* exchanges */}
+      <div
+        style={{
+          display: activeTab === "exchanges" ? "block" : "none",
+          height: "100%",
+        }}
+      >
+        <ExchangePane
+          refreshSignal={refresh.exchanges}
+          onDidSubmit={() => {
+            triggerRefresh(
+              "viewStock",
+              "expiryRisk",
+              "removeStock",
+              "loanSummary",
+              "counterparties",
+              "dashboard"
+            ); // swaps move stock both ways
+          }}
+        />
+      </div>
+
+      {/* counterparties */}
+      <div
+        style={{
+          display: activeTab === "counterparties" ? "block" : "none",
+          height: "100%",
+        }}
+      >
+        <CounterpartyPane
+          refreshSignal={refresh.counterparties}
+          onDidSubmit={() => {
+            triggerRefresh(
+              "loanSummary",
+              "loanDue",
+              "loanAging",
+              "loanStatement",
+              "loanHistory",
+              "addLoan",
+              "exchanges",
+              "dashboard"
+            ); // renames and merges regroup balances
+          }}
+        />
+      </div>
+
       {/* product management */}
       <div
         style={{
@@ -281,13 +572,25 @@ export function RenderedTabs({
           onDidMutateProduct={() => {
             triggerRefresh(
               "viewStock",
+              "expiryRisk",
               "addStock",
               "removeStock",
               "salesTrend",
+              "salesAnalysis",
+              "demandForecast",
+              "profitReport",
+              "refillReminder",
+              "recurringSales",
               "salesHistory",
+              "receivables",
               "loanSummary",
+              "loanDue",
+              "loanAging",
+              "loanStatement",
               "loanHistory",
               "addLoan",
+              "exchanges",
+              "counterparties",
               "dashboard"
             ); // renames/types impact summary display
           }}
@@ -1 +1,7 @@
-export type Config = { url: string; token: string; alert_period: number };
\ No newline at end of file
+export type Config = {
+  url: string;
+  token: string;
+  alert_period: number;
+  business_name: string;
+  receipt_footer: string;
+};
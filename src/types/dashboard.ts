@@ -7,6 +7,23 @@ export interface DashboardValueData {
     expiredValue?: number;
     /** 4) Net value of borrowed/lent products (positive = net asset, negative = net liability) */
     netLoanValue?: number;
+    /** 5) Open loan balances past their due date */
+    overdueLoanCount?: number;
+    /** 6) Value of our stock held by counterparties, and of theirs held by us (positive) */
+    lentOutValue?: number;
+    borrowedInValue?: number;
+    /** 7) Price the loan values use: current member price or the price on the loan date */
+    loanValuation?: LoanValuation;
+    /** 8) The same split per counterparty, largest first */
+    counterpartyLoanValues?: CounterpartyLoanValue[];
+}
+
+export type LoanValuation = "current" | "recorded"; // recorded = price when the line was entered
+
+export interface CounterpartyLoanValue {
+    counterparty: string;
+    lentOutValue: number;
+    borrowedInValue: number;
 }
 
 export interface DashboardSalesData {
@@ -14,4 +31,10 @@ export interface DashboardSalesData {
     this_month_total: number;
     /** 2) Total monetary value sold for the same period last month */
     last_month_same_period_total: number
+    /** 3) USANA points (PV / BV) sold this month, net of returns */
+    this_month_pv: number;
+    this_month_bv: number;
+    /** 4) USANA points sold in the same period last month */
+    last_month_same_period_pv: number;
+    last_month_same_period_bv: number;
 }
\ No newline at end of file
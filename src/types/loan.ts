@@ -6,11 +6,142 @@ export interface LoanHeader {
     direction: Direction;
     counterparty: string;
     note: string | null;
+    due_date?: string | null; // expected return date
+}
+
+export interface LoanHistoryRow {
+    kind: "loan" | "exchange"; // exchanges carry direction "exchange"
+    header: Omit<LoanHeader, "direction"> & { direction: Direction | "exchange" };
+    item_count: number;
+    total_quantity: number;
+    total_value: number; // at the price recorded with each line; exchanges: their balance
+}
+
+export interface LoanHistoryCursor {
+    date: string;
+    id: string;
+}
+
+export interface LoanHistoryFilter {
+    loan_id?: string;
+    counterparty?: string;
+    direction?: Direction | "exchange";
+    product_name?: string;
+    date_from?: string;
+    date_to?: string;
+    status?: "open" | "closed";
+    limit?: number;
+    cursor?: LoanHistoryCursor | null;
+}
+
+export interface LoanHistoryPage {
+    loans: LoanHistoryRow[];
+    next_cursor: LoanHistoryCursor | null; // null = last page
 }
 
 export interface LoanItem {
     id: string;
     product_name: string;
     quantity: number;
-    expiry?: string; // Optional because it's only required when adjustStock is true
-}
\ No newline at end of file
+    expiry?: string | null; // lot; returns and edits may leave it to the backend
+}
+
+export interface Counterparty {
+    id: string;
+    name: string;
+    phone: string | null;
+    wechat: string | null;
+    address: string | null;
+    note: string | null;
+    loan_count?: number; // filled by get_counterparties
+}
+
+export interface StatementLine {
+    loan_id: string;
+    date: string;
+    direction: Direction;
+    product_name: string;
+    quantity: number; // > 0 adds to what they owe us
+    unit_price: number; // current product price
+    value: number;
+    balance_quantity: number; // running balance of the product after this line
+    balance_value: number;
+    note: string | null;
+}
+
+export interface StatementProduct {
+    product_name: string;
+    opening_quantity: number;
+    opening_value: number;
+    closing_quantity: number;
+    closing_value: number;
+}
+
+export interface StatementExchange {
+    exchange_id: string;
+    date: string;
+    description: string;
+    value: number; // > 0 adds to what they owe us
+    balance: number;
+    note: string | null;
+}
+
+export interface LoanStatement {
+    counterparty: string;
+    from: string | null;
+    to: string | null;
+    products: StatementProduct[];
+    lines: StatementLine[];
+    opening_value: number;
+    closing_value: number;
+    exchanges: StatementExchange[];
+    exchange_opening_value: number;
+    exchange_closing_value: number;
+}
+
+export interface LoanAging {
+    loanId: string;
+    itemId: string;
+    date: string;
+    dueDate: string | null;
+    counterparty: string;
+    direction: "loan_in" | "loan_out";
+    productName: string;
+    productType: string | null;
+    quantity: number;
+    returned: number;
+    outstanding: number;
+    daysOpen: number;
+    daysLeft: number | null; // until the due date; negative = overdue
+}
+
+export interface MatchCandidate {
+    loan_item_id: string;
+    loan_id: string;
+    date: string;
+    expiry: string | null;
+    quantity: number;
+    available: number; // not settled by other returns
+    matched: number; // settled by this return line
+}
+
+export interface ExchangeItem {
+    id: string;
+    side: "give" | "receive";
+    product_name: string;
+    expiry: string;
+    quantity: number;
+    unit_value: number | null; // agreed value per unit; null = product price
+}
+
+export interface Exchange {
+    header: {
+        id: string;
+        date: string;
+        counterparty: string;
+        note: string | null;
+        cash: number; // money they paid us (> 0) or we paid them (< 0)
+    };
+    items: ExchangeItem[];
+    balance: number; // value given minus value received and cash; > 0 they owe us
+}
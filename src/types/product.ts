@@ -3,4 +3,15 @@ export type Product = {
     price: number | null;
     picture: string | null;
     type: string | null;
+    cost?: number | null; // default purchase cost per unit
+    pv?: number | null; // USANA point value per unit, currently in effect
+    bv?: number | null; // USANA business value per unit, currently in effect
+    consumption_days?: number | null; // days one unit lasts a customer
+};
+
+// One entry of a product's PV/BV history; applies until the next entry
+export type ProductPoints = {
+    effective_date: string; // "YYYY-MM-DD"
+    pv: number | null;
+    bv: number | null;
 };
\ No newline at end of file
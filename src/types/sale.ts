@@ -2,17 +2,234 @@ export interface SalesHeader {
     id: string;
     date: string;
     note?: string;
+    customer?: string | null;
+    payment_method?: string | null;
+    amount_paid?: number;
+    payment_status?: PaymentStatus; // on create; omitted = "paid", a full payment
 }
 
+export type PaymentStatus = "unpaid" | "partial" | "paid";
+
 export interface SalesItem {
     id: string;
     product_name: string;
     quantity: number;
     expiry: string;
+    price?: number | null; // unit price at time of sale; null = product price
 }
 
 export interface SalesSummary {
     header: SalesHeader;
     top_products: string[];
     total_value: number;
-}
\ No newline at end of file
+    total_pv: number; // USANA points of the items sold
+    total_bv: number;
+}
+
+export interface SalesHistoryCursor {
+    key: string;
+    id: string;
+}
+
+export interface SalesHistoryFilter {
+    date_from?: string;
+    date_to?: string;
+    product_name?: string;
+    customer?: string;    // substring match
+    note?: string;        // substring match
+    sort_by?: "date" | "total_value" | "customer";
+    sort_dir?: "asc" | "desc";
+    limit?: number;
+    cursor?: SalesHistoryCursor | null;
+}
+
+export interface SalesHistoryPage {
+    sales: SalesSummary[];
+    next_cursor: SalesHistoryCursor | null; // null = last page
+}
+
+export interface SalesPayment {
+    id: string;
+    sale_id: string;
+    date: string;
+    amount: number;
+    method?: string | null;
+    note?: string | null;
+}
+
+export interface Receivable {
+    customer: string | null;
+    sales_count: number;
+    total_outstanding: number;
+    days_0_30: number;
+    days_31_60: number;
+    days_61_90: number;
+    days_over_90: number;
+    oldest_date: string;
+}
+
+export interface SalesReturnHeader {
+    id: string;
+    sale_id: string;
+    date: string;
+    note?: string | null;
+}
+
+export interface SalesReturnItem {
+    id: string;
+    sale_item_id: string;
+    product_name?: string; // filled from the sale line
+    expiry?: string;       // filled from the sale line
+    quantity: number;
+    damaged?: boolean;     // true = written off instead of restocked
+    refund?: number | null; // null = quantity * sale price
+}
+
+export interface SalesReturn {
+    header: SalesReturnHeader;
+    items: SalesReturnItem[];
+    total_refund: number;
+}
+
+export type Granularity = "day" | "week" | "month" | "quarter" | "year";
+
+export interface SalesBreakdown {
+    key: string | null; // product name or product type; null = no type set
+    value: number;
+    quantity: number;
+}
+
+export interface SalesBucket {
+    period: string; // "2025-03-14" | "2025-W11" | "2025-03" | "2025-Q1" | "2025"
+    start: string;
+    end: string;
+    value: number;
+    quantity: number;
+    breakdown: SalesBreakdown[];
+}
+
+export type ComparisonPeriod = "week" | "month" | "quarter" | "ytd" | "custom";
+
+export interface PeriodRange {
+    from: string;
+    to: string;
+}
+
+export interface SalesFigures {
+    value: number;
+    quantity: number;
+}
+
+export interface SalesChange {
+    value: number;
+    quantity: number;
+    value_pct: number | null; // null when the base period is 0
+    quantity_pct: number | null;
+}
+
+export interface ComparisonLine {
+    key: string | null; // product name or type; null = total or no type set
+    current: SalesFigures;
+    previous: SalesFigures;
+    last_year: SalesFigures;
+    vs_previous: SalesChange;
+    vs_last_year: SalesChange;
+}
+
+export interface SalesComparison {
+    current: PeriodRange;
+    previous: PeriodRange;
+    last_year: PeriodRange;
+    total: ComparisonLine;
+    products: ComparisonLine[];
+    types: ComparisonLine[];
+}
+
+export type RankingMetric = "value" | "quantity" | "sales_count";
+
+export interface RankingEntry {
+    rank: number;
+    key: string | null; // product name or type; null = no type set
+    value: number;
+    quantity: number;
+    sales_count: number;
+    value_share: number;    // % of total value
+    quantity_share: number; // % of total quantity
+    previous: SalesFigures;
+    previous_rank: number | null; // null = not sold in the previous period
+    vs_previous: SalesChange;
+}
+
+export interface SalesRankings {
+    current: PeriodRange;
+    previous: PeriodRange;
+    total: SalesFigures;
+    total_sales_count: number;
+    entries: RankingEntry[];
+}
+
+export type ForecastMethod = "exponential_smoothing" | "moving_average";
+
+export interface ForecastPoint {
+    period: string;
+    start: string;
+    end: string;
+    quantity: number; // point forecast
+    lower: number;    // 95% range
+    upper: number;
+}
+
+export interface ProductForecast {
+    product_name: string;
+    method: ForecastMethod | "holt_winters"; // model actually used
+    history: { period: string; start: string; quantity: number }[];
+    forecast: ForecastPoint[];
+}
+
+export type ProfitGroup = "sale" | "product" | "month" | "customer";
+
+export interface ProfitLine {
+    key: string | null; // sale id, product, "YYYY-MM" or customer
+    date: string | null;     // sale date, only when grouped by sale
+    customer: string | null; // only when grouped by sale
+    quantity: number;
+    revenue: number;
+    cost: number;
+    gross_profit: number;
+    margin: number | null; // %, null when revenue is 0
+    uncosted_quantity: number; // units without a known cost
+}
+
+export interface ProfitReport {
+    lines: ProfitLine[];
+    total: ProfitLine;
+}
+
+export type IntervalUnit = "day" | "week" | "month";
+
+export interface RecurringSaleHeader {
+    id: string;
+    customer: string;
+    interval_unit: IntervalUnit;
+    interval_count: number;
+    next_date: string; // "YYYY-MM-DD", next order due
+    payment_method: string | null;
+    payment_status?: "paid" | "unpaid" | null; // of generated sales; null = unpaid
+    note: string | null;
+    active: boolean; // false = paused
+    last_sale_id?: string | null; // maintained by the backend
+}
+
+export interface RecurringSaleItem {
+    id: string;
+    product_name: string;
+    quantity: number;
+    price: number | null; // null = list price when the sale is generated
+    available?: number; // unexpired units in stock, read only
+}
+
+export interface RecurringSale {
+    header: RecurringSaleHeader;
+    items: RecurringSaleItem[];
+    days_until_due: number; // negative = overdue
+}